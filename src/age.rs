@@ -0,0 +1,72 @@
+use anyhow::{bail, Result};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// How connection ages are written, from `age_format` or --age-format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AgeFormat {
+    // "42s", "3m12s", "1h3m", "2d4h"
+    #[default]
+    Compact,
+    // "01:03:12"; hours keep growing past a day
+    Clock,
+    // Local time first seen, "2024-01-02 15:04:05"
+    Absolute,
+}
+
+impl AgeFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "compact" => Ok(AgeFormat::Compact),
+            "clock" => Ok(AgeFormat::Clock),
+            "absolute" => Ok(AgeFormat::Absolute),
+            _ => bail!("unknown age format '{}' (expected compact, clock or absolute)", value),
+        }
+    }
+
+    // Widest value, for sizing the Age column
+    pub fn width(self) -> u16 {
+        match self {
+            AgeFormat::Compact => 7,
+            AgeFormat::Clock => 9,
+            AgeFormat::Absolute => 19,
+        }
+    }
+}
+
+// Shared by the Age column and the detail pane
+pub fn format_age(format: AgeFormat, since: Instant) -> String {
+    let secs = since.elapsed().as_secs();
+    match format {
+        AgeFormat::Compact => match secs {
+            0..=59 => format!("{}s", secs),
+            60..=3599 => format!("{}m{}s", secs / 60, secs % 60),
+            3600..=86399 => format!("{}h{}m", secs / 3600, secs % 3600 / 60),
+            _ => format!("{}d{}h", secs / 86400, secs % 86400 / 3600),
+        },
+        AgeFormat::Clock => format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60),
+        AgeFormat::Absolute => {
+            // Instants have no calendar time, so go back from now
+            let at = SystemTime::now() - since.elapsed();
+            let epoch = at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            local_timestamp(epoch as libc::time_t)
+        }
+    }
+}
+
+fn local_timestamp(epoch: libc::time_t) -> String {
+    // SAFETY: localtime_r only writes the tm we pass, and an all-zero tm is
+    // a valid value to start from
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&epoch, &mut tm) }.is_null() {
+        return "-".to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
@@ -0,0 +1,173 @@
+use crate::network::{Connection, IoRate, NetworkMonitor};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub struct ProcessSummary {
+    pub pid: Option<u32>,
+    pub name: String,
+    pub listening: usize,
+    pub active: usize,
+    pub io: Option<IoRate>,
+}
+
+// Groups connections by owning process. Unattributed sockets are folded into
+// a single "-" row so their volume stays visible.
+pub fn by_process<'a>(
+    connections: impl Iterator<Item = &'a Connection>,
+    monitor: &mut NetworkMonitor,
+) -> Vec<ProcessSummary> {
+    let mut groups: HashMap<Option<u32>, ProcessSummary> = HashMap::new();
+
+    for conn in connections {
+        let summary = groups.entry(conn.pid).or_insert_with(|| ProcessSummary {
+            pid: conn.pid,
+            name: conn.process_name.clone().unwrap_or_else(|| "-".to_string()),
+            listening: 0,
+            active: 0,
+            io: None,
+        });
+
+        if conn.is_listening() {
+            summary.listening += 1;
+        } else {
+            summary.active += 1;
+        }
+    }
+
+    let pids: HashSet<u32> = groups.keys().flatten().copied().collect();
+    for summary in groups.values_mut() {
+        if let Some(pid) = summary.pid {
+            summary.io = monitor.process_io_rate(pid);
+        }
+    }
+    monitor.retain_io_samples(&pids);
+
+    let mut summaries: Vec<ProcessSummary> = groups.into_values().collect();
+    summaries.sort_by(|a, b| {
+        (b.listening + b.active)
+            .cmp(&(a.listening + a.active))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    summaries
+}
+
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    const UNITS: &[&str] = &["B/s", "KiB/s", "MiB/s", "GiB/s"];
+
+    let mut value = bytes_per_sec;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Network prefix used to collapse remotes: /24 for IPv4 (including
+// v4-mapped IPv6) and /64 for IPv6
+pub fn subnet_of(address: &str) -> String {
+    let Ok(ip) = address.parse::<IpAddr>() else {
+        return address.to_string();
+    };
+
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    };
+
+    match ip {
+        IpAddr::V4(v4) => {
+            let masked = u32::from(v4) & 0xFFFF_FF00;
+            format!("{}/24", Ipv4Addr::from(masked))
+        }
+        IpAddr::V6(v6) => {
+            let masked = u128::from(v6) & !((1u128 << 64) - 1);
+            format!("{}/64", Ipv6Addr::from(masked))
+        }
+    }
+}
+
+// Buckets connections by remote subnet, largest bucket first. Connections
+// keep their incoming (sorted) order within a bucket.
+pub fn group_by_subnet<'a>(connections: Vec<&'a Connection>) -> Vec<(String, Vec<&'a Connection>)> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<&'a Connection>> = HashMap::new();
+
+    for conn in connections {
+        let subnet = subnet_of(&conn.remote_address);
+        groups
+            .entry(subnet.clone())
+            .or_insert_with(|| {
+                order.push(subnet);
+                Vec::new()
+            })
+            .push(conn);
+    }
+
+    let mut buckets: Vec<(String, Vec<&'a Connection>)> = order
+        .into_iter()
+        .map(|subnet| {
+            let members = groups.remove(&subnet).unwrap_or_default();
+            (subnet, members)
+        })
+        .collect();
+    buckets.sort_by_key(|(_, members)| std::cmp::Reverse(members.len()));
+    buckets
+}
+
+// Connection count per remote address, ports ignored
+pub fn by_remote<'a>(connections: impl Iterator<Item = &'a Connection>) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for conn in connections {
+        // UNIX sockets have no remote address to group by
+        if conn.remote_address != "-" {
+            *counts.entry(conn.remote_address.clone()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+// The `limit` busiest remotes, busiest first; ties are broken by address so
+// rows don't shuffle between refreshes
+pub fn top_remotes(counts: HashMap<String, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut remotes: Vec<(String, usize)> = counts.into_iter().collect();
+    remotes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    remotes.truncate(limit);
+    remotes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_masks_to_slash_24() {
+        assert_eq!(subnet_of("192.168.1.77"), "192.168.1.0/24");
+        assert_eq!(subnet_of("10.0.0.0"), "10.0.0.0/24");
+        assert_eq!(subnet_of("10.0.0.255"), "10.0.0.0/24");
+        assert_ne!(subnet_of("10.0.1.1"), subnet_of("10.0.0.1"));
+    }
+
+    #[test]
+    fn ipv6_masks_to_slash_64() {
+        assert_eq!(subnet_of("2001:db8:1:2:aaaa:bbbb:cccc:dddd"), "2001:db8:1:2::/64");
+        assert_eq!(subnet_of("fe80::1"), "fe80::/64");
+        assert_eq!(subnet_of("::1"), "::/64");
+    }
+
+    #[test]
+    fn v4_mapped_addresses_group_with_ipv4() {
+        assert_eq!(subnet_of("::ffff:192.168.1.77"), "192.168.1.0/24");
+    }
+
+    #[test]
+    fn non_ip_addresses_are_their_own_group() {
+        assert_eq!(subnet_of("/run/docker.sock"), "/run/docker.sock");
+        assert_eq!(subnet_of("*"), "*");
+    }
+}
@@ -0,0 +1,291 @@
+use crate::age::AgeFormat;
+use crate::network::{ProtocolSet, Source};
+use crate::sort::SortKey;
+use anyhow::{anyhow, bail, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: netmon-tui [OPTIONS]
+
+Options:
+      --once              Print the current connections once and exit
+      --summary           Print connection counts per protocol and state and exit
+      --sort <KEY>        Sort by proto, laddr, lport, raddr, rport, state, pid or program
+      --sort-desc         Sort in descending order
+      --top <N>           With --once, print only the first N connections after sorting
+      --since <DURATION>  Only show connections first seen within DURATION (e.g. 30s, 5m)
+      --keep-unknown-age  With --since, also show connections that predate startup
+      --age-format <FMT>  Show ages as compact (1h3m), clock (01:03:12) or absolute [default: compact]
+      --filter-port <N>   With --once or --summary, only count sockets using port N (repeatable)
+      --exit-code         With --once or --summary, exit 1 if no connections matched
+      --diff <OLD> <NEW>  Compare two JSON snapshots (from E or Ctrl+E) and exit
+      --bench <N>         Time N scans and print min/avg/max, then exit
+      --exclude-port <N>  Hide connections using port N locally or remotely (repeatable)
+      --me                Only show sockets owned by the current user
+      --exe-prefix <PATH> Only show sockets of processes whose executable is under PATH
+      --confirm-quit      Require pressing q twice to quit
+      --no-color          Tell states apart by bold, dim and underline instead of colour
+      --focus <PANEL>     Start with the listen or active panel focused [default: listen]
+      --manual            Only refresh at startup and when R is pressed
+      --proto <LIST>      Only read these of tcp, tcp6, udp, udp6 [default: all]
+      --source <SOURCE>   Read TCP/UDP sockets from proc, ss (fallback) or netlink [default: proc]
+      --proc-dir <DIR>    Read the net/ socket tables from DIR instead of /proc
+      --ssh <DEST>        Also list DEST's sockets by running ss over ssh, with a Host column (repeatable)
+      --firewall-hints    Mark exposed listeners the local iptables/nftables rules seem to block (heuristic)
+      --extra-protos      Also read SCTP and raw sockets when the kernel exposes them
+      --unix              Also list UNIX domain sockets, with a Flags column
+      --resolve           Show reverse-DNS names for remote addresses
+      --dns-ttl <SECS>    Re-resolve cached hostnames older than this [default: 3600]
+      --anonymize         Show remote addresses as pseudonyms, for screenshots
+      --anonymize-local   Like --anonymize, and mask local addresses too
+      --debug             Start with the debug overlay open
+      --ipc-socket <PATH> Stream each refresh as NDJSON to clients of a Unix socket
+      --output-on-exit <PATH>
+                          On quit, write the connections on screen as a table to PATH (- for stdout)
+      --config <PATH>     Read settings from PATH instead of the default config file
+      --log <PATH>        Append a diagnostic log to PATH
+  -v, -vv                 Log at debug / trace level (with --log)
+      --tls-ports <LIST>  Comma-separated remote ports treated as likely TLS
+  -V, --version           Print version, kernel and proc file info and exit
+  -h, --help              Print this help and exit";
+
+// Remote ports that conventionally carry TLS (HTTPS, SMTPS, IMAPS, POP3S, ...)
+pub const DEFAULT_TLS_PORTS: &[u16] = &[443, 465, 563, 636, 853, 989, 990, 992, 993, 994, 995, 5061, 8443];
+
+pub struct Args {
+    pub once: bool,
+    pub summary: bool,
+    pub sort_key: Option<SortKey>,
+    pub sort_desc: bool,
+    pub top: Option<usize>,
+    // Non-interactive modes only; any of these ports, locally or remotely
+    pub filter_ports: Vec<u16>,
+    pub exit_code: bool,
+    // Two snapshot files to compare instead of reading live sockets
+    pub diff: Option<(PathBuf, PathBuf)>,
+    // Number of timed scans for --bench
+    pub bench: Option<usize>,
+    pub me: bool,
+    // Matched against /proc/<pid>/exe by path component
+    pub exe_prefix: Option<PathBuf>,
+    pub exclude_ports: Vec<u16>,
+    pub since: Option<Duration>,
+    pub keep_unknown_age: bool,
+    // Overrides `age_format` from the config
+    pub age_format: Option<AgeFormat>,
+    pub confirm_quit: bool,
+    // Also implied by NO_COLOR or a terminal without colour
+    pub no_color: bool,
+    // --focus active; the listening panel is focused otherwise
+    pub focus_active: bool,
+    pub manual: bool,
+    pub protocols: ProtocolSet,
+    pub source: Source,
+    // --ssh destinations, in the order given
+    pub ssh_hosts: Vec<String>,
+    pub firewall_hints: bool,
+    pub extra_protos: bool,
+    pub unix: bool,
+    pub resolve: bool,
+    pub dns_ttl: Duration,
+    pub anonymize: bool,
+    pub anonymize_local: bool,
+    pub debug: bool,
+    pub log_file: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub ipc_socket: Option<PathBuf>,
+    // Stands in for /proc when reading net/tcp and friends
+    pub proc_dir: Option<PathBuf>,
+    pub output_on_exit: Option<PathBuf>,
+    pub verbosity: u8,
+    pub tls_ports: Vec<u16>,
+    pub version: bool,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            once: false,
+            summary: false,
+            sort_key: None,
+            sort_desc: false,
+            top: None,
+            filter_ports: Vec::new(),
+            exit_code: false,
+            diff: None,
+            bench: None,
+            me: false,
+            exe_prefix: None,
+            exclude_ports: Vec::new(),
+            since: None,
+            keep_unknown_age: false,
+            age_format: None,
+            confirm_quit: false,
+            no_color: false,
+            focus_active: false,
+            manual: false,
+            protocols: ProtocolSet::default(),
+            source: Source::default(),
+            ssh_hosts: Vec::new(),
+            firewall_hints: false,
+            extra_protos: false,
+            unix: false,
+            resolve: false,
+            dns_ttl: Duration::from_secs(3600),
+            anonymize: false,
+            anonymize_local: false,
+            debug: false,
+            log_file: None,
+            config: None,
+            ipc_socket: None,
+            proc_dir: None,
+            output_on_exit: None,
+            verbosity: 0,
+            tls_ports: DEFAULT_TLS_PORTS.to_vec(),
+            version: false,
+            help: false,
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    fn parse_from(mut argv: impl Iterator<Item = String>) -> Result<Self> {
+        let mut args = Args::default();
+
+        while let Some(arg) = argv.next() {
+            match arg.as_str() {
+                "-h" | "--help" => args.help = true,
+                "-V" | "--version" => args.version = true,
+                "--once" => args.once = true,
+                "--summary" => args.summary = true,
+                "--sort" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.sort_key = Some(SortKey::parse(&value)?);
+                }
+                "--sort-desc" => args.sort_desc = true,
+                "--top" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.top = Some(parse_number(&value, &arg)?);
+                }
+                "--filter-port" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.filter_ports.push(parse_number(&value, &arg)?);
+                }
+                "--exit-code" => args.exit_code = true,
+                "--bench" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    match parse_number(&value, &arg)? {
+                        0 => bail!("--bench needs at least 1 iteration"),
+                        iterations => args.bench = Some(iterations),
+                    }
+                }
+                "--diff" => {
+                    let old = PathBuf::from(next_value(&mut argv, &arg)?);
+                    args.diff = Some((old, PathBuf::from(next_value(&mut argv, &arg)?)));
+                }
+                "--exclude-port" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.exclude_ports.push(parse_number(&value, &arg)?);
+                }
+                "--since" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.since = Some(parse_duration(&value, &arg)?);
+                }
+                "--keep-unknown-age" => args.keep_unknown_age = true,
+                "--age-format" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.age_format = Some(AgeFormat::parse(&value)?);
+                }
+                "--me" => args.me = true,
+                "--exe-prefix" => args.exe_prefix = Some(PathBuf::from(next_value(&mut argv, &arg)?)),
+                "--confirm-quit" => args.confirm_quit = true,
+                "--no-color" => args.no_color = true,
+                "--manual" => args.manual = true,
+                "--focus" => {
+                    args.focus_active = match next_value(&mut argv, &arg)?.as_str() {
+                        "listen" | "listening" => false,
+                        "active" => true,
+                        other => bail!("unknown panel '{}' for --focus (expected listen or active)", other),
+                    }
+                }
+                "--proto" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.protocols = ProtocolSet::parse(&value)?;
+                }
+                "--source" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.source = Source::parse(&value)?;
+                }
+                "--ssh" => args.ssh_hosts.push(next_value(&mut argv, &arg)?),
+                "--firewall-hints" => args.firewall_hints = true,
+                "--extra-protos" => args.extra_protos = true,
+                "--unix" => args.unix = true,
+                "--resolve" => args.resolve = true,
+                "--dns-ttl" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.dns_ttl = Duration::from_secs(parse_number(&value, &arg)?);
+                }
+                "--anonymize" => args.anonymize = true,
+                "--anonymize-local" => args.anonymize_local = true,
+                "--debug" => args.debug = true,
+                "--ipc-socket" => args.ipc_socket = Some(PathBuf::from(next_value(&mut argv, &arg)?)),
+                "--proc-dir" => args.proc_dir = Some(PathBuf::from(next_value(&mut argv, &arg)?)),
+                "--output-on-exit" => args.output_on_exit = Some(PathBuf::from(next_value(&mut argv, &arg)?)),
+                "--config" => args.config = Some(PathBuf::from(next_value(&mut argv, &arg)?)),
+                "--log" => args.log_file = Some(PathBuf::from(next_value(&mut argv, &arg)?)),
+                _ if arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v') => {
+                    args.verbosity = args.verbosity.saturating_add(arg.len() as u8 - 1);
+                }
+                "--tls-ports" => {
+                    let value = next_value(&mut argv, &arg)?;
+                    args.tls_ports = parse_port_list(&value)?;
+                }
+                _ => bail!("unknown argument '{}'", arg),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn next_value(argv: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    argv.next().ok_or_else(|| anyhow!("{} requires a value", flag))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T> {
+    value
+        .parse::<T>()
+        .map_err(|_| anyhow!("invalid value '{}' for {}", value, flag))
+}
+
+// Seconds, optionally suffixed with s, m or h
+fn parse_duration(value: &str, flag: &str) -> Result<Duration> {
+    let (number, scale) = match value.as_bytes().last() {
+        Some(b's') => (&value[..value.len() - 1], 1),
+        Some(b'm') => (&value[..value.len() - 1], 60),
+        Some(b'h') => (&value[..value.len() - 1], 3600),
+        _ => (value, 1),
+    };
+    match number.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(Duration::from_secs(n.saturating_mul(scale))),
+        _ => bail!("invalid duration '{}' for {} (expected e.g. 30s, 5m, 1h)", value, flag),
+    }
+}
+
+fn parse_port_list(value: &str) -> Result<Vec<u16>> {
+    value
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(|part| {
+            part.trim()
+                .parse::<u16>()
+                .map_err(|_| anyhow!("invalid port '{}'", part.trim()))
+        })
+        .collect()
+}
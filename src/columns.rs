@@ -0,0 +1,76 @@
+// Table columns by identity, so the config can reorder them. Each panel
+// shows its own subset; `column_order` in the config moves the listed ones to
+// the front and leaves the rest in their default order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    // The --ssh destination, or "local"; only with --ssh
+    Host,
+    // listen/active, only in the compact combined table
+    Kind,
+    Proto,
+    Local,
+    Iface,
+    Flags,
+    AcceptRate,
+    // ESTABLISHED connections on a TCP listener's port, listening panel only
+    Established,
+    Remote,
+    // Loopback, link-local, private or public remote, active panel only
+    Scope,
+    State,
+    // Time since first seen, active panel only
+    Age,
+    // Kernel counters, shown with 'i'
+    RecvQueue,
+    SendQueue,
+    Retransmits,
+    Drops,
+    // Owning process's cgroup, shown with 'o'
+    Cgroup,
+    // Container name for the cgroup's container ID, shown with 'O'
+    Container,
+    // Socket inode, shown with 'I'
+    Inode,
+    Program,
+}
+
+const NAMES: &[(ColumnKind, &str)] = &[
+    (ColumnKind::Host, "host"),
+    (ColumnKind::Kind, "kind"),
+    (ColumnKind::Proto, "proto"),
+    (ColumnKind::Local, "local"),
+    (ColumnKind::Iface, "iface"),
+    (ColumnKind::Flags, "flags"),
+    (ColumnKind::AcceptRate, "accept"),
+    (ColumnKind::Established, "active"),
+    (ColumnKind::Remote, "remote"),
+    (ColumnKind::Scope, "scope"),
+    (ColumnKind::State, "state"),
+    (ColumnKind::Age, "age"),
+    (ColumnKind::RecvQueue, "recvq"),
+    (ColumnKind::SendQueue, "sendq"),
+    (ColumnKind::Retransmits, "retrans"),
+    (ColumnKind::Drops, "drops"),
+    (ColumnKind::Cgroup, "cgroup"),
+    (ColumnKind::Container, "container"),
+    (ColumnKind::Inode, "inode"),
+    (ColumnKind::Program, "program"),
+];
+
+impl ColumnKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        NAMES.iter().find(|(_, n)| *n == name).map(|(kind, _)| *kind)
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        NAMES.iter().map(|(_, name)| *name).collect()
+    }
+}
+
+// Stable, so columns missing from `order` keep their relative positions
+pub fn apply_order<T>(columns: &mut [T], order: &[ColumnKind], kind: impl Fn(&T) -> ColumnKind) {
+    columns.sort_by_key(|column| {
+        let kind = kind(column);
+        order.iter().position(|k| *k == kind).unwrap_or(order.len())
+    });
+}
@@ -0,0 +1,220 @@
+use crate::age::AgeFormat;
+use crate::columns::ColumnKind;
+use crate::keymap::Keymap;
+use crate::portscan::ScanThresholds;
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// How connections with loopback at both ends, i.e. IPC over TCP or UDP,
+// are set apart from real network traffic, from `local_ipc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LocalIpc {
+    #[default]
+    Off,
+    // "(local IPC)" after the program
+    Label,
+    // The label, and the row in its own colour
+    Highlight,
+}
+
+impl LocalIpc {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "off" => Ok(LocalIpc::Off),
+            "label" => Ok(LocalIpc::Label),
+            "highlight" => Ok(LocalIpc::Highlight),
+            _ => bail!("unknown local_ipc setting '{}' (expected off, label or highlight)", value),
+        }
+    }
+}
+
+// Settings read from the config file. The format is one `key = value` per
+// line; blank lines and lines starting with '#' are ignored. Lines after a
+// `[keybindings]` header map action names to comma-separated key specs.
+pub struct Config {
+    // How long to wait for input before redrawing
+    pub poll_rate: Duration,
+    // How often /proc is rescanned
+    pub refresh_rate: Duration,
+    pub keymap: Keymap,
+    // Columns moved to the front of every table, in this order
+    pub column_order: Vec<ColumnKind>,
+    pub age_format: AgeFormat,
+    pub local_ipc: LocalIpc,
+    // "1.4k" rather than "1423" in titles and the stats columns
+    pub compact_numbers: bool,
+    // Pins are saved on quit and restored at startup
+    pub persist_pins: bool,
+    // When a process is flagged as a possible port scanner
+    pub scan: ScanThresholds,
+    // Problems that didn't stop the config loading, shown once at startup
+    pub warnings: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            poll_rate: Duration::from_millis(50),
+            refresh_rate: Duration::from_secs(1),
+            keymap: Keymap::default(),
+            column_order: Vec::new(),
+            age_format: AgeFormat::default(),
+            local_ipc: LocalIpc::default(),
+            compact_numbers: false,
+            persist_pins: false,
+            scan: ScanThresholds::default(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    // An explicit path must exist; the default location is optional
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content).with_context(|| format!("in {}", path.display())),
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(anyhow!("cannot read {}: {}", path.display(), err)),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut config = Self::default();
+        let mut section = String::new();
+        let mut keybindings = Vec::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                section = name.trim().to_string();
+                if section != "keybindings" {
+                    config.warnings.push(format!("config line {}: unknown section '{}'", i + 1, section));
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("line {}: expected `key = value`", i + 1);
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match (section.as_str(), key) {
+                ("keybindings", _) => keybindings.push((i + 1, key.to_string(), value.to_string())),
+                // Lines in an unknown section were already warned about
+                (section, _) if !section.is_empty() => {}
+                (_, "poll_ms") => config.poll_rate = parse_millis(value, i + 1)?,
+                (_, "refresh_ms") => config.refresh_rate = parse_millis(value, i + 1)?,
+                (_, "column_order") => config.column_order = parse_columns(value, i + 1, &mut config.warnings),
+                (_, "age_format") => {
+                    config.age_format = AgeFormat::parse(value).map_err(|err| anyhow!("line {}: {}", i + 1, err))?
+                }
+                (_, "local_ipc") => {
+                    config.local_ipc = LocalIpc::parse(value).map_err(|err| anyhow!("line {}: {}", i + 1, err))?
+                }
+                (_, "scan_window_ms") => config.scan.window = parse_millis(value, i + 1)?,
+                (_, "scan_ports") => config.scan.ports = parse_count(value, i + 1)?,
+                (_, "scan_hosts") => config.scan.hosts = parse_count(value, i + 1)?,
+                (_, "compact_numbers") => config.compact_numbers = parse_bool(value, i + 1)?,
+                (_, "persist_pins") => config.persist_pins = parse_bool(value, i + 1)?,
+                // Unknown keys are tolerated so a newer config still works
+                // with an older binary
+                _ => config.warnings.push(format!("config line {}: unknown key '{}'", i + 1, key)),
+            }
+        }
+
+        config.keymap = Keymap::with_overrides(&keybindings, &mut config.warnings);
+        for warning in &config.warnings {
+            warn!("{}", warning);
+        }
+
+        Ok(config)
+    }
+}
+
+// Unknown names are skipped with a warning rather than rejecting the file
+fn parse_columns(value: &str, line: usize, warnings: &mut Vec<String>) -> Vec<ColumnKind> {
+    let mut columns = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        match ColumnKind::parse(name) {
+            Some(kind) if !columns.contains(&kind) => columns.push(kind),
+            Some(_) => warnings.push(format!("config line {}: column '{}' listed twice", line, name)),
+            None => warnings.push(format!(
+                "config line {}: unknown column '{}' (expected one of: {})",
+                line,
+                name,
+                ColumnKind::names().join(", ")
+            )),
+        }
+    }
+    columns
+}
+
+fn parse_millis(value: &str, line: usize) -> Result<Duration> {
+    match value.parse::<u64>() {
+        Ok(ms) if ms > 0 => Ok(Duration::from_millis(ms)),
+        _ => bail!("line {}: expected a positive number of milliseconds, got '{}'", line, value),
+    }
+}
+
+fn parse_count(value: &str, line: usize) -> Result<usize> {
+    value.parse().map_err(|_| anyhow!("line {}: expected a whole number, got '{}'", line, value))
+}
+
+fn parse_bool(value: &str, line: usize) -> Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => bail!("line {}: expected true or false, got '{}'", line, value),
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("netmon-tui").join("config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_ipc_setting() {
+        assert_eq!(Config::parse("").unwrap().local_ipc, LocalIpc::Off);
+        assert_eq!(Config::parse("local_ipc = label\n").unwrap().local_ipc, LocalIpc::Label);
+        assert_eq!(Config::parse("local_ipc = highlight\n").unwrap().local_ipc, LocalIpc::Highlight);
+
+        let err = Config::parse("# IPC\nlocal_ipc = loud\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 2: unknown local_ipc setting 'loud' (expected off, label or highlight)"
+        );
+    }
+
+    #[test]
+    fn compact_numbers_setting() {
+        assert!(!Config::parse("").unwrap().compact_numbers);
+        assert!(Config::parse("compact_numbers = true\n").unwrap().compact_numbers);
+        assert!(!Config::parse("compact_numbers = false\n").unwrap().compact_numbers);
+
+        let err = Config::parse("compact_numbers = yes\n").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: expected true or false, got 'yes'");
+    }
+}
@@ -0,0 +1,151 @@
+use crate::json;
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+// Replies slower than this count as no answer; the daemon is local, so this
+// only trips when it's wedged
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// A failed lookup is retried after this long, so a container started just
+// before its first socket showed up, or a daemon that was briefly down,
+// gets its name eventually
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+// Container names by full container ID, asked of the Docker Engine API on
+// its Unix socket the first time an ID shows up. Podman's Docker-compatible
+// socket works through DOCKER_HOST. containerd and CRI-O only speak gRPC, so
+// their containers keep showing the short ID.
+pub struct ContainerNames {
+    names: HashMap<String, Lookup>,
+    socket: PathBuf,
+    tx: Sender<(String, Option<String>)>,
+    rx: Receiver<(String, Option<String>)>,
+}
+
+enum Lookup {
+    Pending,
+    Found(String),
+    // When the daemon didn't know the ID, or couldn't be asked about it
+    Failed(Instant),
+}
+
+impl Default for ContainerNames {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self { names: HashMap::new(), socket: docker_socket(), tx, rx }
+    }
+}
+
+impl ContainerNames {
+    pub fn name(&self, id: &str) -> Option<&str> {
+        match self.names.get(id)? {
+            Lookup::Found(name) => Some(name),
+            Lookup::Pending | Lookup::Failed(_) => None,
+        }
+    }
+
+    // Starts a background lookup for an ID not seen before, or whose last
+    // lookup failed a while ago
+    pub fn request(&mut self, id: &str) {
+        if !self.should_ask(id, Instant::now()) {
+            return;
+        }
+        // Reserved until the answer arrives, so one lookup runs at a time
+        self.names.insert(id.to_string(), Lookup::Pending);
+
+        let (id, socket, tx) = (id.to_string(), self.socket.clone(), self.tx.clone());
+        tokio::task::spawn_blocking(move || {
+            let name = match inspect(&socket, &id) {
+                Ok(name) => Some(name),
+                Err(err) => {
+                    debug!("no container name for {}: {:#}", &id[..12], err);
+                    None
+                }
+            };
+            let _ = tx.send((id, name));
+        });
+    }
+
+    // Files finished lookups. Called once per loop iteration.
+    pub fn collect(&mut self) {
+        while let Ok((id, name)) = self.rx.try_recv() {
+            let lookup = match name {
+                Some(name) => Lookup::Found(name),
+                None => Lookup::Failed(Instant::now()),
+            };
+            self.names.insert(id, lookup);
+        }
+    }
+
+    fn should_ask(&self, id: &str, now: Instant) -> bool {
+        match self.names.get(id) {
+            None => true,
+            Some(Lookup::Failed(at)) => now.duration_since(*at) >= RETRY_AFTER,
+            Some(Lookup::Pending | Lookup::Found(_)) => false,
+        }
+    }
+}
+
+// DOCKER_HOST when it names a Unix socket, as the docker CLI reads it
+fn docker_socket() -> PathBuf {
+    match std::env::var("DOCKER_HOST") {
+        Ok(host) if host.starts_with("unix://") => PathBuf::from(&host["unix://".len()..]),
+        _ => PathBuf::from("/var/run/docker.sock"),
+    }
+}
+
+// GET /containers/<id>/json; the "Name" field has a leading '/'. HTTP/1.0
+// keeps the daemon from chunking the reply and has it close the connection
+// when done.
+fn inspect(socket: &Path, id: &str) -> Result<String> {
+    let mut stream = UnixStream::connect(socket).with_context(|| format!("cannot connect to {}", socket.display()))?;
+    stream.set_read_timeout(Some(QUERY_TIMEOUT))?;
+    stream.set_write_timeout(Some(QUERY_TIMEOUT))?;
+    stream.write_all(format!("GET /containers/{}/json HTTP/1.0\r\nHost: docker\r\n\r\n", id).as_bytes())?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    let reply = String::from_utf8_lossy(&reply);
+    let Some((head, body)) = reply.split_once("\r\n\r\n") else {
+        bail!("malformed reply");
+    };
+    let status = head.lines().next().unwrap_or("");
+    if status.split_whitespace().nth(1) != Some("200") {
+        bail!("daemon answered '{}'", status);
+    }
+
+    let document = json::parse(body)?;
+    match document.get("Name").and_then(|name| name.as_str()) {
+        Some(name) if !name.trim_start_matches('/').is_empty() => Ok(name.trim_start_matches('/').to_string()),
+        _ => bail!("reply has no container name"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_lookups_are_retried_later() {
+        let mut names = ContainerNames::default();
+        let (found, missing) = ("a".repeat(64), "b".repeat(64));
+        names.tx.send((found.clone(), Some("web".to_string()))).unwrap();
+        names.tx.send((missing.clone(), None)).unwrap();
+        names.collect();
+
+        let now = Instant::now();
+        assert_eq!(names.name(&found), Some("web"));
+        assert_eq!(names.name(&missing), None);
+        assert!(!names.should_ask(&found, now));
+        assert!(!names.should_ask(&missing, now));
+        assert!(names.should_ask(&missing, now + RETRY_AFTER));
+        assert!(!names.should_ask(&found, now + RETRY_AFTER));
+        assert!(names.should_ask(&"c".repeat(64), now));
+    }
+}
@@ -0,0 +1,553 @@
+use log::debug;
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+// TCP internals from the sock_diag netlink interface (what `ss -i` shows).
+// /proc/net/tcp has no RTT or congestion data, so this fills the gap for
+// established sockets; callers treat a `None` as "proc data only".
+#[derive(Debug, Clone, Default)]
+pub struct TcpInfo {
+    pub rtt_ms: f64,
+    pub rttvar_ms: f64,
+    pub snd_cwnd: u32,
+    pub total_retrans: u32,
+    // Unacknowledged segments times the send MSS; an upper-bound estimate
+    pub bytes_in_flight: u64,
+    // Congestion control algorithm ("cubic", "bbr", ...)
+    pub congestion: Option<String>,
+}
+
+// Accept queue of a listener: connections the kernel has completed but the
+// application hasn't accept()ed yet, against the listen() backlog
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListenQueue {
+    pub queued: u32,
+    pub backlog: u32,
+}
+
+impl ListenQueue {
+    // Close enough to the backlog that new handshakes are about to be dropped
+    pub fn is_near_full(&self) -> bool {
+        self.backlog > 0 && self.queued as u64 * 10 >= self.backlog as u64 * 8
+    }
+}
+
+// Buffer limits of a TCP or UDP socket, i.e. SO_RCVBUF and SO_SNDBUF as
+// the kernel applies them (setsockopt values are doubled)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SocketBuffers {
+    pub rcvbuf: u32,
+    pub sndbuf: u32,
+}
+
+// One socket with the fields /proc/net/{tcp,udp} would have given, for
+// --source netlink. States and timer codes are the kernel's, as in /proc.
+#[derive(Debug, Clone)]
+pub struct DiagSocket {
+    pub state: u8,
+    pub timer: u8,
+    pub retransmits: u8,
+    pub local: (IpAddr, u16),
+    pub remote: (IpAddr, u16),
+    pub expires_ms: u32,
+    pub rqueue: u32,
+    pub wqueue: u32,
+    pub uid: u32,
+    pub inode: u64,
+    // Datagrams dropped on receive, from the socket's meminfo
+    pub drops: Option<u32>,
+}
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const INET_DIAG_INFO: u16 = 2;
+const INET_DIAG_CONG: u16 = 4;
+const INET_DIAG_SKMEMINFO: u16 = 7;
+const ALL_STATES: u32 = !0;
+const TCP_ESTABLISHED: u32 = 1;
+const TCP_LISTEN: u32 = 10;
+
+// Byte offsets into `struct inet_diag_msg` and `struct tcp_info`
+// (include/uapi/linux/inet_diag.h, include/uapi/linux/tcp.h). For listeners
+// rqueue is the accept queue length and wqueue the backlog.
+const DIAG_MSG_LEN: usize = 72;
+const DIAG_MSG_STATE: usize = 1;
+const DIAG_MSG_TIMER: usize = 2;
+const DIAG_MSG_RETRANS: usize = 3;
+const DIAG_MSG_SPORT: usize = 4;
+const DIAG_MSG_DPORT: usize = 6;
+const DIAG_MSG_SRC: usize = 8;
+const DIAG_MSG_DST: usize = 24;
+const DIAG_MSG_EXPIRES: usize = 52;
+const DIAG_MSG_RQUEUE: usize = 56;
+const DIAG_MSG_WQUEUE: usize = 60;
+const DIAG_MSG_UID: usize = 64;
+const DIAG_MSG_INODE: usize = 68;
+const TCPI_SND_MSS: usize = 16;
+const TCPI_UNACKED: usize = 24;
+const TCPI_RTT: usize = 68;
+const TCPI_RTTVAR: usize = 72;
+const TCPI_SND_CWND: usize = 80;
+const TCPI_TOTAL_RETRANS: usize = 100;
+// Indexes into the u32 array of INET_DIAG_SKMEMINFO (SK_MEMINFO_RCVBUF and
+// SK_MEMINFO_SNDBUF in include/uapi/linux/sock_diag.h)
+const SKMEMINFO_RCVBUF: usize = 1;
+const SKMEMINFO_SNDBUF: usize = 3;
+const SKMEMINFO_DROPS: usize = 8;
+
+#[repr(C)]
+struct InetDiagSockId {
+    sport: u16,
+    dport: u16,
+    src: [u32; 4],
+    dst: [u32; 4],
+    ifindex: u32,
+    cookie: [u32; 2],
+}
+
+#[repr(C)]
+struct InetDiagReqV2 {
+    family: u8,
+    protocol: u8,
+    ext: u8,
+    pad: u8,
+    states: u32,
+    id: InetDiagSockId,
+}
+
+#[repr(C)]
+struct DiagRequest {
+    header: libc::nlmsghdr,
+    body: InetDiagReqV2,
+}
+
+// Keyed by socket inode, which matches `Connection::inode`. inet_diag only
+// carries the low 32 bits, which is all current kernels hand out.
+pub fn established_tcp_info() -> Option<HashMap<u64, TcpInfo>> {
+    let socket = NetlinkSocket::open()?;
+    let mut infos = HashMap::new();
+
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        let ext = 1 << (INET_DIAG_INFO - 1) | 1 << (INET_DIAG_CONG - 1);
+        let dumped = socket.dump(family as u8, libc::IPPROTO_TCP as u8, 1 << TCP_ESTABLISHED, ext, &mut |message| {
+            let info = find_attribute(message, INET_DIAG_INFO).and_then(parse_tcp_info);
+            if let Some(mut info) = info {
+                info.congestion = find_attribute(message, INET_DIAG_CONG).and_then(parse_congestion);
+                infos.insert(read_u32(message, DIAG_MSG_INODE) as u64, info);
+            }
+        });
+        if let Err(err) = dumped {
+            debug!("sock_diag dump for family {} failed: {}", family, err);
+            return None;
+        }
+    }
+
+    Some(infos)
+}
+
+// Accept queues of TCP listeners, keyed by inode like `established_tcp_info`
+pub fn listen_queues() -> Option<HashMap<u64, ListenQueue>> {
+    let socket = NetlinkSocket::open()?;
+    let mut queues = HashMap::new();
+
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        let dumped = socket.dump(family as u8, libc::IPPROTO_TCP as u8, 1 << TCP_LISTEN, 0, &mut |message| {
+            let queue = ListenQueue {
+                queued: read_u32(message, DIAG_MSG_RQUEUE),
+                backlog: read_u32(message, DIAG_MSG_WQUEUE),
+            };
+            queues.insert(read_u32(message, DIAG_MSG_INODE) as u64, queue);
+        });
+        if let Err(err) = dumped {
+            debug!("sock_diag listener dump for family {} failed: {}", family, err);
+            return None;
+        }
+    }
+
+    Some(queues)
+}
+
+// Buffer limits of every TCP and UDP socket in any state, keyed by inode.
+// UDP needs the udp_diag module; without it only TCP sockets are covered.
+pub fn socket_buffers() -> Option<HashMap<u64, SocketBuffers>> {
+    let socket = NetlinkSocket::open()?;
+    let mut buffers = HashMap::new();
+
+    for protocol in [libc::IPPROTO_TCP, libc::IPPROTO_UDP] {
+        for family in [libc::AF_INET, libc::AF_INET6] {
+            let ext = 1 << (INET_DIAG_SKMEMINFO - 1);
+            let dumped = socket.dump(family as u8, protocol as u8, ALL_STATES, ext, &mut |message| {
+                let inode = read_u32(message, DIAG_MSG_INODE) as u64;
+                let meminfo = find_attribute(message, INET_DIAG_SKMEMINFO);
+                if let Some(meminfo) = meminfo.filter(|meminfo| meminfo.len() > SKMEMINFO_SNDBUF * 4 + 3) {
+                    let entry = SocketBuffers {
+                        rcvbuf: read_u32(meminfo, SKMEMINFO_RCVBUF * 4),
+                        sndbuf: read_u32(meminfo, SKMEMINFO_SNDBUF * 4),
+                    };
+                    // TIME_WAIT sockets have no inode
+                    if inode != 0 {
+                        buffers.insert(inode, entry);
+                    }
+                }
+            });
+            if let Err(err) = dumped {
+                debug!("sock_diag meminfo dump for protocol {} family {} failed: {}", protocol, family, err);
+            }
+        }
+    }
+
+    Some(buffers)
+}
+
+// Every `protocol` socket of `family` (AF_INET or AF_INET6) in any state.
+// UDP needs the udp_diag module, and fails with ENOENT without it.
+pub fn sockets(family: libc::c_int, protocol: libc::c_int) -> std::io::Result<Vec<DiagSocket>> {
+    let socket = NetlinkSocket::open()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Unsupported, "sock_diag unavailable"))?;
+    let mut sockets = Vec::new();
+
+    let ext = 1 << (INET_DIAG_SKMEMINFO - 1);
+    socket.dump(family as u8, protocol as u8, ALL_STATES, ext, &mut |message| {
+        sockets.push(parse_socket(message, family));
+    })?;
+
+    Ok(sockets)
+}
+
+fn parse_socket(message: &[u8], family: libc::c_int) -> DiagSocket {
+    let meminfo = find_attribute(message, INET_DIAG_SKMEMINFO);
+    DiagSocket {
+        state: message[DIAG_MSG_STATE],
+        timer: message[DIAG_MSG_TIMER],
+        retransmits: message[DIAG_MSG_RETRANS],
+        local: (read_address(message, DIAG_MSG_SRC, family), read_port(message, DIAG_MSG_SPORT)),
+        remote: (read_address(message, DIAG_MSG_DST, family), read_port(message, DIAG_MSG_DPORT)),
+        expires_ms: read_u32(message, DIAG_MSG_EXPIRES),
+        rqueue: read_u32(message, DIAG_MSG_RQUEUE),
+        wqueue: read_u32(message, DIAG_MSG_WQUEUE),
+        uid: read_u32(message, DIAG_MSG_UID),
+        inode: read_u32(message, DIAG_MSG_INODE) as u64,
+        drops: meminfo
+            .filter(|meminfo| meminfo.len() >= (SKMEMINFO_DROPS + 1) * 4)
+            .map(|meminfo| read_u32(meminfo, SKMEMINFO_DROPS * 4)),
+    }
+}
+
+// Ports and addresses in inet_diag_sockid are in network order
+fn read_port(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_address(data: &[u8], offset: usize, family: libc::c_int) -> IpAddr {
+    if family == libc::AF_INET {
+        let bytes: [u8; 4] = data[offset..offset + 4].try_into().unwrap_or_default();
+        IpAddr::V4(Ipv4Addr::from(bytes))
+    } else {
+        let bytes: [u8; 16] = data[offset..offset + 16].try_into().unwrap_or_default();
+        IpAddr::V6(Ipv6Addr::from(bytes))
+    }
+}
+
+struct NetlinkSocket(libc::c_int);
+
+impl NetlinkSocket {
+    fn open() -> Option<Self> {
+        // SAFETY: plain socket(2) call; the descriptor is owned by the
+        // returned value and closed on drop
+        let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_SOCK_DIAG) };
+        if fd < 0 {
+            debug!("sock_diag unavailable: {}", std::io::Error::last_os_error());
+            return None;
+        }
+
+        // Don't let a misbehaving kernel stall the refresh
+        let timeout = libc::timeval { tv_sec: 1, tv_usec: 0 };
+        // SAFETY: `timeout` is a valid timeval and its size is passed along
+        unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                size_of::<libc::timeval>() as libc::socklen_t,
+            );
+        }
+
+        Some(Self(fd))
+    }
+
+    // Dumps `protocol` sockets of `family` in any of the `states` (a bitmask
+    // of TCP states), handing each inet_diag_msg that is long enough to carry
+    // an inode (with its attributes) to `handle`
+    fn dump(&self, family: u8, protocol: u8, states: u32, ext: u8, handle: &mut dyn FnMut(&[u8])) -> std::io::Result<()> {
+        // SAFETY: all-zero is a valid value for these plain C structs
+        let mut request: DiagRequest = unsafe { std::mem::zeroed() };
+        request.header.nlmsg_len = size_of::<DiagRequest>() as u32;
+        request.header.nlmsg_type = SOCK_DIAG_BY_FAMILY;
+        request.header.nlmsg_flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+        request.body.family = family;
+        request.body.protocol = protocol;
+        request.body.ext = ext;
+        request.body.states = states;
+
+        // SAFETY: all-zero is a valid sockaddr_nl; pid 0 addresses the kernel
+        let mut kernel: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        kernel.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+
+        // SAFETY: `request` and `kernel` are live, initialised and sized
+        // correctly for the duration of the call
+        let sent = unsafe {
+            libc::sendto(
+                self.0,
+                &request as *const DiagRequest as *const libc::c_void,
+                size_of::<DiagRequest>(),
+                0,
+                &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+                size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            // SAFETY: `buf` is writable for its full length
+            let received = unsafe { libc::recv(self.0, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+            if received < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            let mut handle_full = |message: &[u8]| {
+                if message.len() >= DIAG_MSG_LEN {
+                    handle(message)
+                }
+            };
+            if parse_messages(&buf[..received as usize], &mut handle_full)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Drop for NetlinkSocket {
+    fn drop(&mut self) {
+        // SAFETY: we own the descriptor and close it exactly once
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+// Returns true once the end of the dump has been reached
+fn parse_messages(mut data: &[u8], handle: &mut dyn FnMut(&[u8])) -> std::io::Result<bool> {
+    let header_len = size_of::<libc::nlmsghdr>();
+
+    while data.len() >= header_len {
+        let len = read_u32(data, 0) as usize;
+        let kind = read_u16(data, 4);
+        if len < header_len || len > data.len() {
+            break;
+        }
+
+        match kind as libc::c_int {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                let errno = read_u32(data, header_len) as i32;
+                return Err(std::io::Error::from_raw_os_error(-errno));
+            }
+            _ => handle(&data[header_len..len]),
+        }
+
+        data = &data[align(len).min(data.len())..];
+    }
+
+    Ok(false)
+}
+
+// The payload of the first rtattr of `kind` after the fixed header
+fn find_attribute(message: &[u8], kind: u16) -> Option<&[u8]> {
+    let mut attrs = &message[DIAG_MSG_LEN..];
+    while attrs.len() >= 4 {
+        let len = read_u16(attrs, 0) as usize;
+        if len < 4 || len > attrs.len() {
+            break;
+        }
+        if read_u16(attrs, 2) == kind {
+            return Some(&attrs[4..len]);
+        }
+        attrs = &attrs[align(len).min(attrs.len())..];
+    }
+    None
+}
+
+fn parse_tcp_info(payload: &[u8]) -> Option<TcpInfo> {
+    if payload.len() < TCPI_TOTAL_RETRANS + 4 {
+        return None;
+    }
+
+    Some(TcpInfo {
+        rtt_ms: read_u32(payload, TCPI_RTT) as f64 / 1000.0,
+        rttvar_ms: read_u32(payload, TCPI_RTTVAR) as f64 / 1000.0,
+        snd_cwnd: read_u32(payload, TCPI_SND_CWND),
+        total_retrans: read_u32(payload, TCPI_TOTAL_RETRANS),
+        bytes_in_flight: read_u32(payload, TCPI_UNACKED) as u64 * read_u32(payload, TCPI_SND_MSS) as u64,
+        congestion: None,
+    })
+}
+
+// A NUL-terminated name, as tcp_ca_get_name_by_key() hands it out
+fn parse_congestion(payload: &[u8]) -> Option<String> {
+    let name = payload.split(|byte| *byte == 0).next().unwrap_or_default();
+    (!name.is_empty()).then(|| String::from_utf8_lossy(name).into_owned())
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An inet_diag_msg for 10.0.0.1:443 -> 10.0.0.2:51000, followed by `attrs`
+    fn diag_message(attrs: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut message = vec![0u8; DIAG_MSG_LEN];
+        message[0] = libc::AF_INET as u8;
+        message[DIAG_MSG_STATE] = TCP_ESTABLISHED as u8;
+        message[DIAG_MSG_TIMER] = 1;
+        message[DIAG_MSG_RETRANS] = 2;
+        message[DIAG_MSG_SPORT..DIAG_MSG_SPORT + 2].copy_from_slice(&443u16.to_be_bytes());
+        message[DIAG_MSG_DPORT..DIAG_MSG_DPORT + 2].copy_from_slice(&51000u16.to_be_bytes());
+        message[DIAG_MSG_SRC..DIAG_MSG_SRC + 4].copy_from_slice(&[10, 0, 0, 1]);
+        message[DIAG_MSG_DST..DIAG_MSG_DST + 4].copy_from_slice(&[10, 0, 0, 2]);
+        message[DIAG_MSG_EXPIRES..DIAG_MSG_EXPIRES + 4].copy_from_slice(&200u32.to_ne_bytes());
+        message[DIAG_MSG_RQUEUE..DIAG_MSG_RQUEUE + 4].copy_from_slice(&3u32.to_ne_bytes());
+        message[DIAG_MSG_WQUEUE..DIAG_MSG_WQUEUE + 4].copy_from_slice(&128u32.to_ne_bytes());
+        message[DIAG_MSG_UID..DIAG_MSG_UID + 4].copy_from_slice(&1000u32.to_ne_bytes());
+        message[DIAG_MSG_INODE..DIAG_MSG_INODE + 4].copy_from_slice(&987654u32.to_ne_bytes());
+
+        for (kind, payload) in attrs {
+            message.extend_from_slice(&(payload.len() as u16 + 4).to_ne_bytes());
+            message.extend_from_slice(&kind.to_ne_bytes());
+            message.extend_from_slice(payload);
+            message.resize(align(message.len()), 0);
+        }
+        message
+    }
+
+    fn netlink_message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let len = size_of::<libc::nlmsghdr>() + payload.len();
+        let mut data = Vec::new();
+        data.extend_from_slice(&(len as u32).to_ne_bytes());
+        data.extend_from_slice(&kind.to_ne_bytes());
+        data.extend_from_slice(&[0u8; 10]);
+        data.extend_from_slice(payload);
+        data.resize(align(data.len()), 0);
+        data
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_ne_bytes()).collect()
+    }
+
+    #[test]
+    fn socket_fields() {
+        let mut meminfo = vec![0u32; SKMEMINFO_DROPS + 1];
+        meminfo[SKMEMINFO_DROPS] = 7;
+        let message = diag_message(&[(INET_DIAG_SKMEMINFO, u32s(&meminfo))]);
+
+        let socket = parse_socket(&message, libc::AF_INET);
+        assert_eq!(socket.state, 1);
+        assert_eq!(socket.timer, 1);
+        assert_eq!(socket.retransmits, 2);
+        assert_eq!(socket.local, ("10.0.0.1".parse().unwrap(), 443));
+        assert_eq!(socket.remote, ("10.0.0.2".parse().unwrap(), 51000));
+        assert_eq!(socket.expires_ms, 200);
+        assert_eq!((socket.rqueue, socket.wqueue), (3, 128));
+        assert_eq!(socket.uid, 1000);
+        assert_eq!(socket.inode, 987654);
+        assert_eq!(socket.drops, Some(7));
+
+        assert_eq!(parse_socket(&diag_message(&[]), libc::AF_INET).drops, None);
+    }
+
+    #[test]
+    fn tcp_info_and_congestion_attributes() {
+        let mut info = vec![0u8; TCPI_TOTAL_RETRANS + 4];
+        info[TCPI_RTT..TCPI_RTT + 4].copy_from_slice(&12_500u32.to_ne_bytes());
+        info[TCPI_RTTVAR..TCPI_RTTVAR + 4].copy_from_slice(&800u32.to_ne_bytes());
+        info[TCPI_SND_CWND..TCPI_SND_CWND + 4].copy_from_slice(&10u32.to_ne_bytes());
+        info[TCPI_TOTAL_RETRANS..TCPI_TOTAL_RETRANS + 4].copy_from_slice(&4u32.to_ne_bytes());
+        info[TCPI_UNACKED..TCPI_UNACKED + 4].copy_from_slice(&3u32.to_ne_bytes());
+        info[TCPI_SND_MSS..TCPI_SND_MSS + 4].copy_from_slice(&1448u32.to_ne_bytes());
+        let message = diag_message(&[(INET_DIAG_CONG, b"cubic\0".to_vec()), (INET_DIAG_INFO, info)]);
+
+        let info = find_attribute(&message, INET_DIAG_INFO).and_then(parse_tcp_info).unwrap();
+        assert_eq!(info.rtt_ms, 12.5);
+        assert_eq!(info.rttvar_ms, 0.8);
+        assert_eq!(info.snd_cwnd, 10);
+        assert_eq!(info.total_retrans, 4);
+        assert_eq!(info.bytes_in_flight, 3 * 1448);
+
+        let congestion = find_attribute(&message, INET_DIAG_CONG).and_then(parse_congestion);
+        assert_eq!(congestion.as_deref(), Some("cubic"));
+        assert!(find_attribute(&message, INET_DIAG_SKMEMINFO).is_none());
+
+        // Older kernels hand out a shorter tcp_info
+        assert!(parse_tcp_info(&[0u8; 40]).is_none());
+        assert_eq!(parse_congestion(b"\0"), None);
+    }
+
+    #[test]
+    fn truncated_attributes_are_ignored() {
+        let mut message = diag_message(&[]);
+        message.extend_from_slice(&64u16.to_ne_bytes());
+        message.extend_from_slice(&INET_DIAG_INFO.to_ne_bytes());
+        assert!(find_attribute(&message, INET_DIAG_INFO).is_none());
+    }
+
+    #[test]
+    fn messages_until_done() {
+        let mut data = netlink_message(SOCK_DIAG_BY_FAMILY, &diag_message(&[]));
+        data.extend(netlink_message(SOCK_DIAG_BY_FAMILY, &diag_message(&[])));
+
+        let mut inodes = Vec::new();
+        let done = parse_messages(&data, &mut |message| inodes.push(read_u32(message, DIAG_MSG_INODE))).unwrap();
+        assert!(!done);
+        assert_eq!(inodes, [987654, 987654]);
+
+        data.extend(netlink_message(libc::NLMSG_DONE as u16, &[0; 4]));
+        data.extend(netlink_message(SOCK_DIAG_BY_FAMILY, &diag_message(&[])));
+        inodes.clear();
+        let done = parse_messages(&data, &mut |message| inodes.push(read_u32(message, DIAG_MSG_INODE))).unwrap();
+        assert!(done);
+        assert_eq!(inodes.len(), 2);
+    }
+
+    #[test]
+    fn error_message_carries_errno() {
+        let data = netlink_message(libc::NLMSG_ERROR as u16, &(-libc::ENOENT).to_ne_bytes());
+        let err = parse_messages(&data, &mut |_| panic!("no payload expected")).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+    }
+
+    #[test]
+    fn short_or_bogus_lengths_stop_parsing() {
+        let mut data = netlink_message(SOCK_DIAG_BY_FAMILY, &diag_message(&[]));
+        data.truncate(40);
+        assert!(!parse_messages(&data, &mut |_| panic!("truncated message handled")).unwrap());
+
+        let mut data = netlink_message(SOCK_DIAG_BY_FAMILY, &[]);
+        data[..4].copy_from_slice(&4u32.to_ne_bytes());
+        assert!(!parse_messages(&data, &mut |_| panic!("undersized message handled")).unwrap());
+    }
+}
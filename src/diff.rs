@@ -0,0 +1,223 @@
+use crate::json::{self, Json};
+use crate::network::{Connection, ConnectionKey};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// --diff: compares two snapshots written by 'E', Ctrl+E or read off
+// --ipc-socket, by the same identity the baseline uses. Returns whether they
+// differ.
+pub fn diff_files(old: &Path, new: &Path) -> Result<bool> {
+    let old = load(old)?;
+    let new = load(new)?;
+    let diff = Diff::between(&old, &new);
+    // A closed pipe (`| head`) just ends the output
+    let _ = write_diff(&mut io::stdout().lock(), &diff);
+    Ok(!diff.is_empty())
+}
+
+struct Diff<'a> {
+    added: Vec<&'a Connection>,
+    removed: Vec<&'a Connection>,
+    // (old, new) for sockets whose state or owner changed
+    changed: Vec<(&'a Connection, &'a Connection)>,
+}
+
+impl<'a> Diff<'a> {
+    // New rows in the order of `new`, removed ones in the order of `old`
+    fn between(old: &'a [Connection], new: &'a [Connection]) -> Self {
+        let old_by_key: HashMap<ConnectionKey, &Connection> = old.iter().map(|conn| (conn.key(), conn)).collect();
+        let new_keys: HashSet<ConnectionKey> = new.iter().map(Connection::key).collect();
+
+        let mut diff = Diff { added: Vec::new(), removed: Vec::new(), changed: Vec::new() };
+        for conn in new {
+            match old_by_key.get(&conn.key()) {
+                None => diff.added.push(conn),
+                Some(before) if changed(before, conn) => diff.changed.push((before, conn)),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old.iter().filter(|conn| !new_keys.contains(&conn.key())).collect();
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn changed(old: &Connection, new: &Connection) -> bool {
+    old.state != new.state || old.pid != new.pid || old.process_name != new.process_name
+}
+
+fn write_diff(out: &mut dyn Write, diff: &Diff) -> io::Result<()> {
+    for conn in &diff.added {
+        writeln!(out, "+ {}", describe(conn, conn.state_display()))?;
+    }
+    for conn in &diff.removed {
+        writeln!(out, "- {}", describe(conn, conn.state_display()))?;
+    }
+    for (before, after) in &diff.changed {
+        let state = if before.state == after.state {
+            after.state_display().to_string()
+        } else {
+            format!("{} -> {}", before.state_display(), after.state_display())
+        };
+        let mut line = describe(after, &state);
+        if before.pid != after.pid || before.process_name != after.process_name {
+            line.push_str(&format!(" (was {})", before.pid_program()));
+        }
+        writeln!(out, "~ {}", line)?;
+    }
+    writeln!(out, "{} added, {} removed, {} changed", diff.added.len(), diff.removed.len(), diff.changed.len())
+}
+
+// "TCP 10.0.0.2:5432 <- 10.0.0.9:41236 ESTABLISHED 812/postgres"
+fn describe(conn: &Connection, state: &str) -> String {
+    let host = conn.host.as_deref().map(|host| format!("{}: ", host)).unwrap_or_default();
+    format!(
+        "{}{} {} <- {} {} {}",
+        host,
+        conn.protocol,
+        conn.local_display(),
+        conn.remote_display(),
+        state,
+        conn.pid_program()
+    )
+}
+
+// A snapshot object, or a capture of --ipc-socket's stream with one per
+// line, where the last one counts
+fn load(path: &Path) -> Result<Vec<Connection>> {
+    let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let result = match (lines.next(), lines.next_back()) {
+        // When neither reads, the whole file's error is the useful one; the
+        // last line of a pretty-printed snapshot is just "}"
+        (Some(_), Some(last)) => parse_snapshot(&content).or_else(|err| parse_snapshot(last).map_err(|_| err)),
+        _ => parse_snapshot(&content),
+    };
+    result.with_context(|| format!("in {}", path.display()))
+}
+
+fn parse_snapshot(text: &str) -> Result<Vec<Connection>> {
+    let value = json::parse(text)?;
+    let connections = match &value {
+        Json::Object(_) => value.get("connections").ok_or_else(|| anyhow!("no \"connections\" array"))?,
+        _ => &value,
+    };
+    let Json::Array(items) = connections else {
+        bail!("\"connections\" is not an array");
+    };
+    items.iter().enumerate().map(|(i, item)| connection(item).with_context(|| format!("connection {}", i))).collect()
+}
+
+// Field names as written by export::connection_json; fields added since an
+// older snapshot was taken are simply absent there
+fn connection(item: &Json) -> Result<Connection> {
+    if !matches!(item, Json::Object(_)) {
+        bail!("not an object");
+    }
+    let string = |name| item.get(name).and_then(Json::as_str).map(str::to_string);
+    let number = |name| item.get(name).and_then(Json::as_u64);
+    let port = |name| number(name).and_then(|port| u16::try_from(port).ok()).unwrap_or(0);
+
+    Ok(Connection {
+        protocol: string("protocol").ok_or_else(|| anyhow!("missing \"protocol\""))?,
+        local_address: string("local_address").unwrap_or_default(),
+        local_port: port("local_port"),
+        remote_address: string("remote_address").unwrap_or_default(),
+        remote_port: port("remote_port"),
+        state: string("state").unwrap_or_default(),
+        pid: number("pid").and_then(|pid| u32::try_from(pid).ok()),
+        process_name: string("process_name"),
+        uid: number("uid").and_then(|uid| u32::try_from(uid).ok()),
+        interface: string("interface"),
+        cgroup: string("cgroup"),
+        inode: number("inode").unwrap_or(0),
+        host: string("host"),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn(local_port: u16, state: &str, pid: Option<u32>) -> Connection {
+        Connection {
+            protocol: "TCP".to_string(),
+            local_address: "10.0.0.2".to_string(),
+            local_port,
+            remote_address: "10.0.0.9".to_string(),
+            remote_port: 41236,
+            state: state.to_string(),
+            pid,
+            process_name: pid.map(|_| "postgres".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_lists_added_removed_and_changed() {
+        let old = vec![
+            conn(1, "ESTABLISHED", Some(8)),
+            conn(2, "ESTABLISHED", Some(8)),
+            conn(3, "ESTABLISHED", Some(8)),
+            conn(4, "LISTEN", Some(8)),
+        ];
+        let new = vec![
+            conn(6, "ESTABLISHED", Some(8)),
+            conn(3, "CLOSE_WAIT", Some(8)),
+            conn(5, "ESTABLISHED", None),
+            conn(4, "LISTEN", Some(9)),
+            conn(1, "ESTABLISHED", Some(8)),
+        ];
+        let diff = Diff::between(&old, &new);
+
+        let ports = |conns: &[&Connection]| conns.iter().map(|conn| conn.local_port).collect::<Vec<_>>();
+        assert_eq!(ports(&diff.added), [6, 5]);
+        assert_eq!(ports(&diff.removed), [2]);
+        let changed: Vec<u16> = diff.changed.iter().map(|(_, after)| after.local_port).collect();
+        // A new owner with the same state counts as a change too
+        assert_eq!(changed, [3, 4]);
+
+        let mut out = Vec::new();
+        write_diff(&mut out, &diff).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("~ TCP 10.0.0.2:3 <- 10.0.0.9:41236 ESTABLISHED -> CLOSE_WAIT 8/postgres\n"), "{}", out);
+        assert!(out.contains("(was 8/postgres)"), "{}", out);
+        assert!(out.ends_with("2 added, 1 removed, 2 changed\n"), "{}", out);
+
+        assert!(Diff::between(&old, &old).is_empty());
+    }
+
+    fn load_text(name: &str, text: &str) -> Result<Vec<Connection>> {
+        let path = std::env::temp_dir().join(format!("netmon-tui-diff-{}-{}", name, std::process::id()));
+        fs::write(&path, text).unwrap();
+        let result = load(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn loads_snapshots_and_stream_captures() {
+        let row = |port| format!("{{\"protocol\": \"TCP\", \"local_port\": {}, \"state\": \"LISTEN\"}}", port);
+        let pretty = format!("{{\n  \"connections\": [\n    {},\n    {}\n  ]\n}}\n", row(22), row(80));
+        let loaded = load_text("pretty", &pretty).unwrap();
+        assert_eq!(loaded.iter().map(|conn| conn.local_port).collect::<Vec<_>>(), [22, 80]);
+
+        // One snapshot per line; only the last one counts
+        let stream = format!("{{\"connections\": [{}]}}\n{{\"connections\": [{}]}}\n", row(22), row(443));
+        let loaded = load_text("stream", &stream).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].local_port, 443);
+
+        // The error is about the broken row, not the closing brace
+        let broken = pretty.replace("\"protocol\": \"TCP\", ", "");
+        let err = load_text("broken", &broken).unwrap_err();
+        assert!(format!("{:#}", err).contains("missing \"protocol\""), "{:#}", err);
+    }
+}
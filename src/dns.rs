@@ -0,0 +1,344 @@
+use log::{debug, info, trace};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{CStr, OsString};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Upper bound on concurrent getnameinfo calls so a burst of new remotes
+// can't exhaust tokio's blocking pool. Calls that outlived their timeout
+// still count until they return.
+const MAX_IN_FLIGHT: usize = 8;
+
+// A lookup that takes longer than this is recorded as failed; the blocking
+// call itself can't be cancelled, but its late answer is ignored
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Entry {
+    // `None` records a failed lookup so we don't hammer the resolver for
+    // addresses without a PTR record
+    hostname: Option<String>,
+    resolved_at: SystemTime,
+}
+
+pub struct DnsCache {
+    entries: HashMap<IpAddr, Entry>,
+    // Every address with a lookup queued or running
+    pending: HashSet<IpAddr>,
+    queue: VecDeque<IpAddr>,
+    // getnameinfo calls still running, including ones already timed out
+    running: Arc<AtomicUsize>,
+    ttl: Duration,
+    path: Option<PathBuf>,
+    tx: Sender<(IpAddr, Option<String>)>,
+    rx: Receiver<(IpAddr, Option<String>)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl DnsCache {
+    pub fn new(ttl: Duration) -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            entries: HashMap::new(),
+            pending: HashSet::new(),
+            queue: VecDeque::new(),
+            running: Arc::new(AtomicUsize::new(0)),
+            ttl,
+            path: cache_file(),
+            tx,
+            rx,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    // Loads previously resolved names; expired entries are kept so they can
+    // be shown until the refresh lookup completes.
+    pub fn load(&mut self) {
+        let Some(content) = self.path.as_ref().and_then(|path| fs::read_to_string(path).ok()) else {
+            return;
+        };
+
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 3 {
+                continue;
+            }
+
+            let (Ok(ip), Ok(secs)) = (fields[0].parse::<IpAddr>(), fields[2].parse::<u64>()) else {
+                continue;
+            };
+
+            let hostname = (fields[1] != "-").then(|| fields[1].to_string());
+            self.entries.insert(ip, Entry {
+                hostname,
+                resolved_at: UNIX_EPOCH + Duration::from_secs(secs),
+            });
+        }
+
+        info!("loaded {} cached hostnames", self.entries.len());
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut content = String::new();
+        for (ip, entry) in &self.entries {
+            let secs = entry.resolved_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            content.push_str(&format!("{}\t{}\t{}\n", ip, entry.hostname.as_deref().unwrap_or("-"), secs));
+        }
+
+        info!("saving {} cached hostnames to {}", self.entries.len(), path.display());
+        fs::write(path, content)
+    }
+
+    pub fn hostname(&self, address: &str) -> Option<&str> {
+        let ip = address.parse::<IpAddr>().ok()?;
+        self.entries.get(&ip)?.hostname.as_deref()
+    }
+
+    // Queues a background PTR lookup unless a fresh answer is cached or one
+    // is already in flight.
+    pub fn request(&mut self, address: &str) {
+        let Ok(ip) = address.parse::<IpAddr>() else {
+            return;
+        };
+
+        if ip.is_unspecified() || self.pending.contains(&ip) {
+            return;
+        }
+
+        if let Some(entry) = self.entries.get(&ip) {
+            let age = entry.resolved_at.elapsed().unwrap_or(Duration::ZERO);
+            if age < self.ttl {
+                self.hits += 1;
+                return;
+            }
+        }
+
+        self.misses += 1;
+        self.pending.insert(ip);
+        self.queue.push_back(ip);
+        self.dispatch();
+    }
+
+    // Starts queued lookups while there is room in the pool
+    fn dispatch(&mut self) {
+        while self.running.load(Ordering::SeqCst) < MAX_IN_FLIGHT {
+            let Some(ip) = self.queue.pop_front() else {
+                return;
+            };
+
+            trace!("resolving {}", ip);
+            self.running.fetch_add(1, Ordering::SeqCst);
+            let running = self.running.clone();
+            let tx = self.tx.clone();
+            tokio::spawn(async move {
+                let lookup = tokio::task::spawn_blocking(move || {
+                    let hostname = reverse_lookup(ip);
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    hostname
+                });
+                let hostname = match tokio::time::timeout(LOOKUP_TIMEOUT, lookup).await {
+                    Ok(Ok(hostname)) => hostname,
+                    Ok(Err(_)) => None,
+                    Err(_) => {
+                        debug!("lookup for {} timed out", ip);
+                        None
+                    }
+                };
+                let _ = tx.send((ip, hostname));
+            });
+        }
+    }
+
+    // Lookups queued or running
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    // Fraction of lookups answered from a fresh cache entry
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Moves finished lookups into the cache and starts queued ones. Called
+    // once per loop iteration.
+    pub fn collect(&mut self) {
+        while let Ok((ip, hostname)) = self.rx.try_recv() {
+            debug!("resolved {} -> {:?}", ip, hostname);
+            self.pending.remove(&ip);
+            self.entries.insert(ip, Entry {
+                hostname,
+                resolved_at: SystemTime::now(),
+            });
+        }
+
+        self.dispatch();
+    }
+}
+
+fn cache_file() -> Option<PathBuf> {
+    cache_file_in(std::env::var_os("XDG_CACHE_HOME"), std::env::var_os("HOME"))
+}
+
+fn cache_file_in(xdg_cache_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    let base = match xdg_cache_home {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(home?).join(".cache"),
+    };
+    Some(base.join("netmon-tui").join("dns-cache"))
+}
+
+fn reverse_lookup(ip: IpAddr) -> Option<String> {
+    let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+
+    // SAFETY: the sockaddr structs are fully initialised for their family and
+    // `host` is a writable buffer whose length we pass alongside it.
+    let rc = unsafe {
+        match ip {
+            IpAddr::V4(v4) => {
+                let mut sa: libc::sockaddr_in = std::mem::zeroed();
+                sa.sin_family = libc::AF_INET as libc::sa_family_t;
+                sa.sin_addr.s_addr = u32::from_ne_bytes(v4.octets());
+                libc::getnameinfo(
+                    &sa as *const libc::sockaddr_in as *const libc::sockaddr,
+                    std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+            IpAddr::V6(v6) => {
+                let mut sa: libc::sockaddr_in6 = std::mem::zeroed();
+                sa.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sa.sin6_addr.s6_addr = v6.octets();
+                libc::getnameinfo(
+                    &sa as *const libc::sockaddr_in6 as *const libc::sockaddr,
+                    std::mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+        }
+    };
+
+    if rc != 0 {
+        return None;
+    }
+
+    // SAFETY: getnameinfo NUL-terminates `host` on success
+    let name = unsafe { CStr::from_ptr(host.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_file_prefers_xdg_cache_home() {
+        assert_eq!(
+            cache_file_in(Some("/xdg".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/xdg/netmon-tui/dns-cache"))
+        );
+        assert_eq!(
+            cache_file_in(Some("".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/home/me/.cache/netmon-tui/dns-cache"))
+        );
+        assert_eq!(
+            cache_file_in(None, Some("/home/me".into())),
+            Some(PathBuf::from("/home/me/.cache/netmon-tui/dns-cache"))
+        );
+        assert_eq!(cache_file_in(None, None), None);
+    }
+
+    #[tokio::test]
+    async fn timed_out_lookups_still_hold_a_slot() {
+        let mut cache = DnsCache::new(Duration::from_secs(3600));
+        cache.running.store(MAX_IN_FLIGHT, Ordering::SeqCst);
+
+        cache.request("192.0.2.1");
+        cache.collect();
+        assert_eq!(cache.queue.len(), 1);
+        assert_eq!(cache.pending_count(), 1);
+
+        // A stuck getnameinfo returning frees its slot for the queue
+        cache.running.fetch_sub(1, Ordering::SeqCst);
+        cache.collect();
+        assert!(cache.queue.is_empty());
+        assert!(cache.running.load(Ordering::SeqCst) <= MAX_IN_FLIGHT);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("netmon-tui-dns-test-{}", std::process::id()));
+        let path = dir.join("dns-cache");
+
+        let mut cache = DnsCache::new(Duration::from_secs(3600));
+        cache.path = Some(path.clone());
+        let resolved_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        cache.entries.insert("10.0.0.1".parse().unwrap(), Entry {
+            hostname: Some("gateway.lan".to_string()),
+            resolved_at,
+        });
+        cache.entries.insert("2001:db8::1".parse().unwrap(), Entry {
+            hostname: None,
+            resolved_at,
+        });
+        cache.save().unwrap();
+
+        let mut loaded = DnsCache::new(Duration::from_secs(3600));
+        loaded.path = Some(path);
+        loaded.load();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.entry_count(), 2);
+        assert_eq!(loaded.hostname("10.0.0.1"), Some("gateway.lan"));
+        assert_eq!(loaded.hostname("2001:db8::1"), None);
+        let failed = &loaded.entries[&"2001:db8::1".parse::<IpAddr>().unwrap()];
+        assert!(failed.hostname.is_none());
+        assert_eq!(failed.resolved_at, resolved_at);
+    }
+
+    #[test]
+    fn load_skips_malformed_lines() {
+        let dir = std::env::temp_dir().join(format!("netmon-tui-dns-bad-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dns-cache");
+        fs::write(
+            &path,
+            "10.0.0.1\thost.lan\t100\nnot-an-ip\tx\t1\n10.0.0.2\ty\tsoon\n10.0.0.3\tz\n",
+        )
+        .unwrap();
+
+        let mut cache = DnsCache::new(Duration::from_secs(3600));
+        cache.path = Some(path);
+        cache.load();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cache.entry_count(), 1);
+        assert_eq!(cache.hostname("10.0.0.1"), Some("host.lan"));
+    }
+}
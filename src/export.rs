@@ -0,0 +1,184 @@
+use crate::network::Connection;
+use std::fmt::Write;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// serde_json isn't a dependency, so connections are encoded by hand. Field
+// names match the `Connection` struct.
+pub fn connection_json(conn: &Connection) -> String {
+    let mut out = String::from("{");
+    let _ = write!(
+        out,
+        "\"protocol\":{},\"local_address\":{},\"local_port\":{},\"remote_address\":{},\"remote_port\":{},\"state\":{},\"pid\":{},\"process_name\":{},\"uid\":{},\"interface\":{},\"cgroup\":{},\"inode\":{},\"host\":{}",
+        json_string(&conn.protocol),
+        json_string(&conn.local_address),
+        conn.local_port,
+        json_string(&conn.remote_address),
+        conn.remote_port,
+        json_string(&conn.state),
+        json_option(conn.pid),
+        json_option(conn.process_name.as_deref().map(json_string)),
+        json_option(conn.uid),
+        json_option(conn.interface.as_deref().map(json_string)),
+        json_option(conn.cgroup.as_deref().map(json_string)),
+        json_option(Some(conn.inode).filter(|inode| *inode != 0)),
+        json_option(conn.host.as_deref().map(json_string)),
+    );
+    out.push('}');
+    out
+}
+
+pub fn connections_json(connections: &[&Connection]) -> String {
+    let items: Vec<String> = connections.iter().map(|conn| connection_json(conn)).collect();
+    format!("[{}]", items.join(","))
+}
+
+// {"timestamp":<unix secs>,"connections":[...]}, as streamed over IPC and
+// written by 'E'
+pub fn snapshot_json(connections: &[&Connection]) -> String {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    format!("{{\"timestamp\":{},\"connections\":{}}}", timestamp, connections_json(connections))
+}
+
+// Writes a snapshot to netmon-<unix secs>.json in the working directory
+pub fn write_snapshot(connections: &[&Connection]) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let path = PathBuf::from(format!("netmon-{}.json", timestamp));
+    std::fs::write(&path, snapshot_json(connections) + "\n")?;
+    Ok(path)
+}
+
+// A GitHub-flavored Markdown table. Pipes are escaped so a process name
+// like "a|b" stays in its cell.
+pub fn markdown_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+    let escape = |cell: &str| cell.replace('|', "\\|");
+
+    let mut out = line(headers.iter().map(|header| escape(header)).collect());
+    out.push_str(&line(headers.iter().map(|_| "---".to_string()).collect()));
+    for row in rows {
+        out.push_str(&line(row.iter().map(|cell| escape(cell)).collect()));
+    }
+    out
+}
+
+// There is no clipboard library among the dependencies, so the terminal does
+// the copying: OSC 52 is understood by most terminal emulators (tmux needs
+// `set-clipboard on`) and works over SSH too
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len() / 3 * 4 + 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn json_option<T: std::fmt::Display>(value: Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("line\nbreak\ttab\r"), "\"line\\nbreak\\ttab\\r\"");
+        assert_eq!(json_string("\x1b[31m\x00"), "\"\\u001b[31m\\u0000\"");
+        assert_eq!(json_string("naïve ✓"), "\"naïve ✓\"");
+    }
+
+    #[test]
+    fn connection_fields_and_nulls() {
+        let conn = Connection {
+            protocol: "TCP".to_string(),
+            local_address: "127.0.0.1".to_string(),
+            local_port: 8080,
+            remote_address: "0.0.0.0".to_string(),
+            state: "LISTEN".to_string(),
+            pid: Some(42),
+            process_name: Some("my \"server\"".to_string()),
+            uid: Some(1000),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            connection_json(&conn),
+            concat!(
+                "{\"protocol\":\"TCP\",\"local_address\":\"127.0.0.1\",\"local_port\":8080,",
+                "\"remote_address\":\"0.0.0.0\",\"remote_port\":0,\"state\":\"LISTEN\",\"pid\":42,",
+                "\"process_name\":\"my \\\"server\\\"\",\"uid\":1000,\"interface\":null,\"cgroup\":null,",
+                "\"inode\":null,\"host\":null}"
+            )
+        );
+        assert_eq!(connections_json(&[]), "[]");
+        assert_eq!(connections_json(&[&conn, &conn]).matches("\"protocol\"").count(), 2);
+    }
+
+    #[test]
+    fn output_parses_back() {
+        let conn = Connection {
+            protocol: "UDP".to_string(),
+            process_name: Some("tab\there\u{7}".to_string()),
+            inode: 1 << 40,
+            ..Default::default()
+        };
+        let snapshot = crate::json::parse(&snapshot_json(&[&conn])).unwrap();
+        let Some(crate::json::Json::Array(items)) = snapshot.get("connections") else {
+            panic!("no connections array");
+        };
+        assert_eq!(items[0].get("process_name").and_then(|v| v.as_str()), Some("tab\there\u{7}"));
+        assert_eq!(items[0].get("inode").and_then(|v| v.as_u64()), Some(1 << 40));
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,214 @@
+use crate::network::Connection;
+use crate::regex::Regex;
+use std::net::IpAddr;
+
+pub const FILTER_HELP: &str =
+    "'*' matches any run of chars, '?' a single char; without them the text matches as a substring; '~' starts a regex";
+
+#[derive(Default)]
+pub struct Filter {
+    pattern: String,
+    glob: bool,
+    regex: Option<Regex>,
+}
+
+impl Filter {
+    // A leading '~' makes the rest a case-insensitive regex, matched against
+    // the whole "proto local remote state pid/program" line rather than
+    // field by field. Fails only for a bad regex.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let regex = match pattern.strip_prefix('~') {
+            Some(regex) => Some(Regex::new(regex)?),
+            None => None,
+        };
+        Ok(Self {
+            pattern: pattern.to_lowercase(),
+            glob: pattern.contains(['*', '?']),
+            regex,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    pub fn matches(&self, conn: &Connection) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        if let Some(regex) = &self.regex {
+            return regex.is_match(&regex_line(conn));
+        }
+
+        let local = format!("{}:{}", conn.local_address, conn.local_port);
+        let remote = format!("{}:{}", conn.remote_address, conn.remote_port);
+        let fields = [
+            conn.process_name.as_deref().unwrap_or(""),
+            local.as_str(),
+            remote.as_str(),
+            conn.host.as_deref().unwrap_or(""),
+        ];
+
+        fields.iter().any(|field| self.matches_field(field))
+    }
+
+    fn matches_field(&self, field: &str) -> bool {
+        let field = field.to_lowercase();
+        if self.glob {
+            glob_match(&self.pattern, &field)
+        } else {
+            field.contains(&self.pattern)
+        }
+    }
+}
+
+fn regex_line(conn: &Connection) -> String {
+    let owner = match (conn.pid, &conn.process_name) {
+        (Some(pid), Some(name)) => format!("{}/{}", pid, name),
+        (Some(pid), None) => pid.to_string(),
+        (None, Some(name)) => name.clone(),
+        (None, None) => "-".to_string(),
+    };
+    format!(
+        "{} {}:{} {}:{} {} {}",
+        conn.protocol, conn.local_address, conn.local_port, conn.remote_address, conn.remote_port, conn.state, owner
+    )
+}
+
+// Anchored glob match supporting '*' and '?'. Backtracks to the most recent
+// '*' on mismatch, which keeps it linear for the usual `foo*`/`*foo*` shapes.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Address family restriction cycled with 'f'
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FamilyFilter {
+    #[default]
+    Both,
+    V4,
+    V6,
+}
+
+impl FamilyFilter {
+    pub fn cycle(self) -> Self {
+        match self {
+            FamilyFilter::Both => FamilyFilter::V4,
+            FamilyFilter::V4 => FamilyFilter::V6,
+            FamilyFilter::V6 => FamilyFilter::Both,
+        }
+    }
+
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            FamilyFilter::Both => None,
+            FamilyFilter::V4 => Some("IPv4 only"),
+            FamilyFilter::V6 => Some("IPv6 only"),
+        }
+    }
+
+    // Goes by the local address rather than the "6" protocol suffix, so
+    // v4-mapped addresses on TCP6/UDP6 sockets count as IPv4. Non-IP sockets
+    // (UNIX) only show with both families.
+    pub fn matches(self, conn: &Connection) -> bool {
+        let is_v6 = match conn.local_address.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => false,
+            Ok(IpAddr::V6(v6)) => v6.to_ipv4_mapped().is_none(),
+            Err(_) => return self == FamilyFilter::Both,
+        };
+
+        match self {
+            FamilyFilter::Both => true,
+            FamilyFilter::V4 => !is_v6,
+            FamilyFilter::V6 => is_v6,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str) -> Connection {
+        Connection {
+            protocol: "TCP".to_string(),
+            local_address: "127.0.0.1".to_string(),
+            local_port: 8080,
+            remote_address: "10.0.0.9".to_string(),
+            remote_port: 443,
+            process_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn glob_star_and_question_mark() {
+        assert!(glob_match("nginx*", "nginx"));
+        assert!(glob_match("nginx*", "nginx-worker"));
+        assert!(glob_match("*python*", "python3"));
+        assert!(glob_match("*python*", "/usr/bin/python3.11"));
+        assert!(glob_match("py?hon", "python"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("nginx*", "openresty-nginx"));
+        assert!(!glob_match("py?hon", "pyhon"));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn glob_backtracks_to_the_last_star() {
+        assert!(glob_match("*a*b", "aaab"));
+        assert!(glob_match("*ab*ab", "abxabyab"));
+        assert!(!glob_match("*a*b", "aaba"));
+        assert!(glob_match("a*?c", "abbc"));
+        assert!(!glob_match("a*?c", "ac"));
+    }
+
+    #[test]
+    fn plain_text_matches_as_substring() {
+        let filter = Filter::new("GINX").unwrap();
+        assert!(filter.matches(&process("nginx")));
+        assert!(!filter.matches(&process("sshd")));
+    }
+
+    #[test]
+    fn globs_are_anchored_per_field() {
+        let filter = Filter::new("ngin*").unwrap();
+        assert!(filter.matches(&process("nginx")));
+        assert!(!filter.matches(&process("my-nginx")));
+
+        // Addresses are matched as "address:port"
+        assert!(Filter::new("*:8080").unwrap().matches(&process("sshd")));
+        assert!(Filter::new("10.0.0.?:443").unwrap().matches(&process("sshd")));
+        assert!(!Filter::new("10.0.0.*:80").unwrap().matches(&process("sshd")));
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = Filter::new("").unwrap();
+        assert!(filter.is_empty());
+        assert!(filter.matches(&process("anything")));
+    }
+}
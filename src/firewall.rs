@@ -0,0 +1,725 @@
+use crate::network::Connection;
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+// Rules rarely change, and reading them forks up to three programs
+const REFRESH: Duration = Duration::from_secs(30);
+
+// Jumps into user chains are followed this deep, plenty for what ufw and
+// firewalld generate
+const MAX_JUMPS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    V4,
+    V6,
+    // nftables "inet" tables see both
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Accept,
+    Drop,
+}
+
+#[derive(Debug, Clone)]
+enum Target {
+    Verdict(Verdict),
+    Jump(String),
+    Return,
+    // LOG, counters and anything else that lets the packet carry on
+    Continue,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    // `None` matches every protocol
+    protocol: Option<String>,
+    // Inclusive ranges; `None` matches every port
+    ports: Option<Vec<(u16, u16)>>,
+    target: Target,
+}
+
+impl Rule {
+    fn matches(&self, protocol: &str, port: u16) -> bool {
+        if self.protocol.as_deref().is_some_and(|p| p != protocol) {
+            return false;
+        }
+        match &self.ports {
+            Some(ports) => ports.iter().any(|(low, high)| (*low..=*high).contains(&port)),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Chain {
+    // Only base chains have one; `None` there means accept
+    policy: Option<Verdict>,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Table {
+    family: Family,
+    chains: HashMap<String, Chain>,
+    // Chains the kernel runs for packets addressed to this host
+    inputs: Vec<String>,
+}
+
+impl Table {
+    fn verdict(&self, protocol: &str, port: u16) -> Verdict {
+        // Every input chain a packet passes has to let it through
+        let blocked = self.inputs.iter().any(|name| {
+            let chain = &self.chains[name];
+            self.walk(name, protocol, port, 0).or(chain.policy).unwrap_or(Verdict::Accept) == Verdict::Drop
+        });
+        if blocked {
+            Verdict::Drop
+        } else {
+            Verdict::Accept
+        }
+    }
+
+    // `None` when the chain ends or returns without a verdict
+    fn walk(&self, name: &str, protocol: &str, port: u16, depth: usize) -> Option<Verdict> {
+        let chain = self.chains.get(name)?;
+        for rule in chain.rules.iter().filter(|rule| rule.matches(protocol, port)) {
+            match &rule.target {
+                Target::Verdict(verdict) => return Some(*verdict),
+                Target::Jump(target) if depth < MAX_JUMPS => {
+                    if let Some(verdict) = self.walk(target, protocol, port, depth + 1) {
+                        return Some(verdict);
+                    }
+                }
+                Target::Jump(_) | Target::Continue => {}
+                Target::Return => return None,
+            }
+        }
+        None
+    }
+}
+
+// One read of the rules: the tables, and the tools that answered (for the
+// footer) or the error when none did
+struct Reading {
+    tables: Vec<Table>,
+    sources: Result<Vec<&'static str>, String>,
+}
+
+// The local filter rules, read from `iptables-save`, `ip6tables-save` and
+// `nft list ruleset` (whichever run), and a guess from them at whether a
+// new connection from another host to a port would be let in. This is a
+// heuristic: rules that only admit some sources or interfaces, or that
+// match on anything this doesn't understand (sets, rate limits, marks,
+// negations), are passed over, so a port opened that way reads as blocked
+// and one closed that way reads as open. Reading the rules needs root.
+pub struct Firewall {
+    reading: Reading,
+    pending: bool,
+    read_at: Option<Instant>,
+    tx: Sender<Reading>,
+    rx: Receiver<Reading>,
+}
+
+impl Default for Firewall {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let reading = Reading { tables: Vec::new(), sources: Ok(Vec::new()) };
+        Self { reading, pending: false, read_at: None, tx, rx }
+    }
+}
+
+impl Firewall {
+    // Starts a background read when the last one is stale
+    pub fn refresh(&mut self) {
+        if self.pending || self.read_at.is_some_and(|at| at.elapsed() < REFRESH) {
+            return;
+        }
+        self.pending = true;
+
+        let tx = self.tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = tx.send(read_rules());
+        });
+    }
+
+    // Files a finished read. Called once per loop iteration.
+    pub fn collect(&mut self) {
+        while let Ok(reading) = self.rx.try_recv() {
+            self.reading = reading;
+            self.pending = false;
+            self.read_at = Some(Instant::now());
+        }
+    }
+
+    // True for an exposed TCP or UDP listener the rules seem to keep
+    // other hosts from reaching
+    pub fn blocks(&self, conn: &Connection) -> bool {
+        if conn.host.is_some() || !conn.is_listening() || !conn.is_exposed() {
+            return false;
+        }
+        let (protocol, family) = match conn.protocol.as_str() {
+            "TCP" => ("tcp", Family::V4),
+            "TCP6" => ("tcp", Family::V6),
+            "UDP" => ("udp", Family::V4),
+            "UDP6" => ("udp", Family::V6),
+            _ => return false,
+        };
+        self.reading
+            .tables
+            .iter()
+            .filter(|table| table.family == family || table.family == Family::Both)
+            .any(|table| table.verdict(protocol, conn.local_port) == Verdict::Drop)
+    }
+
+    // For the footer
+    pub fn describe(&self) -> String {
+        match &self.reading.sources {
+            Ok(sources) if sources.is_empty() => "Firewall: reading rules".to_string(),
+            Ok(sources) => format!("Firewall: {} rules (heuristic)", sources.join(", ")),
+            Err(err) => format!("Firewall: {}", err),
+        }
+    }
+
+    pub fn sources(&self) -> &[&'static str] {
+        self.reading.sources.as_deref().unwrap_or(&[])
+    }
+}
+
+fn read_rules() -> Reading {
+    let mut tables = Vec::new();
+    let mut sources = Vec::new();
+    let mut errors = Vec::new();
+
+    for (program, args, family) in [
+        ("iptables-save", &["-t", "filter"][..], Family::V4),
+        ("ip6tables-save", &["-t", "filter"][..], Family::V6),
+        ("nft", &["list", "ruleset"][..], Family::Both),
+    ] {
+        match run(program, args) {
+            Ok(text) => {
+                let before = tables.len();
+                if program == "nft" {
+                    tables.extend(parse_nft(&text, !sources.is_empty()));
+                } else {
+                    tables.extend(parse_iptables(&text, family));
+                }
+                debug!("{}: {} filter tables", program, tables.len() - before);
+                sources.push(program);
+            }
+            Err(err) => {
+                debug!("firewall rules: {:#}", err);
+                errors.push(format!("{:#}", err));
+            }
+        }
+    }
+
+    if sources.is_empty() {
+        let reason = errors.first().cloned().unwrap_or_default();
+        return Reading { tables, sources: Err(format!("can't read rules: {}", reason)) };
+    }
+    Reading { tables, sources: Ok(sources) }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program).args(args).output().with_context(|| format!("cannot run {}", program))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+        bail!("{} exited with {}: {}", program, output.status, reason.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// The filter table from `iptables-save`:
+//   *filter
+//   :INPUT DROP [0:0]
+//   -A INPUT -i lo -j ACCEPT
+//   -A INPUT -p tcp -m multiport --dports 80,443 -j ACCEPT
+//   COMMIT
+fn parse_iptables(text: &str, family: Family) -> Option<Table> {
+    let mut chains: HashMap<String, Chain> = HashMap::new();
+    let mut in_filter = false;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('*') {
+            in_filter = name == "filter";
+        } else if !in_filter {
+            continue;
+        } else if let Some(declaration) = line.strip_prefix(':') {
+            let mut fields = declaration.split_whitespace();
+            let (Some(name), Some(policy)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let policy = match policy {
+                "DROP" | "REJECT" => Some(Verdict::Drop),
+                "ACCEPT" => Some(Verdict::Accept),
+                _ => None,
+            };
+            chains.entry(name.to_string()).or_default().policy = policy;
+        } else if let Some(rule) = line.strip_prefix("-A ") {
+            let words = split_words(rule);
+            let Some((chain, options)) = words.split_first() else {
+                continue;
+            };
+            if let Some(rule) = parse_iptables_rule(options) {
+                chains.entry(chain.clone()).or_default().rules.push(rule);
+            }
+        }
+    }
+
+    chains.contains_key("INPUT").then(|| Table { family, chains, inputs: vec!["INPUT".to_string()] })
+}
+
+// `None` for rules the heuristic passes over
+fn parse_iptables_rule(options: &[String]) -> Option<Rule> {
+    let mut rule = Rule { protocol: None, ports: None, target: Target::Continue };
+    let mut options = options.iter().map(String::as_str);
+
+    while let Some(option) = options.next() {
+        match option {
+            "-p" | "--protocol" => rule.protocol = Some(options.next()?.to_lowercase()),
+            "--dport" | "--destination-port" | "--dports" | "--destination-ports" => {
+                rule.ports = Some(parse_ports(options.next()?, ':')?);
+            }
+            "-j" | "--jump" | "-g" | "--goto" => {
+                rule.target = match options.next()? {
+                    "ACCEPT" => Target::Verdict(Verdict::Accept),
+                    "DROP" | "REJECT" => Target::Verdict(Verdict::Drop),
+                    "RETURN" => Target::Return,
+                    // Targets such as LOG aren't chains, and walking into
+                    // one finds nothing
+                    chain => Target::Jump(chain.to_string()),
+                };
+            }
+            // Loopback traffic never comes from another host, and a rule for
+            // any other interface says nothing about the rest, as with -s
+            "-i" | "--in-interface" => return None,
+            "-m" | "--match" | "--comment" | "--reject-with" | "--log-prefix" | "--log-level" => {
+                options.next()?;
+            }
+            "--syn" => {}
+            // Only a rule that also applies to new connections says
+            // anything about a listener
+            "--state" | "--ctstate" => {
+                if !options.next()?.split(',').any(|state| state == "NEW") {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(rule)
+}
+
+// `nft list ruleset`:
+//   table inet filter {
+//       chain input {
+//           type filter hook input priority filter; policy drop;
+//           iif "lo" accept
+//           tcp dport { 22, 80 } accept
+//       }
+//   }
+//
+// iptables-nft keeps its rules in "ip filter" and "ip6 filter" tables, so
+// with `xtables` set (the *tables-save tools answered) those are left out
+// rather than read twice, once in a translation this may not follow.
+fn parse_nft(text: &str, xtables: bool) -> Vec<Table> {
+    let mut tables = Vec::new();
+    let mut table: Option<Table> = None;
+    let mut chain: Option<String> = None;
+    // Depth of set, map and flowtable blocks being skipped. Braces are
+    // counted per line, since an "elements = { ... }" list can open on one
+    // line and close on another.
+    let mut skipping = 0;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if skipping > 0 {
+            skipping = (skipping + opened(line)).saturating_sub(closed(line));
+            continue;
+        }
+
+        let words = split_words(line);
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match (words.as_slice(), &mut table) {
+            (["table", family, name, "{"], _) => {
+                let family = match (*family, *name) {
+                    ("ip" | "ip6", "filter") if xtables => None,
+                    ("ip", _) => Some(Family::V4),
+                    ("ip6", _) => Some(Family::V6),
+                    ("inet", _) => Some(Family::Both),
+                    _ => None,
+                };
+                table = family.map(|family| Table { family, chains: HashMap::new(), inputs: Vec::new() });
+                if table.is_none() {
+                    skipping = 1;
+                }
+            }
+            (["chain", name, "{"], Some(table)) => {
+                table.chains.entry(name.to_string()).or_default();
+                chain = Some(name.to_string());
+            }
+            ([kind, ..], Some(_)) if chain.is_none() && ["set", "map", "flowtable", "counter", "quota", "limit"].contains(kind) => {
+                skipping = opened(line).saturating_sub(closed(line));
+            }
+            (["}"], _) if chain.is_some() => chain = None,
+            (["}"], _) => tables.extend(table.take().filter(|table| !table.inputs.is_empty())),
+            (["type", ..], Some(table)) => {
+                let Some(name) = chain.as_ref() else {
+                    continue;
+                };
+                let statement = line.replace(';', " ;");
+                let statement: Vec<&str> = statement.split_whitespace().collect();
+                let hooked = statement.windows(3).any(|w| w[0] == "filter" && w[1] == "hook" && w[2] == "input");
+                if hooked {
+                    table.inputs.push(name.clone());
+                }
+                let policy = statement.windows(2).find(|w| w[0] == "policy").map(|w| w[1]);
+                table.chains.entry(name.clone()).or_default().policy = match policy {
+                    Some("drop") => Some(Verdict::Drop),
+                    Some("accept") => Some(Verdict::Accept),
+                    _ => None,
+                };
+            }
+            (_, Some(table)) => {
+                let Some(name) = chain.as_ref() else {
+                    continue;
+                };
+                if let Some(rule) = parse_nft_rule(&words) {
+                    table.chains.entry(name.clone()).or_default().rules.push(rule);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tables
+}
+
+// Braces outside quoted strings
+fn opened(line: &str) -> usize {
+    count_unquoted(line, '{')
+}
+
+fn closed(line: &str) -> usize {
+    count_unquoted(line, '}')
+}
+
+fn count_unquoted(line: &str, brace: char) -> usize {
+    let mut quoted = false;
+    line.chars()
+        .filter(|&c| {
+            quoted ^= c == '"';
+            !quoted && c == brace
+        })
+        .count()
+}
+
+// `None` for rules the heuristic passes over
+fn parse_nft_rule(words: &[&str]) -> Option<Rule> {
+    let mut rule = Rule { protocol: None, ports: None, target: Target::Continue };
+    let mut words = words.iter().copied().peekable();
+
+    while let Some(word) = words.next() {
+        match word {
+            "tcp" | "udp" | "th" => {
+                if word != "th" {
+                    rule.protocol = Some(word.to_string());
+                }
+                match words.next()? {
+                    "dport" => rule.ports = Some(parse_nft_ports(&mut words)?),
+                    // "tcp flags syn" and the like
+                    "flags" => {
+                        words.next()?;
+                    }
+                    _ => return None,
+                }
+            }
+            "meta" => match (words.next()?, words.next()?) {
+                ("l4proto", protocol @ ("tcp" | "udp")) => rule.protocol = Some(protocol.to_string()),
+                // Interface rules are passed over, as for iptables' -i
+                _ => return None,
+            },
+            "iif" | "iifname" => return None,
+            "ct" => {
+                if words.next()? != "state" || !words.next()?.split(',').any(|state| state == "new") {
+                    return None;
+                }
+            }
+            "counter" => {
+                // "counter packets 12 bytes 3400"
+                while words.peek().is_some_and(|next| *next == "packets" || *next == "bytes") {
+                    words.next();
+                    words.next()?;
+                }
+            }
+            "comment" => {
+                words.next()?;
+            }
+            "log" => {
+                // "log prefix \"...\" level info"
+                while words.peek().is_some_and(|next| ["prefix", "level", "flags", "group"].contains(next)) {
+                    words.next();
+                    words.next()?;
+                }
+            }
+            "accept" => rule.target = Target::Verdict(Verdict::Accept),
+            "drop" => rule.target = Target::Verdict(Verdict::Drop),
+            "reject" => {
+                rule.target = Target::Verdict(Verdict::Drop);
+                // "reject with icmp type port-unreachable"
+                if words.peek() == Some(&"with") {
+                    words.by_ref().for_each(drop);
+                }
+            }
+            "jump" | "goto" => rule.target = Target::Jump(words.next()?.to_string()),
+            "return" => rule.target = Target::Return,
+            _ => return None,
+        }
+    }
+
+    Some(rule)
+}
+
+// "22", "1000-2000" or "{ 80, 443, 8000-8080 }"; named sets ("@web") aren't
+// looked up
+fn parse_nft_ports<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Vec<(u16, u16)>> {
+    let first = words.next()?;
+    if first != "{" {
+        return parse_ports(first, '-');
+    }
+
+    let mut ports = Vec::new();
+    for word in words.by_ref() {
+        if word == "}" {
+            return Some(ports);
+        }
+        ports.extend(parse_ports(word, '-')?);
+    }
+    None
+}
+
+// "80", "80,443" or "8000<sep>8080" ranges, comma-separated
+fn parse_ports(text: &str, range_separator: char) -> Option<Vec<(u16, u16)>> {
+    text.split(',')
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.split_once(range_separator) {
+            Some((low, high)) => Some((low.parse().ok()?, high.parse().ok()?)),
+            None => part.parse().ok().map(|port| (port, port)),
+        })
+        .collect()
+}
+
+// Whitespace-separated, keeping double-quoted strings (comments, log
+// prefixes) as one word
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iptables(rules: &str) -> Table {
+        parse_iptables(&format!("*filter\n:INPUT DROP [0:0]\n{}\nCOMMIT\n", rules), Family::V4).unwrap()
+    }
+
+    // As ufw lays it out: INPUT hands over to its own chains, and user rules
+    // sit two jumps down
+    const UFW: &str = "\
+*filter
+:INPUT DROP [0:0]
+:FORWARD DROP [0:0]
+:OUTPUT ACCEPT [0:0]
+:ufw-before-input - [0:0]
+:ufw-user-input - [0:0]
+-A INPUT -j ufw-before-input
+-A ufw-before-input -i lo -j ACCEPT
+-A ufw-before-input -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT
+-A ufw-before-input -j ufw-user-input
+-A ufw-user-input -p tcp -m tcp --dport 22 -j ACCEPT
+-A ufw-user-input -p tcp -m multiport --dports 80,443,8000:8010 -j ACCEPT
+-A ufw-user-input -p udp --dport 53 -m comment --comment \"dns server\" -j ACCEPT
+-A ufw-user-input -p tcp --dport 25 -m conntrack --ctstate NEW,ESTABLISHED -j ACCEPT
+-A ufw-user-input -p tcp --dport 9000 -j RETURN
+-A ufw-user-input -p tcp --dport 9000 -j ACCEPT
+COMMIT
+*nat
+:PREROUTING ACCEPT [0:0]
+-A PREROUTING -p tcp --dport 3306 -j ACCEPT
+COMMIT
+";
+
+    #[test]
+    fn walks_ufw_style_chains() {
+        let table = parse_iptables(UFW, Family::V4).unwrap();
+        assert_eq!(table.inputs, ["INPUT"]);
+        assert_eq!(table.chains["INPUT"].policy, Some(Verdict::Drop));
+
+        assert_eq!(table.verdict("tcp", 22), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 443), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 8005), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 8011), Verdict::Drop);
+        assert_eq!(table.verdict("udp", 53), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 53), Verdict::Drop);
+        // Accepts new connections as well as established ones
+        assert_eq!(table.verdict("tcp", 25), Verdict::Accept);
+        // RETURN goes back to INPUT, which ends on its policy
+        assert_eq!(table.verdict("tcp", 9000), Verdict::Drop);
+        // Only the filter table is read
+        assert_eq!(table.verdict("tcp", 3306), Verdict::Drop);
+    }
+
+    #[test]
+    fn established_only_rules_are_passed_over() {
+        assert_eq!(iptables("-A INPUT -m conntrack --ctstate ESTABLISHED -j ACCEPT").verdict("tcp", 22), Verdict::Drop);
+        assert_eq!(iptables("-A INPUT -m state --state NEW -p tcp --dport 22 -j ACCEPT").verdict("tcp", 22), Verdict::Accept);
+    }
+
+    #[test]
+    fn jump_loops_stop_at_max_jumps() {
+        let table = iptables(":loop - [0:0]\n-A INPUT -j loop\n-A loop -j loop");
+        assert_eq!(table.verdict("tcp", 22), Verdict::Drop);
+
+        // Nested exactly MAX_JUMPS deep still reaches the verdict
+        let mut rules = String::from("-A INPUT -j c1\n");
+        for depth in 1..MAX_JUMPS {
+            rules.push_str(&format!("-A c{} -j c{}\n", depth, depth + 1));
+        }
+        rules.push_str(&format!("-A c{} -p tcp --dport 22 -j ACCEPT", MAX_JUMPS));
+        assert_eq!(iptables(&rules).verdict("tcp", 22), Verdict::Accept);
+        rules = rules.replace("-A INPUT -j c1", "-A INPUT -j c0\n-A c0 -j c1");
+        assert_eq!(iptables(&rules).verdict("tcp", 22), Verdict::Drop);
+    }
+
+    #[test]
+    fn port_lists_and_ranges() {
+        assert_eq!(parse_ports("80,443", ':'), Some(vec![(80, 80), (443, 443)]));
+        assert_eq!(parse_ports("8000:8080", ':'), Some(vec![(8000, 8080)]));
+        assert_eq!(parse_ports("8000-8080", '-'), Some(vec![(8000, 8080)]));
+        assert_eq!(parse_ports("http", ':'), None);
+        assert_eq!(parse_ports("70000", ':'), None);
+
+        let words = ["{", "22,", "80-81", "}"];
+        assert_eq!(parse_nft_ports(&mut words.iter().copied()), Some(vec![(22, 22), (80, 81)]));
+        assert_eq!(parse_nft_ports(&mut ["{", "22"].iter().copied()), None);
+        assert_eq!(parse_nft_ports(&mut ["@blocked"].iter().copied()), None);
+    }
+
+    const NFT: &str = "\
+table ip filter {
+	chain INPUT {
+		type filter hook input priority filter; policy drop;
+		tcp dport 5432 accept
+	}
+}
+table inet filter {
+	set blocked {
+		type inet_service
+		elements = { 23,
+			     25 }
+	}
+	set admins {
+		type ipv4_addr
+		elements = {
+			10.0.0.1 }
+	}
+	map verdicts {
+		type inet_service : verdict
+		elements = { 1 : drop, 2 : accept }
+	}
+	chain input {
+		type filter hook input priority filter; policy drop;
+		iif \"lo\" accept
+		ct state established,related accept
+		tcp dport @blocked drop
+		tcp dport { 22, 80-81 } accept comment \"web {public}\"
+		udp dport 53 counter packets 12 bytes 3400 accept
+		jump services
+	}
+	chain services {
+		tcp dport 8080 accept
+		return
+	}
+	chain forward {
+		type filter hook forward priority filter; policy accept;
+	}
+}
+table netdev ingress {
+	chain ingress {
+		type filter hook ingress device eth0 priority 0; policy accept;
+	}
+}
+";
+
+    #[test]
+    fn reads_nft_tables() {
+        let tables = parse_nft(NFT, true);
+        // iptables-nft's "ip filter" is left to iptables-save, and netdev
+        // tables aren't read
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.family, Family::Both);
+        assert_eq!(table.inputs, ["input"]);
+        assert_eq!(table.chains["input"].policy, Some(Verdict::Drop));
+        assert_eq!(table.chains["forward"].policy, Some(Verdict::Accept));
+        // Nothing in the sets and maps was read as a chain or a rule
+        assert_eq!(table.chains.len(), 3);
+
+        assert_eq!(table.verdict("tcp", 22), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 81), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 82), Verdict::Drop);
+        assert_eq!(table.verdict("udp", 53), Verdict::Accept);
+        assert_eq!(table.verdict("tcp", 8080), Verdict::Accept);
+        // The rule on the named set is passed over, leaving the policy
+        assert_eq!(table.verdict("tcp", 23), Verdict::Drop);
+        assert_eq!(table.verdict("tcp", 5432), Verdict::Drop);
+
+        let tables = parse_nft(NFT, false);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].family, Family::V4);
+        assert_eq!(tables[0].verdict("tcp", 5432), Verdict::Accept);
+    }
+
+    #[test]
+    fn interface_rules_are_passed_over() {
+        assert_eq!(iptables("-A INPUT -i lo -j ACCEPT").verdict("tcp", 22), Verdict::Drop);
+        // Open on eth1 only says nothing about the other interfaces
+        assert_eq!(iptables("-A INPUT -i eth1 -p tcp --dport 22 -j ACCEPT").verdict("tcp", 22), Verdict::Drop);
+        assert_eq!(iptables("-A INPUT -p tcp --dport 22 -j ACCEPT").verdict("tcp", 22), Verdict::Accept);
+
+        for rule in ["iif \"lo\" accept", "iifname \"eth1\" tcp dport 22 accept", "meta iifname \"eth1\" accept"] {
+            let words: Vec<&str> = rule.split_whitespace().collect();
+            assert!(parse_nft_rule(&words).is_none(), "{}", rule);
+        }
+        assert!(parse_nft_rule(&["tcp", "dport", "22", "accept"]).is_some());
+    }
+}
@@ -0,0 +1,76 @@
+// fzf-style matching for the jump prompt: every character of a term has to
+// appear in order, and runs of consecutive characters and matches at the
+// start of a word score higher. Whitespace separates terms, which must all
+// match. Case is ignored.
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 24;
+const WORD_START: i64 = 20;
+const GAP: i64 = 1;
+
+// `None` when some term doesn't match
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    query
+        .split_whitespace()
+        .map(|term| term_score(&term.to_lowercase().chars().collect::<Vec<_>>(), &text))
+        .sum()
+}
+
+// Best over every place the term's first character occurs; each attempt
+// matches the rest greedily
+fn term_score(term: &[char], text: &[char]) -> Option<i64> {
+    let first = *term.first()?;
+    text.iter()
+        .enumerate()
+        .filter(|(_, c)| **c == first)
+        .filter_map(|(start, _)| greedy_score(term, text, start))
+        .max()
+}
+
+fn greedy_score(term: &[char], text: &[char], start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut position = start;
+
+    for c in term {
+        let found = position + text[position..].iter().position(|t| t == c)?;
+        score += MATCH;
+        match previous {
+            Some(previous) if found == previous + 1 => score += CONSECUTIVE,
+            Some(previous) => score -= GAP * (found - previous - 1) as i64,
+            None => {}
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_term_must_match_in_order() {
+        assert!(score("ngx", "1234/nginx").is_some());
+        assert!(score("NGINX 443", "1234/nginx 0.0.0.0:443").is_some());
+        assert_eq!(score("xgn", "1234/nginx"), None);
+        assert_eq!(score("nginx 8080", "1234/nginx 0.0.0.0:443"), None);
+        assert_eq!(score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn runs_and_word_starts_rank_higher() {
+        // Consecutive beats scattered
+        assert!(score("ssh", "1/sshd").unwrap() > score("ssh", "1/systemd-shim").unwrap());
+        // A match at a word start beats one inside a word
+        assert!(score("pg", "9/pg_ctl").unwrap() > score("pg", "9/upgrade").unwrap());
+        // The best starting point is used, not the first one
+        assert_eq!(score("dns", "d/dnsmasq"), score("dns", "x/dnsmasq"));
+    }
+}
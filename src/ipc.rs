@@ -0,0 +1,130 @@
+use crate::export;
+use crate::network::Connection;
+use log::{debug, info, warn};
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+use tokio::sync::broadcast;
+
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+// Streams each refresh to local clients as one JSON object per line:
+// {"timestamp":<unix secs>,"connections":[...]}
+pub struct IpcServer {
+    path: PathBuf,
+    tx: broadcast::Sender<Arc<String>>,
+}
+
+impl IpcServer {
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        // A socket file left behind by a crashed run would make bind fail.
+        // Anything else at the path is left alone.
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        let listener = UnixListener::bind(path)?;
+        let (tx, _) = broadcast::channel::<Arc<String>>(16);
+        info!("serving snapshots on {}", path.display());
+
+        let accept_tx = tx.clone();
+        tokio::spawn(async move {
+            loop {
+                let mut stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    // Usually EMFILE; retrying at once would spin until a
+                    // descriptor frees up
+                    Err(err) => {
+                        warn!("ipc accept failed: {}", err);
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                };
+
+                debug!("ipc client connected");
+                let mut rx = accept_tx.subscribe();
+                tokio::spawn(async move {
+                    loop {
+                        match rx.recv().await {
+                            Ok(line) => {
+                                if stream.write_all(line.as_bytes()).await.is_err() {
+                                    debug!("ipc client disconnected");
+                                    return;
+                                }
+                            }
+                            // A slow reader just misses intermediate snapshots
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => return,
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            tx,
+        })
+    }
+
+    pub fn publish(&self, connections: &[Connection]) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+
+        let all: Vec<&Connection> = connections.iter().collect();
+        let line = export::snapshot_json(&all) + "\n";
+        let _ = self.tx.send(Arc::new(line));
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            warn!("failed to remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("netmon-tui-ipc-{}-{}", name, std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn refuses_to_replace_a_regular_file() {
+        let path = temp_path("file");
+        std::fs::write(&path, "keep me").unwrap();
+
+        let err = IpcServer::bind(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replaces_a_stale_socket() {
+        let path = temp_path("stale");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let server = IpcServer::bind(&path).unwrap();
+        drop(server);
+        assert!(!path.exists());
+    }
+}
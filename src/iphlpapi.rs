@@ -0,0 +1,358 @@
+#[cfg(windows)]
+use crate::network::{Connection, ConnectionSource, ProtocolSet, Source};
+#[cfg(any(windows, test))]
+use crate::network::escape_control;
+#[cfg(windows)]
+use anyhow::{bail, Context, Result};
+#[cfg(windows)]
+use log::debug;
+#[cfg(windows)]
+use std::collections::HashMap;
+#[cfg(any(windows, test))]
+use std::net::Ipv4Addr;
+#[cfg(windows)]
+use std::net::Ipv6Addr;
+
+// --source windows: the owner-PID socket tables of the IP Helper API
+// (GetExtendedTcpTable/GetExtendedUdpTable), with process names from a
+// Toolhelp snapshot. This is only the socket listing; the rest of the
+// monitor still reads /proc and talks over Unix sockets, so netmon-tui as a
+// whole doesn't build for Windows yet.
+
+// MIB_TCP_STATE from tcpmib.h, under the names /proc and ss states get.
+// DELETE_TCB is a socket being torn down, which Linux just calls CLOSE.
+#[cfg(any(windows, test))]
+fn tcp_state_name(state: u32) -> &'static str {
+    match state {
+        1 => "CLOSE",
+        2 => "LISTEN",
+        3 => "SYN_SENT",
+        4 => "SYN_RECV",
+        5 => "ESTABLISHED",
+        6 => "FIN_WAIT1",
+        7 => "FIN_WAIT2",
+        8 => "CLOSE_WAIT",
+        9 => "CLOSING",
+        10 => "LAST_ACK",
+        11 => "TIME_WAIT",
+        12 => "CLOSE",
+        _ => "UNKNOWN",
+    }
+}
+
+// Ports are DWORDs holding the port in network byte order in their low
+// two bytes; the upper two are undefined
+#[cfg(any(windows, test))]
+fn port(dword: u32) -> u16 {
+    u16::from_be(dword as u16)
+}
+
+// IPv4 addresses are an in_addr read as a DWORD, so the bytes in memory
+// are already in network order
+#[cfg(any(windows, test))]
+fn ipv4(dword: u32) -> Ipv4Addr {
+    Ipv4Addr::from(dword.to_ne_bytes())
+}
+
+// szExeFile is NUL-terminated UTF-16; escaped like a Linux comm
+#[cfg(any(windows, test))]
+fn exe_name(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    escape_control(&String::from_utf16_lossy(&wide[..len]))
+}
+
+#[cfg(windows)]
+mod ffi {
+    use std::ffi::c_void;
+
+    pub const NO_ERROR: u32 = 0;
+    pub const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+    pub const AF_INET: u32 = 2;
+    pub const AF_INET6: u32 = 23;
+    pub const TCP_TABLE_OWNER_PID_ALL: i32 = 5;
+    pub const UDP_TABLE_OWNER_PID: i32 = 1;
+    pub const TH32CS_SNAPPROCESS: u32 = 0x2;
+    pub const INVALID_HANDLE_VALUE: isize = -1;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MibTcpRowOwnerPid {
+        pub state: u32,
+        pub local_addr: u32,
+        pub local_port: u32,
+        pub remote_addr: u32,
+        pub remote_port: u32,
+        pub owning_pid: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MibTcp6RowOwnerPid {
+        pub local_addr: [u8; 16],
+        pub local_scope_id: u32,
+        pub local_port: u32,
+        pub remote_addr: [u8; 16],
+        pub remote_scope_id: u32,
+        pub remote_port: u32,
+        pub state: u32,
+        pub owning_pid: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MibUdpRowOwnerPid {
+        pub local_addr: u32,
+        pub local_port: u32,
+        pub owning_pid: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MibUdp6RowOwnerPid {
+        pub local_addr: [u8; 16],
+        pub local_scope_id: u32,
+        pub local_port: u32,
+        pub owning_pid: u32,
+    }
+
+    #[repr(C)]
+    pub struct ProcessEntry32W {
+        pub size: u32,
+        pub usage: u32,
+        pub process_id: u32,
+        pub default_heap_id: usize,
+        pub module_id: u32,
+        pub threads: u32,
+        pub parent_process_id: u32,
+        pub pri_class_base: i32,
+        pub flags: u32,
+        pub exe_file: [u16; 260],
+    }
+
+    #[link(name = "iphlpapi")]
+    extern "system" {
+        pub fn GetExtendedTcpTable(
+            table: *mut c_void,
+            size: *mut u32,
+            order: i32,
+            af: u32,
+            class: i32,
+            reserved: u32,
+        ) -> u32;
+        pub fn GetExtendedUdpTable(
+            table: *mut c_void,
+            size: *mut u32,
+            order: i32,
+            af: u32,
+            class: i32,
+            reserved: u32,
+        ) -> u32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn CreateToolhelp32Snapshot(flags: u32, process_id: u32) -> isize;
+        pub fn Process32FirstW(snapshot: isize, entry: *mut ProcessEntry32W) -> i32;
+        pub fn Process32NextW(snapshot: isize, entry: *mut ProcessEntry32W) -> i32;
+        pub fn CloseHandle(handle: isize) -> i32;
+    }
+}
+
+// Sockets opened between the sizing call and the real one make the table
+// outgrow its buffer, so the size is asked for again a few times
+#[cfg(windows)]
+const TABLE_ATTEMPTS: usize = 4;
+
+// The rows of one MIB_*TABLE_OWNER_PID: a DWORD count, then the rows.
+// `get` is GetExtendedTcpTable or GetExtendedUdpTable with the family and
+// table class filled in.
+#[cfg(windows)]
+fn read_table<Row: Copy>(get: impl Fn(*mut std::ffi::c_void, *mut u32) -> u32) -> Result<Vec<Row>> {
+    let mut size: u32 = 0;
+    for _ in 0..TABLE_ATTEMPTS {
+        // u32 words keep the count and the rows, all DWORD fields, aligned
+        let mut buffer = vec![0u32; size as usize / 4 + 1];
+        match get(buffer.as_mut_ptr().cast(), &mut size) {
+            ffi::NO_ERROR => {
+                let count = buffer.first().copied().unwrap_or(0) as usize;
+                let bytes = 4 + count * std::mem::size_of::<Row>();
+                if bytes > buffer.len() * 4 {
+                    bail!("table of {} rows doesn't fit its {} byte buffer", count, buffer.len() * 4);
+                }
+                // SAFETY: the API wrote `count` rows right after the count,
+                // and they were just checked to lie within the buffer
+                let rows = unsafe { std::slice::from_raw_parts(buffer.as_ptr().add(1).cast::<Row>(), count) };
+                return Ok(rows.to_vec());
+            }
+            ffi::ERROR_INSUFFICIENT_BUFFER => continue,
+            code => bail!("error {}", code),
+        }
+    }
+    bail!("table still growing after {} attempts", TABLE_ATTEMPTS)
+}
+
+#[cfg(windows)]
+fn tcp_table<Row: Copy>(family: u32) -> Result<Vec<Row>> {
+    read_table(|table, size| unsafe {
+        ffi::GetExtendedTcpTable(table, size, 0, family, ffi::TCP_TABLE_OWNER_PID_ALL, 0)
+    })
+    .context("GetExtendedTcpTable failed")
+}
+
+#[cfg(windows)]
+fn udp_table<Row: Copy>(family: u32) -> Result<Vec<Row>> {
+    read_table(|table, size| unsafe {
+        ffi::GetExtendedUdpTable(table, size, 0, family, ffi::UDP_TABLE_OWNER_PID, 0)
+    })
+    .context("GetExtendedUdpTable failed")
+}
+
+// Executable names by PID. A failed snapshot only costs the names; the
+// PIDs still come from the socket tables.
+#[cfg(windows)]
+fn process_names() -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    // SAFETY: the entry is plain data with its size set as the API requires,
+    // and the snapshot handle is closed once walked
+    unsafe {
+        let snapshot = ffi::CreateToolhelp32Snapshot(ffi::TH32CS_SNAPPROCESS, 0);
+        if snapshot == ffi::INVALID_HANDLE_VALUE {
+            debug!("CreateToolhelp32Snapshot failed: {}", std::io::Error::last_os_error());
+            return names;
+        }
+        let mut entry: ffi::ProcessEntry32W = std::mem::zeroed();
+        entry.size = std::mem::size_of::<ffi::ProcessEntry32W>() as u32;
+        let mut more = ffi::Process32FirstW(snapshot, &mut entry) != 0;
+        while more {
+            names.insert(entry.process_id, exe_name(&entry.exe_file));
+            more = ffi::Process32NextW(snapshot, &mut entry) != 0;
+        }
+        ffi::CloseHandle(snapshot);
+    }
+    names
+}
+
+#[cfg(windows)]
+#[derive(Default)]
+pub struct WindowsSource;
+
+#[cfg(windows)]
+impl ConnectionSource for WindowsSource {
+    fn connections(&mut self, protocols: ProtocolSet) -> Result<Vec<Connection>> {
+        let names = process_names();
+        // PID 0 is the idle process, which TIME_WAIT sockets are charged to
+        let owner = |pid: u32| {
+            let pid = Some(pid).filter(|&pid| pid != 0);
+            (pid, pid.and_then(|pid| names.get(&pid).cloned()))
+        };
+
+        let mut connections = Vec::new();
+        if protocols.tcp {
+            for row in tcp_table::<ffi::MibTcpRowOwnerPid>(ffi::AF_INET)? {
+                let (pid, process_name) = owner(row.owning_pid);
+                connections.push(Connection {
+                    protocol: "TCP".to_string(),
+                    local_address: ipv4(row.local_addr).to_string(),
+                    local_port: port(row.local_port),
+                    remote_address: ipv4(row.remote_addr).to_string(),
+                    remote_port: port(row.remote_port),
+                    state: tcp_state_name(row.state).to_string(),
+                    pid,
+                    process_name,
+                    ..Default::default()
+                });
+            }
+        }
+        if protocols.tcp6 {
+            for row in tcp_table::<ffi::MibTcp6RowOwnerPid>(ffi::AF_INET6)? {
+                let (pid, process_name) = owner(row.owning_pid);
+                connections.push(Connection {
+                    protocol: "TCP6".to_string(),
+                    local_address: Ipv6Addr::from(row.local_addr).to_string(),
+                    local_port: port(row.local_port),
+                    remote_address: Ipv6Addr::from(row.remote_addr).to_string(),
+                    remote_port: port(row.remote_port),
+                    state: tcp_state_name(row.state).to_string(),
+                    pid,
+                    process_name,
+                    ..Default::default()
+                });
+            }
+        }
+        // UDP rows have no remote end, even for connected sockets
+        if protocols.udp {
+            for row in udp_table::<ffi::MibUdpRowOwnerPid>(ffi::AF_INET)? {
+                let (pid, process_name) = owner(row.owning_pid);
+                connections.push(Connection {
+                    protocol: "UDP".to_string(),
+                    local_address: ipv4(row.local_addr).to_string(),
+                    local_port: port(row.local_port),
+                    remote_address: "0.0.0.0".to_string(),
+                    pid,
+                    process_name,
+                    ..Default::default()
+                });
+            }
+        }
+        if protocols.udp6 {
+            for row in udp_table::<ffi::MibUdp6RowOwnerPid>(ffi::AF_INET6)? {
+                let (pid, process_name) = owner(row.owning_pid);
+                connections.push(Connection {
+                    protocol: "UDP6".to_string(),
+                    local_address: Ipv6Addr::from(row.local_addr).to_string(),
+                    local_port: port(row.local_port),
+                    remote_address: "::".to_string(),
+                    pid,
+                    process_name,
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(connections)
+    }
+
+    fn kind(&self) -> Source {
+        Source::Windows
+    }
+
+    fn attributes_owners(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_windows_tcp_states() {
+        assert_eq!(tcp_state_name(2), "LISTEN");
+        assert_eq!(tcp_state_name(5), "ESTABLISHED");
+        assert_eq!(tcp_state_name(4), "SYN_RECV");
+        assert_eq!(tcp_state_name(11), "TIME_WAIT");
+        assert_eq!(tcp_state_name(1), "CLOSE");
+        assert_eq!(tcp_state_name(12), "CLOSE");
+        assert_eq!(tcp_state_name(0), "UNKNOWN");
+        assert_eq!(tcp_state_name(13), "UNKNOWN");
+    }
+
+    #[test]
+    fn decodes_table_fields() {
+        // Bytes as the API lays them out: network order in memory
+        let dword = |bytes: [u8; 4]| u32::from_ne_bytes(bytes);
+        assert_eq!(port(dword([0x01, 0xbb, 0, 0])), 443);
+        assert_eq!(port(dword([0x00, 0x50, 0xff, 0xff])), 80);
+        assert_eq!(ipv4(dword([192, 168, 1, 20])), Ipv4Addr::new(192, 168, 1, 20));
+    }
+
+    #[test]
+    fn exe_names_stop_at_the_nul() {
+        let mut wide = [0u16; 260];
+        for (slot, unit) in wide.iter_mut().zip("svchost.exe".encode_utf16()) {
+            *slot = unit;
+        }
+        assert_eq!(exe_name(&wide), "svchost.exe");
+        let bell: Vec<u16> = "a\u{7}b".encode_utf16().collect();
+        assert_eq!(exe_name(&bell), "a\\u{7}b");
+    }
+}
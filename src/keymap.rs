@@ -0,0 +1,306 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+// Everything a key can be bound to. Counts, "gg" and "G" stay fixed vim
+// motions and apply only to keys that aren't bound here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Up,
+    Down,
+    SwitchPanel,
+    Filter,
+    Command,
+    Jump,
+    Sort,
+    SortReverse,
+    Processes,
+    Remotes,
+    GroupSubnets,
+    Select,
+    Detail,
+    OnlyMine,
+    Family,
+    Pin,
+    ToggleTcp,
+    ToggleUdp,
+    Ephemeral,
+    Compact,
+    Exposed,
+    ExcludePort,
+    ClearExclusions,
+    Pager,
+    Baseline,
+    DiffOnly,
+    Debug,
+    HideUnattributed,
+    HideKernel,
+    Resolve,
+    SinceWindow,
+    ColorBy,
+    Anonymize,
+    Follow,
+    Stats,
+    Cgroup,
+    Container,
+    Inode,
+    WrapRemote,
+    Export,
+    ExportAll,
+    Check,
+    ExportChecked,
+    Watch,
+    Refresh,
+    Source,
+    MergeListeners,
+    CopyMarkdown,
+    OnlyQueued,
+}
+
+// Names used in the `[keybindings]` section, with the default keys
+const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Quit, "quit", &["q", "esc", "ctrl-c"]),
+    (Action::Up, "up", &["up", "k"]),
+    (Action::Down, "down", &["down", "j"]),
+    (Action::SwitchPanel, "switch_panel", &["tab", "backtab"]),
+    (Action::Filter, "filter", &["/"]),
+    (Action::Command, "command", &[":"]),
+    (Action::Jump, "jump", &["ctrl-p"]),
+    (Action::Sort, "sort", &["s"]),
+    (Action::SortReverse, "sort_reverse", &["S"]),
+    (Action::Processes, "processes", &["a"]),
+    (Action::Remotes, "remotes", &["t"]),
+    (Action::GroupSubnets, "group_subnets", &["c"]),
+    (Action::Select, "select", &["enter"]),
+    (Action::Detail, "detail", &["d"]),
+    (Action::OnlyMine, "only_mine", &["m"]),
+    (Action::Family, "family", &["f"]),
+    (Action::Pin, "pin", &["P"]),
+    (Action::ToggleTcp, "toggle_tcp", &["T"]),
+    (Action::ToggleUdp, "toggle_udp", &["U"]),
+    (Action::Ephemeral, "ephemeral", &["r"]),
+    (Action::Compact, "compact", &["v"]),
+    (Action::Exposed, "exposed", &["e"]),
+    (Action::ExcludePort, "exclude_port", &["x"]),
+    (Action::ClearExclusions, "clear_exclusions", &["X"]),
+    (Action::Pager, "pager", &["p"]),
+    (Action::Baseline, "baseline", &["b"]),
+    (Action::DiffOnly, "diff_only", &["B"]),
+    (Action::Debug, "debug", &["D"]),
+    (Action::HideUnattributed, "hide_unattributed", &["u"]),
+    (Action::HideKernel, "hide_kernel", &["K"]),
+    (Action::Resolve, "resolve", &["n"]),
+    (Action::SinceWindow, "since_window", &["w"]),
+    (Action::ColorBy, "color_by", &["C"]),
+    (Action::Anonymize, "anonymize", &["A"]),
+    (Action::Follow, "follow", &["F"]),
+    (Action::Stats, "stats", &["i"]),
+    (Action::Cgroup, "cgroup", &["o"]),
+    (Action::Container, "container", &["O"]),
+    (Action::Inode, "inode", &["I"]),
+    (Action::WrapRemote, "wrap_remote", &["z"]),
+    (Action::Export, "export", &["E"]),
+    (Action::ExportAll, "export_all", &["ctrl-e"]),
+    (Action::Check, "check", &["space"]),
+    (Action::ExportChecked, "export_checked", &["alt-e"]),
+    (Action::Watch, "watch", &["W"]),
+    (Action::Refresh, "refresh", &["R"]),
+    (Action::Source, "source", &["L"]),
+    (Action::MergeListeners, "merge_listeners", &["M"]),
+    (Action::CopyMarkdown, "copy_markdown", &["y"]),
+    (Action::OnlyQueued, "only_queued", &["Q"]),
+];
+
+pub struct Keymap {
+    bindings: HashMap<KeyEvent, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        for (action, _, keys) in ACTIONS {
+            for spec in *keys {
+                let key = parse_key(spec).expect("default key specs are valid");
+                bindings.insert(key, *action);
+            }
+        }
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    // Applies `(line, action, keys)` overrides from the config. A rebound
+    // action loses its default keys; bad names, bad key specs and keys already
+    // taken by another action are reported and skipped, and an action left
+    // with no usable key gets its defaults back.
+    pub fn with_overrides(overrides: &[(usize, String, String)], warnings: &mut Vec<String>) -> Self {
+        let mut keymap = Self::default();
+
+        let mut rebound = Vec::new();
+        for (line, name, value) in overrides {
+            match ACTIONS.iter().find(|(_, action_name, _)| action_name == name) {
+                Some((action, _, _)) => rebound.push((*line, *action, value)),
+                None => warnings.push(format!("config line {}: unknown action '{}'", line, name)),
+            }
+        }
+
+        // Clear every rebound action first so actions can swap keys
+        keymap.bindings.retain(|_, action| !rebound.iter().any(|(_, rebound, _)| rebound == action));
+
+        for (line, action, value) in rebound {
+            let mut bound = false;
+            for spec in value.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+                let Some(key) = parse_key(spec) else {
+                    warnings.push(format!("config line {}: unknown key '{}'", line, spec));
+                    continue;
+                };
+                match keymap.bindings.get(&key) {
+                    Some(other) if *other != action => {
+                        warnings.push(format!("config line {}: '{}' is already bound to {}", line, spec, name(*other)));
+                    }
+                    _ => {
+                        keymap.bindings.insert(key, action);
+                        bound = true;
+                    }
+                }
+            }
+
+            if !bound {
+                keymap.restore_defaults(action);
+            }
+        }
+
+        keymap
+    }
+
+    fn restore_defaults(&mut self, action: Action) {
+        let Some((_, _, keys)) = ACTIONS.iter().find(|(a, _, _)| *a == action) else {
+            return;
+        };
+        for key in keys.iter().filter_map(|spec| parse_key(spec)) {
+            self.bindings.entry(key).or_insert(action);
+        }
+    }
+
+    // Shift is already reflected in the character, so it's ignored for
+    // character keys; on other keys (Shift+Up) it falls back to the bare
+    // key. Ctrl and Alt chords only match their own bindings, so an unbound
+    // Ctrl-C doesn't run whatever 'c' does.
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        let exact = self.bindings.get(&KeyEvent::new(key.code, modifiers));
+        match exact {
+            None if modifiers == KeyModifiers::SHIFT => self.bindings.get(&KeyEvent::new(key.code, KeyModifiers::NONE)),
+            _ => exact,
+        }
+        .copied()
+    }
+}
+
+fn name(action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(a, _, _)| *a == action)
+        .map(|(_, name, _)| *name)
+        .unwrap_or("?")
+}
+
+// Key specs are a single character ("q", "/", "P"), a named key ("esc",
+// "enter", "tab", "backtab", "space", "comma", arrows, "home", "end",
+// "pageup", "pagedown", "f1".."f12"), optionally prefixed with "ctrl-" or
+// "alt-"
+fn parse_key(spec: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+    loop {
+        if let Some(tail) = rest.strip_prefix("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = tail;
+        } else {
+            break;
+        }
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_lowercase().as_str() {
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "comma" => KeyCode::Char(','),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return None,
+            },
+        },
+    };
+
+    Some(KeyEvent::new(code, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn unbound_ctrl_and_alt_chords_do_nothing() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(key(KeyCode::Char('c'), KeyModifiers::NONE)), Some(Action::GroupSubnets));
+        assert_eq!(keymap.action(key(KeyCode::Char('c'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert_eq!(keymap.action(key(KeyCode::Char('s'), KeyModifiers::CONTROL)), None);
+        assert_eq!(keymap.action(key(KeyCode::Char('s'), KeyModifiers::ALT)), None);
+        assert_eq!(keymap.action(key(KeyCode::Char('e'), KeyModifiers::CONTROL)), Some(Action::ExportAll));
+        assert_eq!(keymap.action(key(KeyCode::Char('e'), KeyModifiers::ALT)), Some(Action::ExportChecked));
+    }
+
+    #[test]
+    fn shift_is_ignored() {
+        let keymap = Keymap::default();
+        // Terminals report 'S' with or without the SHIFT flag
+        assert_eq!(keymap.action(key(KeyCode::Char('S'), KeyModifiers::SHIFT)), Some(Action::SortReverse));
+        assert_eq!(keymap.action(key(KeyCode::Char('S'), KeyModifiers::NONE)), Some(Action::SortReverse));
+        assert_eq!(keymap.action(key(KeyCode::Up, KeyModifiers::SHIFT)), Some(Action::Up));
+        assert_eq!(keymap.action(key(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::SHIFT)), None);
+    }
+
+    #[test]
+    fn overrides_replace_defaults_and_report_conflicts() {
+        let overrides = [
+            (1, "quit".to_string(), "ctrl-q, ctrl-x".to_string()),
+            (2, "sort".to_string(), "j".to_string()),
+            (3, "bogus".to_string(), "z".to_string()),
+            (4, "detail".to_string(), "hyper-d".to_string()),
+        ];
+        let mut warnings = Vec::new();
+        let keymap = Keymap::with_overrides(&overrides, &mut warnings);
+
+        assert_eq!(keymap.action(key(KeyCode::Char('x'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert_eq!(keymap.action(key(KeyCode::Char('q'), KeyModifiers::NONE)), None);
+        assert_eq!(keymap.action(key(KeyCode::Char('c'), KeyModifiers::CONTROL)), None);
+        // 'j' belongs to down, so sort keeps its default
+        assert_eq!(keymap.action(key(KeyCode::Char('s'), KeyModifiers::NONE)), Some(Action::Sort));
+        assert_eq!(keymap.action(key(KeyCode::Char('d'), KeyModifiers::NONE)), Some(Action::Detail));
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+    }
+}
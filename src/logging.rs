@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Appends log records to a file. Nothing is ever written to stdout/stderr,
+// which would corrupt the TUI.
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{}.{:03} {:<5} {}: {}",
+                now.as_secs(),
+                now.subsec_millis(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Verbosity 0 logs info and above, 1 (-v) adds debug, 2+ (-vv) adds trace
+fn level_for(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => Level::Info,
+        1 => Level::Debug,
+        _ => Level::Trace,
+    }
+    .to_level_filter()
+}
+
+pub fn init(path: &Path, verbosity: u8) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("cannot open log file {}", path.display()))?;
+
+    let level = level_for(verbosity);
+
+    // The logger lives for the rest of the process
+    let logger = Box::leak(Box::new(FileLogger {
+        file: Mutex::new(file),
+        level,
+    }));
+    log::set_logger(logger).map_err(|err| anyhow::anyhow!("{}", err))?;
+    log::set_max_level(level);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn verbosity_levels() {
+        assert_eq!(level_for(0), LevelFilter::Info);
+        assert_eq!(level_for(1), LevelFilter::Debug);
+        assert_eq!(level_for(2), LevelFilter::Trace);
+        assert_eq!(level_for(9), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn writes_records_at_or_above_the_level() {
+        let path = std::env::temp_dir().join(format!("netmon-tui-log-test-{}", std::process::id()));
+        let logger = FileLogger {
+            file: Mutex::new(File::create(&path).unwrap()),
+            level: LevelFilter::Debug,
+        };
+
+        for (level, message) in [(Level::Info, "kept"), (Level::Debug, "also kept"), (Level::Trace, "dropped")] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target("netmon_tui::network")
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+        logger.flush();
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" INFO  netmon_tui::network: kept"), "{}", lines[0]);
+        assert!(lines[1].ends_with(" DEBUG netmon_tui::network: also kept"), "{}", lines[1]);
+
+        // "<secs>.<millis>" timestamp
+        let (secs, millis) = lines[0].split(' ').next().unwrap().split_once('.').unwrap();
+        assert!(secs.parse::<u64>().is_ok());
+        assert_eq!(millis.len(), 3);
+    }
+}
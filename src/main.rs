@@ -1,284 +1,3506 @@
+mod age;
+mod aggregate;
+mod cli;
+mod columns;
+mod config;
+mod container;
+mod diag;
+mod diff;
+mod dns;
+mod export;
+mod filter;
+mod firewall;
+mod fuzzy;
+mod iphlpapi;
+mod ipc;
+mod json;
+mod keymap;
+mod logging;
 mod network;
+mod pins;
+mod portscan;
+mod regex;
+mod remote;
+mod snapshot;
+mod sort;
+mod ss;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use network::{Connection, NetworkMonitor};
+use age::{format_age, AgeFormat};
+use aggregate::{format_rate, ProcessSummary};
+use cli::{Args, USAGE};
+use columns::ColumnKind;
+use config::{Config, LocalIpc};
+use container::ContainerNames;
+use dns::DnsCache;
+use filter::{FamilyFilter, Filter, FILTER_HELP};
+use firewall::Firewall;
+use keymap::Action;
+use log::debug;
+use network::{Connection, ConnectionKey, NetworkMonitor, TimerKind};
+use portscan::ScanDetector;
+use sort::{sort_connections, SortKey};
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    buffer::Buffer,
+    layout::{Constraint, Direction, Flex, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
     Frame, Terminal,
 };
 use std::{
+    borrow::Borrow,
+    cell::{Ref, RefCell},
+    collections::{BTreeSet, HashMap, HashSet},
     error::Error,
     io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
+// Knobs that shape how the tables are rendered, kept apart from the rest of
+// `App` so they can be borrowed alongside the table state
+struct ViewOptions {
+    tls_ports: Vec<u16>,
+    resolve_hosts: bool,
+    // Adds a Flags column, only filled in for UNIX sockets
+    show_flags: bool,
+    // When set, addresses whose port falls in this range are highlighted
+    ephemeral_ports: Option<(u16, u16)>,
+    color_by: ColorBy,
+    // Addresses are drawn as pseudonyms for screenshots ('A'); local ones
+    // only with --anonymize-local
+    anonymize: bool,
+    anonymize_local: bool,
+    // Per-run, so pseudonyms are stable on screen but can't be reversed by
+    // hashing every IPv4 address
+    anonymize_salt: u64,
+    // Columns to move to the front, from the config
+    column_order: Vec<ColumnKind>,
+    // Adds queue, retransmit and drop counter columns ('i')
+    show_stats: bool,
+    // Adds the owning cgroup column ('o')
+    show_cgroup: bool,
+    // Adds the container name column ('O')
+    show_container: bool,
+    // Adds the socket inode column ('I')
+    show_inode: bool,
+    // Host column, with --ssh
+    show_host: bool,
+    local_ipc: LocalIpc,
+    // `compact_numbers`; exports and the detail pane stay exact
+    compact_numbers: bool,
+    // Two-line active rows, so long foreign addresses wrap instead of being
+    // cut off ('z')
+    wrap_remote: bool,
+    // No colours at all, for --no-color and terminals without them
+    monochrome: bool,
+    age_format: AgeFormat,
+}
+
+impl ViewOptions {
+    // A count or byte figure as the tables show it
+    fn number(&self, n: u64) -> String {
+        if self.compact_numbers {
+            humanize(n)
+        } else {
+            n.to_string()
+        }
+    }
+
+    // Pseudonym for `address` while anonymizing, e.g. "host-3fa9c1", or
+    // "net-3fa9c1/24" for a subnet. Loopback and wildcard addresses reveal
+    // nothing and are left alone, as are non-IP (UNIX) addresses.
+    fn masked(&self, address: &str, local: bool) -> Option<String> {
+        if !self.anonymize || (local && !self.anonymize_local) {
+            return None;
+        }
+
+        let (ip, prefix) = match address.split_once('/') {
+            Some((ip, prefix)) => (ip, Some(prefix)),
+            None => (address, None),
+        };
+        let parsed = ip.parse::<std::net::IpAddr>().ok()?;
+        if parsed.is_loopback() || parsed.is_unspecified() {
+            return None;
+        }
+
+        let hash = ip.bytes().fold(0xcbf29ce484222325_u64 ^ self.anonymize_salt, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Some(match prefix {
+            Some(prefix) => format!("net-{:06x}/{}", hash & 0xffffff, prefix),
+            None => format!("host-{:06x}", hash & 0xffffff),
+        })
+    }
+
+    fn local_endpoint(&self, conn: &Connection) -> String {
+        match self.masked(&conn.local_address, true) {
+            Some(name) => format!("{}:{}", name, conn.local_port),
+            None => conn.local_bind_display(),
+        }
+    }
+}
+
+// What the row colour encodes, switched with 'C'
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorBy {
+    State,
+    Process,
+    // Bright when first seen, fading to grey over AGE_FADE
+    Age,
+}
+
+// Why a listener has no PID
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Unowned {
+    // Tagged [kernel]: no file descriptor exists, the kernel holds the socket
+    Kernel,
+    // A real inode that no visible process has open, although we could read
+    // its owner's fds
+    Orphan,
+}
+
+// State history of one connection, recorded with 'W'
+struct Watch {
+    key: ConnectionKey,
+    // Each state with when it was first seen, oldest first
+    timeline: Vec<(Instant, String)>,
+}
+
+impl Watch {
+    fn describe(&self) -> String {
+        self.timeline
+            .iter()
+            .map(|(at, state)| format!("{} ({}s ago)", state, at.elapsed().as_secs()))
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Listening,
+    Active,
+    // The single combined table of the compact layout
+    All,
+}
+
+// Below this terminal height the compact layout is used automatically
+const COMPACT_BELOW_ROWS: u16 = 20;
+
+// From this terminal width the panels sit side by side rather than stacked;
+// below it the active panel's columns wouldn't fit in its share
+const SIDE_BY_SIDE_FROM_COLUMNS: u16 = 220;
+
+// A panel's sort column and direction; `None` keeps the kernel's order
+#[derive(Clone, Copy, Default)]
+struct PanelSort {
+    key: Option<SortKey>,
+    desc: bool,
+}
+
+impl PanelSort {
+    // Shown after the sort column's title and in the panel title
+    fn arrow(&self) -> &'static str {
+        if self.desc {
+            "↓"
+        } else {
+            "↑"
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    Connections,
+    Processes,
+    Remotes,
+}
+
+// Rows in the top talkers view
+const TOP_TALKERS: usize = 50;
+
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Refresh intervals ':interval' accepts, in milliseconds
+const INTERVAL_MS: std::ops::RangeInclusive<u64> = 100..=60_000;
+
+const COMMAND_HELP: &str = "interval <ms>";
+
+// A table row: either a single connection or a collapsible group of them
+enum PanelRow<'a> {
+    Connection(&'a Connection),
+    // In the baseline but gone now
+    Removed(&'a Connection),
+    Subnet {
+        subnet: String,
+        count: usize,
+        expanded: bool,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum RowKey {
+    Connection(ConnectionKey),
+    Subnet(String),
+}
+
+// A visible connection with its index in `App::connections`, so the order
+// can be cached by index without the cache borrowing `App`
+struct Indexed<'a>(usize, &'a Connection);
+
+impl Borrow<Connection> for Indexed<'_> {
+    fn borrow(&self) -> &Connection {
+        self.1
+    }
+}
+
+// One panel's visible rows and everything its title and badges count,
+// worked out in the same pass so frames between changes redo none of it
+#[derive(Default)]
+struct PanelCache {
+    // Filtered and sorted indices into `App::connections`
+    visible: Vec<usize>,
+    // Counted before the display-only toggles, like the title always was
+    added: HashSet<ConnectionKey>,
+    removed: usize,
+    excluded: usize,
+    unattributed: usize,
+    kernel: usize,
+    exposed: usize,
+    // Connections with queued bytes, and the largest queue among them
+    queued: (usize, u32),
+    // Listening panel only, while merging: each merged listener's group,
+    // itself first, as indices
+    merged: HashMap<ConnectionKey, Vec<usize>>,
+}
+
+impl PanelRow<'_> {
+    fn key(&self) -> RowKey {
+        match self {
+            PanelRow::Connection(conn) | PanelRow::Removed(conn) => RowKey::Connection(conn.key()),
+            PanelRow::Subnet { subnet, .. } => RowKey::Subnet(subnet.clone()),
+        }
+    }
+}
+
+// Jump prompt state; matches are recomputed from the query on every frame
+#[derive(Default)]
+struct Finder {
+    query: String,
+    // Index into the ranked matches
+    selected: usize,
+}
+
+// One hit in the jump prompt: where the row is and how it reads there
+struct FinderMatch {
+    panel: Panel,
+    index: usize,
+    line: String,
+}
+
+// Hits listed in the jump prompt
+const FINDER_ROWS: usize = 12;
+
+// Pending vim-style prefix: a repeat count and/or the first 'g' of "gg"
+#[derive(Default)]
+struct KeySequence {
+    count: Option<usize>,
+    g_pending: bool,
+}
+
+impl KeySequence {
+    fn take_count(&mut self) -> Option<usize> {
+        self.g_pending = false;
+        self.count.take()
+    }
+
+    fn reset(&mut self) {
+        self.count = None;
+        self.g_pending = false;
+    }
+}
+
 struct App {
     connections: Vec<Connection>,
-    table_state: TableState,
+    focused: Panel,
+    listening_state: TableState,
+    active_state: TableState,
+    all_state: TableState,
+    // Each panel sorts on its own ('s', 'S' and header clicks change the
+    // focused one); the compact table has its own too
+    listening_sort: PanelSort,
+    active_sort: PanelSort,
+    all_sort: PanelSort,
     last_update: Instant,
     network_monitor: NetworkMonitor,
+    filter: Filter,
+    filter_text: String,
+    filter_input: Option<String>,
+    // The ':' prompt while it's open
+    command_input: Option<String>,
+    // The jump prompt ('ctrl-p') while it's open
+    finder: Option<Finder>,
+    view: ViewOptions,
+    dns: DnsCache,
+    // Only asked while the Container column is shown
+    containers: ContainerNames,
+    view_mode: ViewMode,
+    process_summaries: Vec<ProcessSummary>,
+    status: Option<(String, Instant)>,
+    confirm_quit: bool,
+    // --manual: no periodic scans, only 'R' (and SIGUSR1)
+    manual: bool,
+    quit_pressed_at: Option<Instant>,
+    hide_unattributed: bool,
+    // 'K': hides sockets tagged [kernel]
+    hide_kernel: bool,
+    show_debug: bool,
+    group_subnets: bool,
+    // 'M': one listening row per port, merging TCP/TCP6/UDP/UDP6 sockets
+    merge_listeners: bool,
+    expanded_subnets: HashSet<String>,
+    show_detail: bool,
+    usernames: HashMap<u32, String>,
+    current_uid: u32,
+    only_mine: bool,
+    // --exe-prefix
+    exe_prefix: Option<PathBuf>,
+    ipc: Option<ipc::IpcServer>,
+    // --ssh hosts, merged into every refresh
+    remote: Option<remote::RemoteHosts>,
+    excluded_ports: BTreeSet<u16>,
+    tcp_info: HashMap<u64, diag::TcpInfo>,
+    // SO_RCVBUF/SO_SNDBUF by inode, read with tcp_info for the detail pane
+    socket_buffers: HashMap<u64, diag::SocketBuffers>,
+    // Accept queues of TCP listeners by inode, refreshed with every scan
+    listen_queues: HashMap<u64, diag::ListenQueue>,
+    listen_counters: Option<(Instant, network::ListenCounters)>,
+    // SYNs dropped at listeners per second over the last refresh
+    listen_drop_rate: f64,
+    only_exposed: bool,
+    // 'Q': only active connections with bytes in either queue
+    only_queued: bool,
+    remotes_state: TableState,
+    // Set by drilling into a remote from the top talkers view; limits the
+    // active panel to that address
+    remote_focus: Option<String>,
+    config: Config,
+    // Where each panel was last drawn, for mapping mouse clicks
+    panel_areas: Vec<(Panel, Rect)>,
+    // Snapshot taken with 'b'; rows are then marked as added or removed
+    // relative to it
+    baseline: Option<HashMap<ConnectionKey, Connection>>,
+    // Baseline entries missing from the last refresh; worked out once per
+    // scan rather than on every frame
+    removed: Vec<ConnectionKey>,
+    diff_only: bool,
+    family: FamilyFilter,
+    // Pinned rows sort to the top of their panel; keys are endpoint-based so
+    // pins survive refreshes
+    pinned: HashSet<ConnectionKey>,
+    // Rows ticked with Space for 'export_checked'
+    checked: HashSet<ConnectionKey>,
+    watch: Option<Watch>,
+    // ESTABLISHED TCP keys per local port at the last refresh, diffed against
+    // the next one to estimate how fast each listener accepts
+    established_by_port: Option<(Instant, HashMap<u16, HashSet<ConnectionKey>>)>,
+    // New connections per second by local port; `None` until two samples
+    accept_rates: Option<HashMap<u16, f64>>,
+    // When each connection first showed up; `None` for those already open at
+    // startup, whose age is unknown. Empty until the first scan.
+    first_seen: Option<HashMap<ConnectionKey, Option<Instant>>>,
+    // Connections opened and closed per second over the last refresh
+    churn: (f64, f64),
+    // Hide active connections first seen longer ago than this ('w' toggles)
+    since: Option<Duration>,
+    since_window: Duration,
+    keep_unknown_age: bool,
+    // Newest-first active rows with the top one selected, like `tail -f`;
+    // cleared by manual navigation
+    follow: bool,
+    // The listening, active and combined panels' rows and counts, so frames
+    // between changes skip the filter and sort. Filled on demand and
+    // emptied by `invalidate_rows`.
+    visible_cache: RefCell<[Option<PanelCache>; 3]>,
+    // 'v' toggle; `too_short` forces the compact layout regardless
+    compact: bool,
+    too_short: bool,
+    // One-off notice shown in the footer until the next keypress
+    banner: Option<String>,
+    // Processes reaching many remote ports or hosts
+    scans: ScanDetector,
+    // --firewall-hints
+    firewall: Option<Firewall>,
+}
+
+impl App {
+    fn new(args: &Args, config: Config) -> App {
+        let initial_sort = PanelSort {
+            key: args.sort_key,
+            desc: args.sort_desc,
+        };
+        App {
+            connections: Vec::new(),
+            focused: if args.focus_active { Panel::Active } else { Panel::Listening },
+            listening_state: TableState::default(),
+            active_state: TableState::default(),
+            all_state: TableState::default(),
+            listening_sort: initial_sort,
+            active_sort: initial_sort,
+            all_sort: initial_sort,
+            last_update: Instant::now(),
+            network_monitor: build_monitor(args),
+            filter: Filter::default(),
+            filter_text: String::new(),
+            filter_input: None,
+            command_input: None,
+            finder: None,
+            view: ViewOptions {
+                tls_ports: args.tls_ports.clone(),
+                resolve_hosts: args.resolve,
+                show_flags: args.unix,
+                ephemeral_ports: None,
+                color_by: ColorBy::State,
+                anonymize: args.anonymize || args.anonymize_local,
+                anonymize_local: args.anonymize_local,
+                anonymize_salt: anonymize_salt(),
+                column_order: config.column_order.clone(),
+                show_stats: false,
+                show_cgroup: false,
+                show_container: false,
+                show_inode: false,
+                show_host: !args.ssh_hosts.is_empty(),
+                wrap_remote: false,
+                local_ipc: config.local_ipc,
+                compact_numbers: config.compact_numbers,
+                monochrome: args.no_color || !terminal_has_color(),
+                age_format: args.age_format.unwrap_or(config.age_format),
+            },
+            dns: DnsCache::new(args.dns_ttl),
+            containers: ContainerNames::default(),
+            view_mode: ViewMode::Connections,
+            process_summaries: Vec::new(),
+            status: None,
+            confirm_quit: args.confirm_quit,
+            manual: args.manual,
+            quit_pressed_at: None,
+            hide_unattributed: false,
+            hide_kernel: false,
+            show_debug: args.debug,
+            group_subnets: false,
+            merge_listeners: false,
+            expanded_subnets: HashSet::new(),
+            show_detail: false,
+            usernames: network::load_usernames(),
+            current_uid: network::current_uid(),
+            only_mine: args.me,
+            exe_prefix: args.exe_prefix.clone(),
+            ipc: None,
+            remote: (!args.ssh_hosts.is_empty()).then(|| remote::RemoteHosts::start(&args.ssh_hosts)),
+            excluded_ports: args.exclude_ports.iter().copied().collect(),
+            tcp_info: HashMap::new(),
+            socket_buffers: HashMap::new(),
+            listen_queues: HashMap::new(),
+            listen_counters: None,
+            listen_drop_rate: 0.0,
+            only_exposed: false,
+            only_queued: false,
+            remotes_state: TableState::default(),
+            remote_focus: None,
+            scans: ScanDetector::new(config.scan),
+            firewall: args.firewall_hints.then(Firewall::default),
+            config,
+            panel_areas: Vec::new(),
+            baseline: None,
+            removed: Vec::new(),
+            diff_only: false,
+            banner: None,
+            family: FamilyFilter::Both,
+            pinned: HashSet::new(),
+            checked: HashSet::new(),
+            watch: None,
+            established_by_port: None,
+            accept_rates: None,
+            first_seen: None,
+            churn: (0.0, 0.0),
+            since: args.since,
+            since_window: args.since.unwrap_or(DEFAULT_SINCE_WINDOW),
+            keep_unknown_age: args.keep_unknown_age,
+            follow: false,
+            visible_cache: RefCell::default(),
+            compact: false,
+            too_short: false,
+        }
+    }
+
+    async fn update_connections(&mut self) {
+        // The scan is blocking file I/O, proc read retries included, so the
+        // runtime moves its other tasks (IPC clients, lookups) off this worker
+        let scan = tokio::task::block_in_place(|| self.network_monitor.get_connections());
+        let mut connections = match scan {
+            Ok(connections) => connections,
+            Err(err) => {
+                // The last good listing stays up, and the next try waits
+                // for the next refresh rather than the next loop iteration
+                debug!("scan failed: {:#}", err);
+                self.set_status(format!("Scan failed: {:#}", err));
+                self.last_update = Instant::now();
+                return;
+            }
+        };
+        // Each host's last listing; the new one is shown next refresh
+        if let Some(remote) = &self.remote {
+            connections.extend(remote.connections());
+            remote.refresh(self.network_monitor.protocols());
+        }
+        self.with_stable_selection(|app| {
+            app.connections = connections;
+            app.update_removed();
+            app.update_churn();
+            app.update_first_seen();
+        });
+        self.update_scan_alerts();
+        // Start on the first row so navigation and the detail pane work
+        // straight away; an existing selection is left to the stable
+        // selection logic above
+        if self.focused_state().selected().is_none() && self.selectable_rows() > 0 {
+            self.select_row(0);
+        }
+        if self.follow {
+            self.follow_newest();
+        }
+        self.update_watch();
+        // A closed connection can't be exported, so it stops counting
+        if !self.checked.is_empty() {
+            let current: HashSet<ConnectionKey> = self.connections.iter().map(Connection::key).collect();
+            self.checked.retain(|key| current.contains(key));
+        }
+        self.last_update = Instant::now();
+        self.request_hostnames();
+        self.request_container_names();
+        self.update_aggregates();
+        self.update_tcp_info();
+        self.update_accept_rates();
+        self.update_listen_health();
+        if let Some(ipc) = &self.ipc {
+            ipc.publish(&self.connections);
+        }
+    }
+
+    // The drop counters are system-wide, so the banner can only say that some
+    // listener is overflowing; the per-socket queues point at which one
+    fn update_listen_health(&mut self) {
+        self.listen_queues = diag::listen_queues().unwrap_or_default();
+
+        let Some(counters) = network::listen_counters() else {
+            return;
+        };
+        let now = Instant::now();
+        let was_dropping = self.listen_drop_rate > 0.0;
+        if let Some((sampled_at, previous)) = self.listen_counters {
+            let secs = now.duration_since(sampled_at).as_secs_f64();
+            if secs > 0.0 {
+                self.listen_drop_rate = counters.drops.saturating_sub(previous.drops) as f64 / secs;
+            }
+        }
+        self.listen_counters = Some((now, counters));
+
+        // Only when drops start, so a dismissed banner stays dismissed
+        if self.listen_drop_rate > 0.0 && !was_dropping && self.banner.is_none() {
+            let near_full = self.listen_queues.values().filter(|queue| queue.is_near_full()).count();
+            self.banner = Some(format!(
+                "Listen queues overflowing: {:.1} SYNs dropped/s; {} listener(s) near their backlog are marked ⚠. Press any key",
+                self.listen_drop_rate, near_full
+            ));
+        }
+    }
+
+    // Like the listen queue banner, this only fires when a process is first
+    // flagged, so dismissing it doesn't bring it back every refresh
+    fn update_scan_alerts(&mut self) {
+        let flagged = self.scans.update(&self.connections);
+        let Some(&first) = flagged.first() else {
+            return;
+        };
+        if self.banner.is_some() {
+            return;
+        }
+        let name = self
+            .connections
+            .iter()
+            .find(|conn| conn.pid == Some(first))
+            .map_or_else(|| first.to_string(), Connection::pid_program);
+        let reason = self.scans.suspicion_for(first).map(|suspicion| suspicion.describe(self.scans.window()));
+        let more = match flagged.len() {
+            1 => String::new(),
+            n => format!(" (and {} more)", n - 1),
+        };
+        self.banner = Some(format!(
+            "Possible port scan: {} reached {}{}; see the active panel. Press any key",
+            name,
+            reason.unwrap_or_default(),
+            more
+        ));
+    }
+
+    // Diffs against the keys in `first_seen`, so it must run before that is
+    // replaced; the first scan has nothing to diff against and reports 0
+    fn update_churn(&mut self) {
+        let Some(previous) = &self.first_seen else {
+            return;
+        };
+        let secs = self.last_update.elapsed().as_secs_f64();
+        if secs <= 0.0 {
+            return;
+        }
+        let current: HashSet<ConnectionKey> = self.connections.iter().map(Connection::key).collect();
+        let opened = current.iter().filter(|key| !previous.contains_key(key)).count();
+        let closed = previous.keys().filter(|key| !current.contains(key)).count();
+        self.churn = (opened as f64 / secs, closed as f64 / secs);
+    }
+
+    // Keys are dropped once a connection goes away, so a reused endpoint
+    // counts as new
+    fn update_first_seen(&mut self) {
+        let now = Instant::now();
+
+        // A --ssh host's first listing (or first after an outage) is like
+        // the startup scan: its connections were already open
+        let known_hosts: HashSet<Option<&str>> = match &self.first_seen {
+            Some(previous) => previous.keys().map(|key| key.host.as_deref()).collect(),
+            None => HashSet::new(),
+        };
+
+        let mut first_seen = HashMap::with_capacity(self.connections.len());
+        for conn in &self.connections {
+            let key = conn.key();
+            let seen = match &self.first_seen {
+                Some(previous) if known_hosts.contains(&key.host.as_deref()) => {
+                    previous.get(&key).copied().unwrap_or(Some(now))
+                }
+                _ => None,
+            };
+            first_seen.insert(key, seen);
+        }
+        self.first_seen = Some(first_seen);
+    }
+
+    fn first_seen(&self, conn: &Connection) -> Option<Instant> {
+        self.first_seen.as_ref()?.get(&conn.key()).copied().flatten()
+    }
+
+    // Without fd access, or for another user's sockets when not root, a
+    // missing PID just means we couldn't look, so nothing is claimed
+    fn unowned(&self, conn: &Connection) -> Option<Unowned> {
+        if conn.is_kernel() {
+            return Some(Unowned::Kernel);
+        }
+        if conn.state != "LISTEN" || conn.pid.is_some() || conn.host.is_some() || !self.network_monitor.has_fd_access() {
+            return None;
+        }
+        (self.current_uid == 0 || conn.uid == Some(self.current_uid)).then_some(Unowned::Orphan)
+    }
+
+    // A bare "-" says nothing about a socket without a PID, but /proc still
+    // has its uid (the same one sock_diag reports), so that's shown instead
+    fn program_label(&self, conn: &Connection) -> String {
+        match conn.uid.filter(|_| !conn.is_attributed()) {
+            Some(uid) => format!("- ({})", self.owner_name(conn, uid)),
+            None => conn.pid_program(),
+        }
+    }
+
+    // Names come from this machine's /etc/passwd, so --ssh rows keep the
+    // bare uid
+    fn owner_name(&self, conn: &Connection, uid: u32) -> String {
+        match conn.host {
+            Some(_) => format!("uid {}", uid),
+            None => self.username(uid),
+        }
+    }
+
+    fn within_since(&self, conn: &Connection) -> bool {
+        let Some(window) = self.since else {
+            return true;
+        };
+        match self.first_seen(conn) {
+            Some(seen) => seen.elapsed() <= window,
+            None => self.keep_unknown_age,
+        }
+    }
+
+    // Connections that open and close between two refreshes are never seen,
+    // so this undercounts short-lived traffic
+    fn update_accept_rates(&mut self) {
+        let mut current: HashMap<u16, HashSet<ConnectionKey>> = HashMap::new();
+        for conn in &self.connections {
+            if conn.protocol.starts_with("TCP") && conn.state == "ESTABLISHED" {
+                current.entry(conn.local_port).or_default().insert(conn.key());
+            }
+        }
+
+        let now = Instant::now();
+        if let Some((sampled_at, previous)) = &self.established_by_port {
+            let secs = now.duration_since(*sampled_at).as_secs_f64();
+            if secs > 0.0 {
+                let rates = current.iter().map(|(port, keys)| {
+                    let new = match previous.get(port) {
+                        Some(seen) => keys.difference(seen).count(),
+                        None => keys.len(),
+                    };
+                    (*port, new as f64 / secs)
+                });
+                self.accept_rates = Some(rates.collect());
+            }
+        }
+        self.established_by_port = Some((now, current));
+    }
+
+    // The netlink dump is only needed for the detail pane; an empty map just
+    // means the pane shows proc data alone
+    fn update_tcp_info(&mut self) {
+        if self.show_detail {
+            self.tcp_info = diag::established_tcp_info().unwrap_or_default();
+            self.socket_buffers = diag::socket_buffers().unwrap_or_default();
+        } else {
+            self.tcp_info = HashMap::new();
+            self.socket_buffers = HashMap::new();
+        }
+    }
+
+    // Aggregation reads /proc/<pid>/io, so only do it while the view is shown
+    fn update_aggregates(&mut self) {
+        if self.view_mode != ViewMode::Processes {
+            return;
+        }
+
+        let filter = &self.filter;
+        // Remote PIDs would be looked up in this machine's /proc
+        self.process_summaries = aggregate::by_process(
+            self.connections.iter().filter(|conn| conn.host.is_none() && filter.matches(conn)),
+            &mut self.network_monitor,
+        );
+    }
+
+    // Connections that belong in `panel` and pass the filter, before port
+    // exclusions and the display-only toggles are applied
+    fn panel_matches(&self, panel: Panel) -> impl Iterator<Item = &Connection> {
+        self.connections.iter().filter(move |conn| self.belongs_in(conn, panel))
+    }
+
+    fn belongs_in(&self, conn: &Connection, panel: Panel) -> bool {
+        let in_panel = match panel {
+            Panel::Listening => conn.is_listening(),
+            Panel::Active => !conn.is_listening(),
+            Panel::All => true,
+        };
+        let remote_ok = match &self.remote_focus {
+            Some(remote) if !conn.is_listening() => &conn.remote_address == remote,
+            _ => true,
+        };
+        // Listeners stay put; the window is about new traffic
+        let recent = conn.is_listening() || self.within_since(conn);
+
+        in_panel
+            && self.filter.matches(conn)
+            && self.family.matches(conn)
+            && (!self.only_mine || conn.uid == Some(self.current_uid))
+            && exe_matches(conn, self.exe_prefix.as_deref())
+            && remote_ok
+            && recent
+    }
+
+    fn update_removed(&mut self) {
+        self.removed.clear();
+        let Some(baseline) = &self.baseline else {
+            return;
+        };
+
+        let current: HashSet<ConnectionKey> = self.connections.iter().map(|conn| conn.key()).collect();
+        self.removed = baseline.keys().filter(|key| !current.contains(key)).cloned().collect();
+    }
+
+    // Baseline connections that would be shown in `panel` but no longer exist
+    fn removed_connections(&self, panel: Panel) -> Vec<&Connection> {
+        let Some(baseline) = &self.baseline else {
+            return Vec::new();
+        };
+
+        let mut removed: Vec<&Connection> = self
+            .removed
+            .iter()
+            .filter_map(|key| baseline.get(key))
+            .filter(|conn| self.belongs_in(conn, panel) && !self.is_excluded(conn))
+            .collect();
+
+        // HashMap order is arbitrary, so these are always sorted
+        let sort = self.rows_sort(panel);
+        sort_connections(&mut removed, sort.key.unwrap_or(SortKey::LocalPort), sort.desc);
+        removed
+    }
+
+    fn is_added(&self, conn: &Connection) -> bool {
+        self.baseline.as_ref().is_some_and(|baseline| !baseline.contains_key(&conn.key()))
+    }
+
+    fn toggle_baseline(&mut self) {
+        if self.baseline.take().is_some() {
+            self.removed.clear();
+            self.diff_only = false;
+            self.set_status("Baseline cleared");
+        } else {
+            self.baseline = Some(self.connections.iter().map(|conn| (conn.key(), conn.clone())).collect());
+            self.set_status(format!("Baseline of {} connections taken ('B' shows only changes)", self.connections.len()));
+        }
+    }
+
+    fn top_talkers(&self) -> Vec<(String, usize)> {
+        aggregate::top_remotes(aggregate::by_remote(self.panel_candidates(Panel::Active)), TOP_TALKERS)
+    }
+
+    // Leaves the top talkers view showing only the selected remote's
+    // connections
+    fn drill_into_remote(&mut self) {
+        let Some(selected) = self.remotes_state.selected() else {
+            return;
+        };
+        let Some((remote, _)) = self.top_talkers().into_iter().nth(selected) else {
+            return;
+        };
+
+        self.remote_focus = Some(remote);
+        self.invalidate_rows();
+        self.view_mode = ViewMode::Connections;
+        self.focused = Panel::Active;
+        self.select_row(0);
+    }
+
+    fn panel_candidates(&self, panel: Panel) -> impl Iterator<Item = &Connection> {
+        self.panel_matches(panel).filter(|conn| !self.is_excluded(conn))
+    }
+
+    fn is_excluded(&self, conn: &Connection) -> bool {
+        self.excluded_ports.contains(&conn.local_port) || self.excluded_ports.contains(&conn.remote_port)
+    }
+
+    // Excludes the port that identifies the selected row's service: the bound
+    // port for listeners, the remote port for active connections
+    fn exclude_selected_port(&mut self) {
+        let Some(conn) = self.selected_connection() else {
+            return;
+        };
+
+        let port = if conn.is_listening() { conn.local_port } else { conn.remote_port };
+        if port == 0 {
+            return;
+        }
+
+        self.with_stable_selection(|app| {
+            app.excluded_ports.insert(port);
+        });
+        self.set_status(format!("Excluded port {} ('X' clears exclusions)", port));
+    }
+
+    fn visible_connections(&self, panel: Panel) -> Vec<&Connection> {
+        self.panel_cache(panel).visible.iter().map(|&index| &self.connections[index]).collect()
+    }
+
+    // Don't hold on to the result across anything that may fill another
+    // panel's cache
+    fn panel_cache(&self, panel: Panel) -> Ref<'_, PanelCache> {
+        let slot = panel as usize;
+        if self.visible_cache.borrow()[slot].is_none() {
+            let cache = self.fill_panel_cache(panel);
+            self.visible_cache.borrow_mut()[slot] = Some(cache);
+        }
+        Ref::map(self.visible_cache.borrow(), |caches| caches[slot].as_ref().expect("filled above"))
+    }
+
+    fn fill_panel_cache(&self, panel: Panel) -> PanelCache {
+        let visible = self.sorted_visible(panel).into_iter().map(|Indexed(index, _)| index).collect();
+        let mut cache = PanelCache { visible, removed: self.removed_connections(panel).len(), ..Default::default() };
+
+        for conn in self.panel_matches(panel) {
+            if self.is_excluded(conn) {
+                cache.excluded += 1;
+                continue;
+            }
+            if self.is_added(conn) {
+                cache.added.insert(conn.key());
+            }
+            cache.unattributed += !conn.is_attributed() as usize;
+            cache.kernel += conn.is_kernel() as usize;
+            cache.exposed += (conn.is_listening() && conn.is_exposed()) as usize;
+            if is_queued(conn) {
+                let (count, largest) = cache.queued;
+                cache.queued = (count + 1, largest.max(conn.send_queue.max(conn.recv_queue)));
+            }
+        }
+
+        if panel == Panel::Listening && self.merge_listeners {
+            let mut by_port: HashMap<u16, Vec<usize>> = HashMap::new();
+            for &index in &cache.visible {
+                let conn = &self.connections[index];
+                if mergeable(conn) {
+                    by_port.entry(conn.local_port).or_default().push(index);
+                }
+            }
+            for members in by_port.into_values().filter(|members| members.len() > 1) {
+                for &index in &members {
+                    let mut group = vec![index];
+                    group.extend(members.iter().copied().filter(|&other| other != index));
+                    cache.merged.insert(self.connections[index].key(), group);
+                }
+            }
+        }
+
+        cache
+    }
+
+    fn sorted_visible(&self, panel: Panel) -> Vec<Indexed<'_>> {
+        let mut connections: Vec<Indexed> = self
+            .connections
+            .iter()
+            .enumerate()
+            .filter(|(_, conn)| self.belongs_in(conn, panel) && !self.is_excluded(conn))
+            .filter(|(_, conn)| !self.hide_unattributed || conn.is_attributed())
+            .filter(|(_, conn)| !self.hide_kernel || !conn.is_kernel())
+            .filter(|(_, conn)| !conn.is_listening() || !self.only_exposed || conn.is_exposed())
+            .filter(|(_, conn)| conn.is_listening() || !self.only_queued || is_queued(conn))
+            .map(|(index, conn)| Indexed(index, conn))
+            .collect();
+
+        let sort = self.rows_sort(panel);
+        if let Some(key) = sort.key {
+            sort_connections(&mut connections, key, sort.desc);
+        }
+
+        // Connections older than startup have no known age and sink
+        if self.follow && panel != Panel::Listening {
+            connections.sort_by_key(|Indexed(_, conn)| std::cmp::Reverse(self.first_seen(conn)));
+        }
+
+        // Stable, so pinned rows keep the chosen order among themselves
+        if !self.pinned.is_empty() {
+            connections.sort_by_key(|Indexed(_, conn)| !self.pinned.contains(&conn.key()));
+        }
+
+        connections
+    }
+
+    // Must follow any change to the connections or to what filters and
+    // sorts them; `with_stable_selection` does this for its callers
+    fn invalidate_rows(&mut self) {
+        *self.visible_cache.get_mut() = Default::default();
+    }
+
+    // Selects the newest active row and scrolls it into view. In the compact
+    // table the active rows come after the listeners.
+    fn follow_newest(&mut self) {
+        let (panel, first_active) = if self.compact_layout() {
+            (Panel::All, self.panel_rows(Panel::Listening).len())
+        } else {
+            (Panel::Active, 0)
+        };
+        if self.panel_rows(panel).len() <= first_active {
+            return;
+        }
+
+        let state = self.panel_state(panel);
+        state.select(Some(first_active));
+        *state.offset_mut() = first_active;
+    }
+
+    fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        self.invalidate_rows();
+        if self.follow {
+            if !self.compact_layout() {
+                self.focused = Panel::Active;
+            }
+            self.follow_newest();
+        }
+    }
+
+    fn stop_following(&mut self) {
+        if self.follow {
+            self.follow = false;
+            self.invalidate_rows();
+        }
+    }
+
+    fn toggle_pin(&mut self) {
+        let Some(key) = self.selected_connection().map(|conn| conn.key()) else {
+            return;
+        };
+
+        self.with_stable_selection(|app| {
+            if !app.pinned.remove(&key) {
+                app.pinned.insert(key);
+            }
+        });
+    }
+
+    fn toggle_checked(&mut self) {
+        let Some(key) = self.selected_connection().map(|conn| conn.key()) else {
+            return;
+        };
+        if !self.checked.remove(&key) {
+            self.checked.insert(key);
+        }
+    }
+
+    // Watching another connection replaces the current watch
+    fn toggle_watch(&mut self) {
+        let Some(conn) = self.selected_connection() else {
+            return;
+        };
+        let key = conn.key();
+        if self.watch.as_ref().is_some_and(|watch| watch.key == key) {
+            self.watch = None;
+            self.set_status("Stopped watching");
+            return;
+        }
+
+        let message = format!("Watching {} → {} ('W' again to stop)", conn.local_display(), conn.remote_display());
+        let timeline = vec![(Instant::now(), conn.state_display().to_string())];
+        self.watch = Some(Watch { key, timeline });
+        self.set_status(message);
+    }
+
+    // Appends state changes; once the connection is gone the timeline goes
+    // to the footer, since there is no row left to show it on
+    fn update_watch(&mut self) {
+        let Some(watch) = &mut self.watch else {
+            return;
+        };
+        match self.connections.iter().find(|conn| conn.key() == watch.key) {
+            Some(conn) => {
+                let changed = match watch.timeline.last() {
+                    Some((_, state)) => state != conn.state_display(),
+                    None => true,
+                };
+                if changed {
+                    watch.timeline.push((Instant::now(), conn.state_display().to_string()));
+                }
+            }
+            None => {
+                let message = format!("Watched connection closed: {} → gone", watch.describe());
+                self.watch = None;
+                self.set_status(message);
+            }
+        }
+    }
+
+    // Rows as displayed: the visible connections, with remotes collapsed per
+    // subnet in the active panel when grouping is on. Single-member subnets
+    // stay plain rows since collapsing them would hide nothing.
+    fn panel_rows(&self, panel: Panel) -> Vec<PanelRow<'_>> {
+        // The combined table keeps listeners above active connections, each
+        // built exactly as its own panel would be
+        if panel == Panel::All {
+            let mut rows = self.panel_rows(Panel::Listening);
+            rows.extend(self.panel_rows(Panel::Active));
+            return rows;
+        }
+
+        let mut rows = self.connection_rows(panel);
+        rows.extend(self.removed_connections(panel).into_iter().map(PanelRow::Removed));
+        rows
+    }
+
+    fn connection_rows(&self, panel: Panel) -> Vec<PanelRow<'_>> {
+        let mut connections = self.visible_connections(panel);
+        if self.diff_only {
+            connections.retain(|conn| self.is_added(conn));
+        }
+
+        // The first socket on each port stands for the rest
+        if panel == Panel::Listening && self.merge_listeners {
+            let mut ports = HashSet::new();
+            connections.retain(|conn| !mergeable(conn) || ports.insert(conn.local_port));
+        }
+
+        if panel != Panel::Active || !self.group_subnets {
+            return connections.into_iter().map(PanelRow::Connection).collect();
+        }
+
+        let mut rows = Vec::new();
+        for (subnet, members) in aggregate::group_by_subnet(connections) {
+            if members.len() == 1 {
+                rows.push(PanelRow::Connection(members[0]));
+                continue;
+            }
+
+            let expanded = self.expanded_subnets.contains(&subnet);
+            rows.push(PanelRow::Subnet {
+                subnet,
+                count: members.len(),
+                expanded,
+            });
+            if expanded {
+                rows.extend(members.into_iter().map(PanelRow::Connection));
+            }
+        }
+        rows
+    }
+
+    // All visible listeners on `conn`'s port while merging, `conn` first;
+    // empty when it isn't merged with anything
+    fn listener_group(&self, conn: &Connection) -> Vec<&Connection> {
+        if !self.merge_listeners || !conn.is_listening() || !mergeable(conn) {
+            return Vec::new();
+        }
+        match self.panel_cache(Panel::Listening).merged.get(&conn.key()) {
+            Some(group) => group.iter().map(|&index| &self.connections[index]).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn selected_row_key(&self, panel: Panel) -> Option<RowKey> {
+        let selected = self.panel_state_ref(panel).selected()?;
+        Some(self.panel_rows(panel).get(selected)?.key())
+    }
+
+    // The connection under the cursor in the focused panel, if the cursor is
+    // on a connection rather than a group row
+    fn selected_connection(&self) -> Option<&Connection> {
+        let selected = self.panel_state_ref(self.current_panel()).selected()?;
+        match self.panel_rows(self.current_panel()).into_iter().nth(selected)? {
+            PanelRow::Connection(conn) | PanelRow::Removed(conn) => Some(conn),
+            PanelRow::Subnet { .. } => None,
+        }
+    }
+
+    fn toggle_subnet(&mut self) {
+        if let Some(RowKey::Subnet(subnet)) = self.selected_row_key(self.current_panel()) {
+            self.with_stable_selection(|app| {
+                if !app.expanded_subnets.remove(&subnet) {
+                    app.expanded_subnets.insert(subnet);
+                }
+            });
+        }
+    }
+
+    fn panel_title(&self, panel: Panel) -> String {
+        let mut title = match panel {
+            Panel::Listening => "Listening Ports".to_string(),
+            Panel::Active => "Active Connections".to_string(),
+            Panel::All => "Connections".to_string(),
+        };
+
+        if let Some(remote) = self.remote_focus.as_ref().filter(|_| panel != Panel::Listening) {
+            let remote = self.view.masked(remote, false).unwrap_or_else(|| remote.clone());
+            title.push_str(&format!(" (remote {}, Esc to clear)", remote));
+        }
+
+        if let Some(label) = self.family.label() {
+            title.push_str(&format!(" ({})", label));
+        }
+
+        if self.follow && panel != Panel::Listening {
+            title.push_str(" (following newest, 'F' to stop)");
+        }
+
+        if let Some(window) = self.since.filter(|_| panel != Panel::Listening) {
+            title.push_str(&format!(" (first seen < {}s ago)", window.as_secs()));
+        }
+
+        if panel != Panel::Active && self.only_exposed {
+            title.push_str(" (exposed only)");
+        }
+
+        if panel != Panel::Listening && self.only_queued {
+            title.push_str(" (queued only)");
+        }
+
+        let cache = self.panel_cache(panel);
+        if self.baseline.is_some() {
+            title.push_str(&format!(" (vs baseline: +{} -{})", cache.added.len(), cache.removed));
+        }
+
+        if !self.excluded_ports.is_empty() {
+            title.push_str(&format!(" (excluded: {})", cache.excluded));
+        }
+
+        if self.hide_unattributed {
+            title.push_str(&format!(" (hidden: {} unattributed)", cache.unattributed));
+        }
+
+        if self.hide_kernel {
+            title.push_str(&format!(" (hidden: {} kernel)", cache.kernel));
+        }
+
+        title
+    }
+
+    fn request_hostnames(&mut self) {
+        if !self.view.resolve_hosts {
+            return;
+        }
+
+        for conn in &self.connections {
+            if conn.remote_port > 0 {
+                self.dns.request(&conn.remote_address);
+            }
+        }
+    }
+
+    fn request_container_names(&mut self) {
+        if !self.view.show_container {
+            return;
+        }
+
+        for conn in &self.connections {
+            if let Some(id) = conn.cgroup.as_deref().and_then(network::container_id) {
+                self.containers.request(id);
+            }
+        }
+    }
+
+    fn panel_state_ref(&self, panel: Panel) -> &TableState {
+        match panel {
+            Panel::Listening => &self.listening_state,
+            Panel::Active => &self.active_state,
+            Panel::All => &self.all_state,
+        }
+    }
+
+    fn panel_state(&mut self, panel: Panel) -> &mut TableState {
+        match panel {
+            Panel::Listening => &mut self.listening_state,
+            Panel::Active => &mut self.active_state,
+            Panel::All => &mut self.all_state,
+        }
+    }
+
+    fn panel_sort(&mut self, panel: Panel) -> &mut PanelSort {
+        match panel {
+            Panel::Listening => &mut self.listening_sort,
+            Panel::Active => &mut self.active_sort,
+            Panel::All => &mut self.all_sort,
+        }
+    }
+
+    // The order `panel`'s rows are built in. The compact table is made of the
+    // listening and active rows, so in that layout they follow its sort.
+    fn rows_sort(&self, panel: Panel) -> PanelSort {
+        match panel {
+            _ if self.compact_layout() => self.all_sort,
+            Panel::Listening => self.listening_sort,
+            Panel::Active => self.active_sort,
+            Panel::All => self.all_sort,
+        }
+    }
+
+    // Runs `change` (a refresh, re-sort or re-filter) and then moves each
+    // panel's selection back onto the same connection, or onto the row now
+    // closest to where it was if that connection is gone.
+    fn with_stable_selection(&mut self, change: impl FnOnce(&mut Self)) {
+        let panels = [Panel::Listening, Panel::Active, Panel::All];
+        let saved = panels.map(|panel| {
+            let selected = self.panel_state(panel).selected()?;
+            Some((self.selected_row_key(panel)?, selected))
+        });
+
+        change(self);
+        self.invalidate_rows();
+
+        for (panel, saved) in panels.into_iter().zip(saved) {
+            let Some((key, previous_index)) = saved else {
+                continue;
+            };
+
+            let visible = self.panel_rows(panel);
+            let selection = match visible.iter().position(|row| row.key() == key) {
+                Some(index) => Some(index),
+                None if visible.is_empty() => None,
+                None => Some(previous_index.min(visible.len() - 1)),
+            };
+            self.panel_state(panel).select(selection);
+        }
+    }
+
+    // The selection that navigation keys move: the focused panel's, or the
+    // top talkers table while that view is shown
+    fn focused_state(&mut self) -> &mut TableState {
+        if self.view_mode == ViewMode::Remotes {
+            return &mut self.remotes_state;
+        }
+        self.panel_state(self.current_panel())
+    }
+
+    fn compact_layout(&self) -> bool {
+        self.compact || self.too_short
+    }
+
+    // The panel navigation applies to: the combined table in the compact
+    // layout, otherwise the focused one
+    fn current_panel(&self) -> Panel {
+        if self.compact_layout() {
+            Panel::All
+        } else {
+            self.focused
+        }
+    }
+
+    fn selectable_rows(&self) -> usize {
+        match self.view_mode {
+            ViewMode::Remotes => self.top_talkers().len(),
+            _ => self.panel_rows(self.current_panel()).len(),
+        }
+    }
+
+    // A left click on a panel focuses it; on a header cell it sorts by that
+    // column, flipping the direction when it's already the sort column
+    fn handle_click(&mut self, x: u16, y: u16) {
+        self.stop_following();
+        let Some(&(panel, area)) = self
+            .panel_areas
+            .iter()
+            .find(|(_, area)| x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height)
+        else {
+            return;
+        };
+
+        if panel != Panel::All {
+            self.focused = panel;
+        }
+        if y != area.y + 1 {
+            return;
+        }
+
+        let columns = panel_columns(panel, &self.view);
+        let Some(column) = column_at(&columns, area, x) else {
+            return;
+        };
+        let Some(&default_key) = column.sort_keys.first() else {
+            return;
+        };
+
+        let already_sorted = self.rows_sort(panel).key.is_some_and(|key| column.sort_keys.contains(&key));
+        self.with_stable_selection(|app| {
+            let sort = app.panel_sort(panel);
+            if already_sorted {
+                sort.desc = !sort.desc;
+            } else {
+                *sort = PanelSort {
+                    key: Some(default_key),
+                    desc: false,
+                };
+            }
+        });
+    }
+
+    fn toggle_focus(&mut self) {
+        self.focused = match self.focused {
+            Panel::Listening => Panel::Active,
+            _ => Panel::Listening,
+        };
+    }
+
+    // Single steps wrap around like before; counted moves (e.g. "5j") clamp
+    // at the ends instead.
+    fn move_selection(&mut self, steps: isize) {
+        let len = self.selectable_rows();
+        if len == 0 {
+            self.focused_state().select(None);
+            return;
+        }
+
+        let last = len as isize - 1;
+        let i = match self.focused_state().selected() {
+            Some(i) => {
+                let target = i.min(len - 1) as isize + steps;
+                match steps.abs() {
+                    1 if target > last => 0,
+                    1 if target < 0 => last,
+                    _ => target.clamp(0, last),
+                }
+            }
+            None => 0,
+        };
+        self.focused_state().select(Some(i as usize));
+    }
+
+    fn select_row(&mut self, index: usize) {
+        let len = self.selectable_rows();
+        let selection = (len > 0).then(|| index.min(len - 1));
+        self.focused_state().select(selection);
+    }
+
+    // The wheel moves the selection unless a prompt or popup has the input
+    fn accepts_scroll(&self) -> bool {
+        self.banner.is_none() && self.filter_input.is_none() && self.command_input.is_none() && self.finder.is_none()
+    }
+
+    fn select_last(&mut self) {
+        self.select_row(usize::MAX);
+    }
+
+    // Rows that fit in the focused panel, for PageUp/PageDown
+    fn page_rows(&self) -> usize {
+        self.panel_areas
+            .iter()
+            .find(|(panel, _)| *panel == self.current_panel())
+            // Borders, header and the header's margin
+            .map_or(10, |(_, area)| area.height.saturating_sub(4).max(1) as usize)
+    }
+
+    // Handles navigation keys, including count prefixes and "gg". Returns
+    // false when the key isn't a navigation key, after clearing any prefix.
+    // `action` is what the key is bound to; the count, "gg"/"G", Home/End
+    // and PageUp/PageDown motions only see unbound keys
+    fn handle_navigation_key(&mut self, code: KeyCode, action: Option<Action>, keys: &mut KeySequence) -> bool {
+        // Moving by hand means the user wants to look at something else;
+        // prefixes ("5", the first 'g') don't move yet
+        let moves = match (action, code) {
+            (Some(Action::Up | Action::Down), _) => true,
+            (None, KeyCode::Char('g')) => keys.g_pending,
+            (None, KeyCode::Char('G') | KeyCode::Home | KeyCode::End | KeyCode::PageUp | KeyCode::PageDown) => true,
+            _ => false,
+        };
+        if moves {
+            self.stop_following();
+        }
+
+        match (action, code) {
+            (Some(Action::Down), _) => {
+                let count = keys.take_count().unwrap_or(1);
+                self.move_selection(count as isize);
+            }
+            (Some(Action::Up), _) => {
+                let count = keys.take_count().unwrap_or(1);
+                self.move_selection(-(count as isize));
+            }
+            (None, KeyCode::Char(c @ '0'..='9')) if c != '0' || keys.count.is_some() => {
+                let digit = c as usize - '0' as usize;
+                keys.count = Some(keys.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                keys.g_pending = false;
+            }
+            (None, KeyCode::Char('g')) if keys.g_pending => {
+                // "gg" goes to the top, "5gg" to the fifth row
+                let row = keys.take_count().map(|n| n.saturating_sub(1)).unwrap_or(0);
+                self.select_row(row);
+            }
+            (None, KeyCode::Char('g')) => keys.g_pending = true,
+            (None, KeyCode::Char('G')) => match keys.take_count() {
+                Some(n) => self.select_row(n.saturating_sub(1)),
+                None => self.select_last(),
+            },
+            (None, KeyCode::Home) => {
+                keys.reset();
+                self.select_row(0);
+            }
+            (None, KeyCode::End) => {
+                keys.reset();
+                self.select_last();
+            }
+            // Clamped like a counted move rather than wrapping
+            (None, KeyCode::PageDown) => {
+                keys.reset();
+                self.move_selection(self.page_rows().max(2) as isize);
+            }
+            (None, KeyCode::PageUp) => {
+                keys.reset();
+                self.move_selection(-(self.page_rows().max(2) as isize));
+            }
+            _ => {
+                keys.reset();
+                return false;
+            }
+        }
+        true
+    }
+
+    fn username(&self, uid: u32) -> String {
+        match self.usernames.get(&uid) {
+            Some(name) => name.clone(),
+            None => uid.to_string(),
+        }
+    }
+
+    // Persistent indicators for the footer, e.g. active filters
+    fn status_segments(&self) -> Vec<String> {
+        let mut segments = Vec::new();
+        if !self.filter.is_empty() {
+            segments.push(format!("Filter: {} ('/' to edit, empty to clear)", self.filter_text));
+        }
+        let protocols = self.network_monitor.protocols();
+        if !protocols.is_all() {
+            segments.push(format!("Protocols: {} ('T'/'U' to toggle)", protocols.names().join(",")));
+        }
+        if self.only_mine {
+            segments.push(format!("User: {} (uid {})", self.username(self.current_uid), self.current_uid));
+        }
+        if let Some(prefix) = &self.exe_prefix {
+            segments.push(format!("Exe: {}", prefix.display()));
+        }
+        if !self.checked.is_empty() {
+            segments.push(format!("{} checked (Alt+E to export)", self.checked.len()));
+        }
+        segments.push(format!("Source: {} ('L' to cycle)", self.network_monitor.source().name()));
+        if let Some(firewall) = &self.firewall {
+            segments.push(firewall.describe());
+        }
+        if self.manual {
+            segments.push(format!("Manual refresh ('R'), updated {}s ago", self.last_update.elapsed().as_secs()));
+        }
+        if let Some(remote) = &self.remote {
+            for (host, err) in remote.errors() {
+                segments.push(format!("ssh {}: {}", host, err));
+            }
+        }
+        segments.push(format!("{:.1} new/s, {:.1} closed/s", self.churn.0, self.churn.1));
+        segments
+    }
+
+    // 'T' and 'U' switch a protocol's v4 and v6 tables together; the next
+    // fetch skips (or reads again) the corresponding files
+    fn toggle_protocol(&mut self, tcp: bool) {
+        let mut protocols = self.network_monitor.protocols();
+        let (v4, v6) = if tcp {
+            (&mut protocols.tcp, &mut protocols.tcp6)
+        } else {
+            (&mut protocols.udp, &mut protocols.udp6)
+        };
+        let enable = !(*v4 || *v6);
+        *v4 = enable;
+        *v6 = enable;
+        self.network_monitor.set_protocols(protocols);
+    }
+
+    // What the tables show, filtered and sorted, listeners first
+    fn shown_connections(&self) -> Vec<&Connection> {
+        if self.compact_layout() {
+            return self.visible_connections(Panel::All);
+        }
+        let mut connections = self.visible_connections(Panel::Listening);
+        connections.extend(self.visible_connections(Panel::Active));
+        connections
+    }
+
+    // By default the shown connections; `all` writes every scanned
+    // connection instead
+    fn export(&mut self, all: bool) {
+        let connections: Vec<&Connection> = if all {
+            self.connections.iter().collect()
+        } else {
+            self.shown_connections()
+        };
+        let message = match export::write_snapshot(&connections) {
+            Ok(path) => format!("Exported {} connections to {}", connections.len(), path.display()),
+            Err(err) => format!("Export failed: {}", err),
+        };
+        self.set_status(message);
+    }
+
+    // Only the rows ticked with Space, in scan order and regardless of
+    // filters, so rows checked under an earlier filter are kept
+    fn export_checked(&mut self) {
+        if self.checked.is_empty() {
+            self.set_status("Nothing checked; Space ticks the selected row".to_string());
+            return;
+        }
+        let connections: Vec<&Connection> =
+            self.connections.iter().filter(|conn| self.checked.contains(&conn.key())).collect();
+        let message = match export::write_snapshot(&connections) {
+            Ok(path) => format!("Exported {} checked connections to {}", connections.len(), path.display()),
+            Err(err) => format!("Export failed: {}", err),
+        };
+        self.set_status(message);
+    }
+
+    // The shown connections as Markdown, one table per panel with that
+    // panel's columns. Cells are the plain text of the table's, without
+    // markers like pins or the TLS lock.
+    fn copy_markdown(&mut self) {
+        let panels: &[Panel] = if self.compact_layout() { &[Panel::All] } else { &[Panel::Listening, Panel::Active] };
+        let mut markdown = String::new();
+        let mut count = 0;
+        for &panel in panels {
+            let columns = panel_columns(panel, &self.view);
+            let headers: Vec<&str> = columns.iter().map(|column| column.title).collect();
+            let connections = self.visible_connections(panel);
+            let rows: Vec<Vec<String>> = connections
+                .iter()
+                .map(|conn| columns.iter().map(|column| self.cell_text(column.kind, conn)).collect())
+                .collect();
+            count += rows.len();
+
+            if !markdown.is_empty() {
+                markdown.push('\n');
+            }
+            markdown.push_str(&format!("### {}\n\n", self.panel_title(panel)));
+            markdown.push_str(&export::markdown_table(&headers, &rows));
+        }
+
+        let message = match export::copy_to_clipboard(&markdown) {
+            Ok(()) => format!("Copied {} connections as Markdown (via the terminal, OSC 52)", count),
+            Err(err) => format!("Copy failed: {}", err),
+        };
+        self.set_status(message);
+    }
+
+    fn cell_text(&self, kind: ColumnKind, conn: &Connection) -> String {
+        let established = self.established_by_port.as_ref().map(|(_, by_port)| by_port);
+        match kind {
+            ColumnKind::Host => conn.host.clone().unwrap_or_else(|| "local".to_string()),
+            ColumnKind::Kind => if conn.is_listening() { "listen" } else { "active" }.to_string(),
+            ColumnKind::Proto => conn.protocol.clone(),
+            ColumnKind::Local => self.view.local_endpoint(conn),
+            ColumnKind::Iface => conn.interface.clone().unwrap_or_else(|| "-".to_string()),
+            ColumnKind::Flags => conn.flags.clone().unwrap_or_default(),
+            ColumnKind::AcceptRate => match &self.accept_rates {
+                Some(rates) if conn.protocol.starts_with("TCP") && conn.state == "LISTEN" => {
+                    format!("~{:.1}", rates.get(&conn.local_port).copied().unwrap_or(0.0))
+                }
+                _ => "-".to_string(),
+            },
+            ColumnKind::Established => {
+                established_on(conn, established).map_or("-".to_string(), |count| count.to_string())
+            }
+            ColumnKind::Remote => match (self.view.masked(&conn.remote_address, false), self.dns.hostname(&conn.remote_address)) {
+                (Some(name), _) => format!("{}:{}", name, conn.remote_port),
+                (None, Some(host)) if self.view.resolve_hosts => format!("{}:{}", host, conn.remote_port),
+                _ => conn.remote_display(),
+            },
+            ColumnKind::Scope => network::address_scope(&conn.remote_address).map_or("-", |scope| scope.name()).to_string(),
+            ColumnKind::State => conn.state_display().to_string(),
+            ColumnKind::Age => match self.first_seen(conn) {
+                Some(seen) => format_age(self.view.age_format, seen),
+                None => "-".to_string(),
+            },
+            ColumnKind::RecvQueue => conn.recv_queue.to_string(),
+            ColumnKind::SendQueue => conn.send_queue.to_string(),
+            ColumnKind::Retransmits if conn.protocol.starts_with("TCP") => conn.retransmits.to_string(),
+            ColumnKind::Retransmits => "-".to_string(),
+            ColumnKind::Drops => conn.drops.map_or("-".to_string(), |drops| drops.to_string()),
+            ColumnKind::Cgroup => conn.cgroup.as_deref().map_or("-".to_string(), network::cgroup_label),
+            ColumnKind::Container => container_label(conn, &self.containers),
+            ColumnKind::Inode => inode_text(conn),
+            ColumnKind::Program => {
+                let label = match self.unowned(conn) {
+                    Some(Unowned::Orphan) => format!("{} (orphan?)", self.program_label(conn)),
+                    Some(Unowned::Kernel) | None => self.program_label(conn),
+                };
+                if self.firewall.as_ref().is_some_and(|firewall| firewall.blocks(conn)) {
+                    format!("{} (firewalled?)", label)
+                } else {
+                    label
+                }
+            }
+        }
+    }
+
+    fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), Instant::now()));
+    }
+
+    fn status_message(&self) -> Option<&str> {
+        match &self.status {
+            Some((message, at)) if at.elapsed() < STATUS_TIMEOUT => Some(message),
+            _ => None,
+        }
+    }
+
+    // With --confirm-quit the first press only arms the quit; a second press
+    // inside STATUS_TIMEOUT actually exits.
+    fn request_quit(&mut self) -> bool {
+        if !self.confirm_quit {
+            return true;
+        }
+
+        if let Some(at) = self.quit_pressed_at {
+            if at.elapsed() < STATUS_TIMEOUT {
+                return true;
+            }
+        }
+
+        self.quit_pressed_at = Some(Instant::now());
+        self.set_status("Press q again to quit");
+        false
+    }
+
+    fn handle_filter_key(&mut self, code: KeyCode) {
+        let Some(input) = self.filter_input.as_mut() else {
+            return;
+        };
+
+        match code {
+            // A bad regex keeps the prompt open, with the error in the footer
+            KeyCode::Enter => {
+                let Ok(filter) = Filter::new(input) else {
+                    return;
+                };
+                let text = self.filter_input.take().unwrap_or_default();
+                self.with_stable_selection(|app| {
+                    app.filter = filter;
+                    app.filter_text = text;
+                });
+            }
+            KeyCode::Esc => self.filter_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    // Shown rows ranked by how well they match the query, best first; rows
+    // of equal score keep table order. Every row while the query is empty.
+    fn finder_matches(&self, query: &str) -> Vec<FinderMatch> {
+        let panels: &[Panel] = if self.compact_layout() { &[Panel::All] } else { &[Panel::Listening, Panel::Active] };
+        let mut scored = Vec::new();
+        for &panel in panels {
+            for (index, row) in self.panel_rows(panel).iter().enumerate() {
+                let PanelRow::Connection(conn) = row else {
+                    continue;
+                };
+                let line = [ColumnKind::Proto, ColumnKind::Local, ColumnKind::Remote, ColumnKind::State, ColumnKind::Program]
+                    .into_iter()
+                    .map(|kind| self.cell_text(kind, conn))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if let Some(score) = fuzzy::score(query, &line) {
+                    scored.push((score, FinderMatch { panel, index, line }));
+                }
+            }
+        }
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, hit)| hit).collect()
+    }
+
+    fn handle_finder_key(&mut self, code: KeyCode) {
+        let Some(finder) = self.finder.as_mut() else {
+            return;
+        };
+
+        match code {
+            KeyCode::Enter => {
+                let finder = self.finder.take().unwrap_or_default();
+                let mut matches = self.finder_matches(&finder.query);
+                if matches.is_empty() {
+                    return;
+                }
+                let hit = matches.swap_remove(finder.selected.min(matches.len() - 1));
+                self.stop_following();
+                self.view_mode = ViewMode::Connections;
+                if hit.panel != Panel::All {
+                    self.focused = hit.panel;
+                }
+                self.panel_state(hit.panel).select(Some(hit.index));
+            }
+            KeyCode::Esc => self.finder = None,
+            KeyCode::Up => finder.selected = finder.selected.saturating_sub(1),
+            KeyCode::Down => finder.selected = (finder.selected + 1).min(FINDER_ROWS - 1),
+            KeyCode::Backspace => {
+                finder.query.pop();
+                finder.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                finder.query.push(c);
+                finder.selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    // Unlike the filter prompt, Enter always closes this one; a command that
+    // fails says why in the status line
+    fn handle_command_key(&mut self, code: KeyCode) {
+        let Some(input) = self.command_input.as_mut() else {
+            return;
+        };
+
+        match code {
+            KeyCode::Enter => {
+                let command = self.command_input.take().unwrap_or_default();
+                let message = match self.run_command(&command) {
+                    Ok(message) => message,
+                    Err(err) => err,
+                };
+                self.set_status(message);
+            }
+            KeyCode::Esc => self.command_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    fn run_command(&mut self, command: &str) -> Result<String, String> {
+        let mut words = command.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (None, _, _) => Err(format!("Commands: {}", COMMAND_HELP)),
+            (Some("interval"), Some(value), None) => {
+                let ms = value.strip_suffix("ms").unwrap_or(value);
+                let ms = ms.parse::<u64>().map_err(|_| format!("interval: '{}' is not a number of milliseconds", value))?;
+                if !INTERVAL_MS.contains(&ms) {
+                    return Err(format!(
+                        "interval: {} ms is out of range ({}-{} ms)",
+                        ms,
+                        INTERVAL_MS.start(),
+                        INTERVAL_MS.end()
+                    ));
+                }
+                self.config.refresh_rate = Duration::from_millis(ms);
+                Ok(format!("Refresh interval set to {} ms", ms))
+            }
+            (Some("interval"), _, _) => Err(format!("Usage: {}", COMMAND_HELP)),
+            (Some(name), _, _) => Err(format!("Unknown command '{}' (commands: {})", name, COMMAND_HELP)),
+        }
+    }
+}
+
+// Parser behaviour depends on what the kernel exposes, so bug reports want
+// the kernel and the readable proc files alongside the crate version
+fn print_version() {
+    println!("netmon-tui {}", env!("CARGO_PKG_VERSION"));
+    println!("kernel: {}", network::kernel_version().unwrap_or_else(|| "unknown".to_string()));
+    println!("proc files:");
+    for path in network::PROC_NET_FILES {
+        let status = match std::fs::File::open(path) {
+            Ok(_) => "readable".to_string(),
+            Err(err) => err.to_string(),
+        };
+        println!("  {:<24}{}", path, status);
+    }
+}
+
+// --bench: the scan the TUI runs on every refresh, with the same options,
+// repeated on one monitor so its caches warm up as they would live. The
+// first scan fills them and is reported on its own.
+fn run_bench(args: &Args, iterations: usize) -> anyhow::Result<()> {
+    let mut monitor = build_monitor(args);
+    let mut durations = Vec::with_capacity(iterations);
+    let mut counts = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let started = Instant::now();
+        let connections = monitor.get_connections()?;
+        durations.push(started.elapsed());
+        counts.push(connections.len());
+    }
+
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    println!("first:       {:8.2} ms  {} connections", ms(durations[0]), counts[0]);
+    let (warm, warm_counts) = (&durations[1..], &counts[1..]);
+    if let (Some(min), Some(max)) = (warm.iter().min(), warm.iter().max()) {
+        let total: Duration = warm.iter().sum();
+        println!("min:         {:8.2} ms", ms(*min));
+        println!("avg:         {:8.2} ms", ms(total / warm.len() as u32));
+        println!("max:         {:8.2} ms", ms(*max));
+        println!(
+            "connections: {} to {} over {} scans after the first",
+            warm_counts.iter().min().unwrap_or(&0),
+            warm_counts.iter().max().unwrap_or(&0),
+            warm.len()
+        );
+    }
+    let stats = monitor.stats();
+    println!(
+        "inodes mapped: {}, processes cached: {}, last scan hit rate {}",
+        stats.inode_map_size,
+        stats.process_cache_size,
+        cache_hits(stats)
+    );
+    Ok(())
+}
+
+fn build_monitor(args: &Args) -> NetworkMonitor {
+    let mut monitor = NetworkMonitor::new();
+    monitor.set_extra_protocols(args.extra_protos);
+    monitor.set_unix_sockets(args.unix);
+    monitor.set_protocols(args.protocols);
+    monitor.set_source(args.source);
+    if let Some(dir) = &args.proc_dir {
+        monitor.set_proc_dir(dir.clone());
+    }
+    monitor
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("netmon-tui: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    if args.version {
+        print_version();
+        return Ok(());
+    }
+
+    if let Some(iterations) = args.bench {
+        if let Err(err) = run_bench(&args, iterations) {
+            eprintln!("netmon-tui: {:#}", err);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+
+    // Exits like diff(1): 0 if the snapshots match, 1 if they differ, 2 on errors
+    if let Some((old, new)) = &args.diff {
+        match diff::diff_files(old, new) {
+            Ok(differ) => std::process::exit(differ as i32),
+            Err(err) => {
+                eprintln!("netmon-tui: {:#}", err);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Some(path) = &args.log_file {
+        if let Err(err) = logging::init(path, args.verbosity) {
+            eprintln!("netmon-tui: {:#}", err);
+            std::process::exit(2);
+        }
+    }
+
+    let config = match Config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("netmon-tui: config error {:#}", err);
+            std::process::exit(2);
+        }
+    };
+
+    if args.summary || args.once {
+        let mut connections = match build_monitor(&args).get_connections() {
+            Ok(connections) => connections,
+            Err(err) => {
+                // 2 like other failures, so --exit-code's 1 only means "no match"
+                eprintln!("netmon-tui: {:#}", err);
+                std::process::exit(2);
+            }
+        };
+        // An unreachable host is reported but doesn't stop the others
+        if !args.ssh_hosts.is_empty() {
+            let (remote, errors) = remote::fetch_all(&args.ssh_hosts, args.protocols);
+            for (host, err) in errors {
+                eprintln!("netmon-tui: ssh {}: {}", host, err);
+            }
+            connections.extend(remote);
+        }
+        if !args.filter_ports.is_empty() {
+            connections.retain(|conn| {
+                args.filter_ports.contains(&conn.local_port) || args.filter_ports.contains(&conn.remote_port)
+            });
+        }
+        connections.retain(|conn| exe_matches(conn, args.exe_prefix.as_deref()));
+        let matched = !connections.is_empty();
+
+        if args.summary {
+            snapshot::print_summary(&connections);
+        } else {
+            if let Some(key) = args.sort_key {
+                sort_connections(&mut connections, key, args.sort_desc);
+            }
+            if let Some(top) = args.top {
+                connections.truncate(top);
+            }
+            snapshot::print_plain(&connections);
+        }
+
+        if args.exit_code && !matched {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Bind before touching the terminal so a bad path is reported plainly
+    let ipc = match &args.ipc_socket {
+        Some(path) => match ipc::IpcServer::bind(path) {
+            Ok(server) => Some(server),
+            Err(err) => {
+                eprintln!("netmon-tui: cannot listen on {}: {}", path.display(), err);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    // Setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Create app and run it
+    let mut app = App::new(&args, config);
+    if !app.network_monitor.has_fd_access() {
+        app.banner = Some(
+            "No PIDs: /proc/<pid>/fd can't be read here (restricted container?), so sockets are shown unattributed. Press any key"
+                .to_string(),
+        );
+    } else if let Some(first) = app.config.warnings.first() {
+        let more = match app.config.warnings.len() {
+            1 => String::new(),
+            n => format!(" (+{} more, see --log)", n - 1),
+        };
+        app.banner = Some(format!("{}{}. Press any key", first, more));
+    } else if !network::other_users_fds_readable() {
+        app.banner = Some(
+            "Run as root to see all process names: other users' sockets show no PID without it. Press any key".to_string(),
+        );
+    }
+    app.ipc = ipc;
+    app.dns.load();
+    let pins_file = app.config.persist_pins.then(pins::state_file).flatten();
+    if let Some(path) = &pins_file {
+        app.pinned = pins::load(path);
+    }
+    let res = run_app(&mut terminal, &mut app).await;
+
+    // Restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{:?}", err)
+    } else if let Some(path) = &args.output_on_exit {
+        // After the terminal is restored, so "-" lands in the normal screen
+        let connections = app.shown_connections();
+        let written = if path.as_os_str() == "-" {
+            snapshot::write_plain(&mut io::stdout().lock(), connections)
+        } else {
+            std::fs::File::create(path).and_then(|mut file| snapshot::write_plain(&mut file, connections))
+        };
+        if let Err(err) = written {
+            eprintln!("netmon-tui: cannot write {}: {}", path.display(), err);
+        }
+    }
+
+    // Losing the cache only costs a slower start next time
+    let _ = app.dns.save();
+    if let Some(path) = &pins_file {
+        if let Err(err) = pins::save(path, &app.pinned) {
+            eprintln!("netmon-tui: cannot save pins to {}: {}", path.display(), err);
+        }
+    }
+
+    Ok(())
+}
+
+// Hands the terminal to $PAGER (default `less`) on the selected process's
+// /proc/<pid>/status, then restores the TUI exactly as main set it up
+fn open_process_status<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    let Some(pid) = app.selected_connection().and_then(|conn| conn.pid) else {
+        app.set_status("No PID known for the selected socket");
+        return Ok(());
+    };
+    if let Some(host) = app.selected_connection().and_then(|conn| conn.host.clone()) {
+        app.set_status(format!("Process {} runs on {}", pid, host));
+        return Ok(());
+    }
+
+    let path = format!("/proc/{}/status", pid);
+    if !std::path::Path::new(&path).exists() {
+        app.set_status(format!("Process {} has exited", pid));
+        return Ok(());
+    }
+
+    let pager = std::env::var("PAGER").ok().filter(|pager| !pager.trim().is_empty());
+    let pager = pager.as_deref().unwrap_or("less");
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    let result = std::process::Command::new(program).args(words).arg(&path).status();
+
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+
+    match result {
+        Ok(status) if !status.success() => app.set_status(format!("{} exited with {}", program, status)),
+        Ok(_) => {}
+        Err(err) => app.set_status(format!("Cannot run {}: {}", program, err)),
+    }
+
+    Ok(())
+}
+
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    let mut keys = KeySequence::default();
+    let refresh_requested = refresh_on_sigusr1()?;
+
+    // Initial data load
+    app.update_connections().await;
+
+    loop {
+        terminal.draw(|f| ui(f, app))?;
+
+        // Input is polled on its own short interval so keys are handled as
+        // soon as they arrive; the /proc scan keeps to refresh_rate below
+        // In manual mode there is nothing to wake up for but keys, signals and
+        // expiring status messages
+        let timeout = if app.manual {
+            STATUS_TIMEOUT
+        } else {
+            let until_refresh = app.config.refresh_rate.saturating_sub(app.last_update.elapsed());
+            app.config.poll_rate.min(until_refresh)
+        };
+        let mut refresh_now = false;
+
+        if crossterm::event::poll(timeout)? {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => app.handle_click(mouse.column, mouse.row),
+                    MouseEventKind::ScrollDown | MouseEventKind::ScrollUp if app.accepts_scroll() => {
+                        app.stop_following();
+                        app.move_selection(if mouse.kind == MouseEventKind::ScrollDown { 2 } else { -2 });
+                    }
+                    _ => {}
+                }
+            }
+
+            if let Event::Key(key) = event {
+                let action = app.config.keymap.action(key);
+                if app.banner.is_some() {
+                    app.banner = None;
+                } else if app.filter_input.is_some() {
+                    app.handle_filter_key(key.code);
+                } else if app.command_input.is_some() {
+                    app.handle_command_key(key.code);
+                } else if app.finder.is_some() {
+                    app.handle_finder_key(key.code);
+                } else if !app.handle_navigation_key(key.code, action, &mut keys) {
+                    match action {
+                        _ if key.code == KeyCode::Esc && app.remote_focus.is_some() => {
+                            app.with_stable_selection(|app| app.remote_focus = None)
+                        }
+                        Some(Action::Quit) if app.request_quit() => return Ok(()),
+                        Some(Action::SwitchPanel) => app.toggle_focus(),
+                        Some(Action::Filter) => app.filter_input = Some(app.filter_text.clone()),
+                        Some(Action::Command) => app.command_input = Some(String::new()),
+                        Some(Action::Jump) => app.finder = Some(Finder::default()),
+                        Some(Action::Sort) => {
+                            app.with_stable_selection(|app| {
+                                let sort = app.panel_sort(app.current_panel());
+                                sort.key = SortKey::cycle(sort.key);
+                            })
+                        }
+                        Some(Action::SortReverse) => app.with_stable_selection(|app| {
+                            let sort = app.panel_sort(app.current_panel());
+                            sort.desc = !sort.desc;
+                        }),
+                        Some(Action::Processes) => {
+                            app.view_mode = match app.view_mode {
+                                ViewMode::Processes => ViewMode::Connections,
+                                _ => ViewMode::Processes,
+                            };
+                            app.update_aggregates();
+                        }
+                        Some(Action::Remotes) => {
+                            app.view_mode = match app.view_mode {
+                                ViewMode::Remotes => ViewMode::Connections,
+                                _ => ViewMode::Remotes,
+                            };
+                            if app.view_mode == ViewMode::Remotes {
+                                app.select_row(app.remotes_state.selected().unwrap_or(0));
+                            }
+                        }
+                        Some(Action::GroupSubnets) => app.with_stable_selection(|app| app.group_subnets = !app.group_subnets),
+                        Some(Action::MergeListeners) => {
+                            app.with_stable_selection(|app| app.merge_listeners = !app.merge_listeners)
+                        }
+                        Some(Action::Select) if app.view_mode == ViewMode::Remotes => app.drill_into_remote(),
+                        Some(Action::Select) => app.toggle_subnet(),
+                        Some(Action::Detail) => {
+                            app.show_detail = !app.show_detail;
+                            app.update_tcp_info();
+                        }
+                        Some(Action::OnlyMine) => app.with_stable_selection(|app| app.only_mine = !app.only_mine),
+                        Some(Action::Family) => app.with_stable_selection(|app| app.family = app.family.cycle()),
+                        Some(Action::Pin) => app.toggle_pin(),
+                        Some(Action::ToggleTcp) => app.toggle_protocol(true),
+                        Some(Action::ToggleUdp) => app.toggle_protocol(false),
+                        Some(Action::Ephemeral) => {
+                            app.view.ephemeral_ports = match app.view.ephemeral_ports {
+                                Some(_) => None,
+                                None => Some(app.network_monitor.ephemeral_ports()),
+                            }
+                        }
+                        Some(Action::Stats) => app.view.show_stats = !app.view.show_stats,
+                        Some(Action::Cgroup) => app.view.show_cgroup = !app.view.show_cgroup,
+                        Some(Action::Container) => {
+                            app.view.show_container = !app.view.show_container;
+                            app.request_container_names();
+                        }
+                        Some(Action::Inode) => app.view.show_inode = !app.view.show_inode,
+                        Some(Action::WrapRemote) => app.view.wrap_remote = !app.view.wrap_remote,
+                        Some(Action::Export) => app.export(false),
+                        Some(Action::ExportAll) => app.export(true),
+                        Some(Action::Check) => app.toggle_checked(),
+                        Some(Action::ExportChecked) => app.export_checked(),
+                        Some(Action::CopyMarkdown) => app.copy_markdown(),
+                        Some(Action::Watch) => app.toggle_watch(),
+                        Some(Action::Refresh) => refresh_now = true,
+                        Some(Action::Source) => {
+                            let source = app.network_monitor.source().next();
+                            app.network_monitor.set_source(source);
+                            app.set_status(format!("Reading sockets from {}", source.name()));
+                            refresh_now = true;
+                        }
+                        Some(Action::Follow) => app.toggle_follow(),
+                        Some(Action::Anonymize) => app.view.anonymize = !app.view.anonymize,
+                        Some(Action::ColorBy) => {
+                            app.view.color_by = match app.view.color_by {
+                                ColorBy::State => ColorBy::Process,
+                                ColorBy::Process => ColorBy::Age,
+                                ColorBy::Age => ColorBy::State,
+                            }
+                        }
+                        Some(Action::Compact) => app.with_stable_selection(|app| app.compact = !app.compact),
+                        Some(Action::Exposed) => app.with_stable_selection(|app| app.only_exposed = !app.only_exposed),
+                        Some(Action::OnlyQueued) => app.with_stable_selection(|app| app.only_queued = !app.only_queued),
+                        Some(Action::ExcludePort) => app.exclude_selected_port(),
+                        Some(Action::Pager) => open_process_status(terminal, app)?,
+                        Some(Action::ClearExclusions) => app.with_stable_selection(|app| app.excluded_ports.clear()),
+                        Some(Action::Baseline) => app.with_stable_selection(|app| app.toggle_baseline()),
+                        Some(Action::DiffOnly) if app.baseline.is_some() => {
+                            app.with_stable_selection(|app| app.diff_only = !app.diff_only)
+                        }
+                        Some(Action::Debug) => app.show_debug = !app.show_debug,
+                        Some(Action::HideUnattributed) => {
+                            app.with_stable_selection(|app| app.hide_unattributed = !app.hide_unattributed)
+                        }
+                        Some(Action::HideKernel) => app.with_stable_selection(|app| app.hide_kernel = !app.hide_kernel),
+                        Some(Action::SinceWindow) => app.with_stable_selection(|app| {
+                            app.since = match app.since {
+                                Some(_) => None,
+                                None => Some(app.since_window),
+                            }
+                        }),
+                        Some(Action::Resolve) => {
+                            app.view.resolve_hosts = !app.view.resolve_hosts;
+                            app.request_hostnames();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        app.dns.collect();
+        app.containers.collect();
+        if let Some(firewall) = &mut app.firewall {
+            firewall.collect();
+            firewall.refresh();
+        }
+
+        // Update connections periodically, or straight away when poked
+        let poked = refresh_requested.swap(false, Ordering::Relaxed) || refresh_now;
+        if poked || (!app.manual && app.last_update.elapsed() >= app.config.refresh_rate) {
+            app.update_connections().await;
+        }
+    }
+}
+
+// The signal stream is drained on a runtime task, which only sets a flag; the
+// loop above picks it up within one poll interval
+fn refresh_on_sigusr1() -> io::Result<Arc<AtomicBool>> {
+    let mut signals = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())?;
+    let requested = Arc::new(AtomicBool::new(false));
+
+    let flag = requested.clone();
+    tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            debug!("SIGUSR1 received, refreshing");
+            flag.store(true, Ordering::Relaxed);
+        }
+    });
+
+    Ok(requested)
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    let too_short = f.area().height < COMPACT_BELOW_ROWS;
+    if too_short != app.too_short {
+        // The listening and active rows change sort with the layout
+        app.with_stable_selection(|app| app.too_short = too_short);
+    }
+
+    // Refilled by render_connection_panels when the panels are on screen
+    app.panel_areas.clear();
+
+    // Reserve a footer line for the prompts and status messages
+    let show_footer = app.filter_input.is_some()
+        || app.command_input.is_some()
+        || app.status_message().is_some()
+        || app.banner.is_some()
+        || !app.status_segments().is_empty();
+    let outer_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(0), Constraint::Length(if show_footer { 1 } else { 0 })].as_ref())
+        .split(f.area());
+
+    if app.view_mode == ViewMode::Processes {
+        render_process_table(f, outer_chunks[0], &app.process_summaries);
+    } else if app.view_mode == ViewMode::Remotes {
+        let remotes = app.top_talkers();
+        render_remotes_table(f, outer_chunks[0], &remotes, &mut app.remotes_state, &app.view);
+    } else if app.show_detail {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(DETAIL_HEIGHT)].as_ref())
+            .split(outer_chunks[0]);
+        render_connection_panels(f, chunks[0], app);
+        render_detail_pane(f, chunks[1], app);
+    } else {
+        render_connection_panels(f, outer_chunks[0], app);
+    }
+
+    if show_footer {
+        render_footer(f, outer_chunks[1], app);
+    }
+
+    if app.show_debug {
+        render_debug_overlay(f, app);
+    }
+
+    if let Some(finder) = &app.finder {
+        render_finder(f, app, finder);
+    }
+
+    if app.view.monochrome {
+        strip_colors(f.buffer_mut());
+    }
+}
+
+// https://no-color.org, plus terminals that can't show colour: "dumb" ones
+// and DEC VT100/VT220 types, as on most serial consoles
+fn terminal_has_color() -> bool {
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+    match std::env::var("TERM") {
+        Ok(term) => !(term.is_empty() || term == "dumb" || term.starts_with("vt")),
+        Err(_) => false,
+    }
+}
+
+// Drops every colour from the finished frame. Anything that stood out by its
+// background (the selected row, title badges) is reversed instead, so it
+// still stands out; state styles switch to modifiers in render_panel.
+fn strip_colors(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+        cell.underline_color = Color::Reset;
+    }
+}
+
+fn render_connection_panels(f: &mut Frame, area: Rect, app: &mut App) {
+    if app.compact_layout() {
+        app.panel_areas = vec![(Panel::All, area)];
+        render_panel(f, area, app, Panel::All);
+        return;
+    }
+
+    // Split screen into two panels. Side by side, the active panel gets the
+    // larger share for its extra columns.
+    let main_chunks = if area.width >= SIDE_BY_SIDE_FROM_COLUMNS {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+            .split(area)
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(area)
+    };
+
+    app.panel_areas = vec![(Panel::Listening, main_chunks[0]), (Panel::Active, main_chunks[1])];
+    render_panel(f, main_chunks[0], app, Panel::Listening);
+    render_panel(f, main_chunks[1], app, Panel::Active);
+}
+
+fn render_panel(f: &mut Frame, area: Rect, app: &mut App, panel: Panel) {
+    // The table state is copied out so it can be updated while the visible
+    // connection list still borrows `app`
+    let mut state = app.panel_state_ref(panel).clone();
+
+    {
+        let visible = app.visible_connections(panel);
+        // Listeners reachable from the network (the attack surface), and
+        // active connections with queued bytes
+        let (exposed_count, queued, added) = {
+            let cache = app.panel_cache(panel);
+            (cache.exposed, cache.queued, cache.added.clone())
+        };
+        let view = PanelView {
+            title: app.panel_title(panel),
+            connection_count: visible.len(),
+            rows: app.panel_rows(panel),
+            panel,
+            sort: app.rows_sort(panel),
+            focused: app.current_panel() == panel,
+            exposed_count: (panel != Panel::Active).then_some(exposed_count),
+            queued: (panel != Panel::Listening).then_some(queued),
+            added,
+            pinned: &app.pinned,
+            checked: &app.checked,
+            scans: &app.scans,
+            firewall: app.firewall.as_ref(),
+            accept_rates: app.accept_rates.as_ref(),
+            established_by_port: app.established_by_port.as_ref().map(|(_, by_port)| by_port),
+            listen_queues: &app.listen_queues,
+            first_seen: app.first_seen.as_ref(),
+            merged: visible
+                .iter()
+                .map(|conn| (conn.key(), app.listener_group(conn)))
+                .filter(|(_, members)| !members.is_empty())
+                .collect(),
+            unowned: visible
+                .iter()
+                .filter_map(|conn| app.unowned(conn).map(|unowned| (conn.key(), unowned)))
+                .collect(),
+            owners: visible
+                .iter()
+                .filter(|conn| !conn.is_attributed() && conn.uid.is_some())
+                .map(|conn| (conn.key(), app.program_label(conn)))
+                .collect(),
+        };
+        render_connections_table(f, area, &view, &mut state, &app.view, &app.dns, &app.containers);
+    }
+
+    *app.panel_state(panel) = state;
+}
+
+// Window for 'w' when --since wasn't given
+const DEFAULT_SINCE_WINDOW: Duration = Duration::from_secs(30);
+
+// Tall enough for everything an established TCP socket shows, borders
+// included
+const DETAIL_HEIGHT: u16 = 16;
+
+fn render_detail_pane(f: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title(" Details ('d' to close) ");
+
+    let Some(conn) = app.selected_connection() else {
+        f.render_widget(Paragraph::new("No connection selected").block(block), area);
+        return;
+    };
+
+    let interface = conn.interface.as_deref().unwrap_or("-");
+    // The raw forms, not the table's "*:80", unless anonymizing
+    let local = match app.view.masked(&conn.local_address, true) {
+        Some(name) => format!("{}:{}", name, conn.local_port),
+        None => conn.local_display(),
+    };
+    let remote = match app.view.masked(&conn.remote_address, false) {
+        Some(name) => format!("{}:{}", name, conn.remote_port),
+        None => conn.remote_display(),
+    };
+    let mut lines = vec![
+        format!("Protocol:  {}", conn.protocol),
+        format!("Local:     {} ({})", local, interface),
+        format!("Remote:    {}", remote),
+        format!("State:     {}", conn.state_display()),
+        match &conn.exe {
+            // Paths are whatever the binary was installed under, control characters included
+            Some(exe) => format!(
+                "Process:   {} ({})",
+                conn.pid_program(),
+                network::escape_control(&exe.display().to_string())
+            ),
+            None => format!("Process:   {}", conn.pid_program()),
+        },
+        format!(
+            "User:      {}",
+            match conn.uid {
+                Some(uid) if conn.host.is_some() => format!("uid {}", uid),
+                Some(uid) => format!("{} (uid {})", app.username(uid), uid),
+                None => "-".to_string(),
+            }
+        ),
+    ];
+
+    if let Some(host) = &conn.host {
+        lines.insert(0, format!("Host:      {} (via ssh)", host));
+    }
+
+    if let Some(flags) = &conn.flags {
+        lines.push(format!("Flags:     {} (inode {})", flags, conn.inode));
+    }
+
+    let members = app.listener_group(conn);
+    if !members.is_empty() {
+        lines.push(format!("Merged:    {} sockets on port {} ('M' to split)", members.len(), conn.local_port));
+        for member in members {
+            lines.push(format!(
+                "  {:<6} {} ({}) {}",
+                member.protocol,
+                app.view.local_endpoint(member),
+                member.interface.as_deref().unwrap_or("-"),
+                member.pid_program()
+            ));
+        }
+    }
+
+    if let Some(cgroup) = &conn.cgroup {
+        let label = match network::container_id(cgroup).and_then(|id| app.containers.name(id)) {
+            Some(name) => name.to_string(),
+            None => network::cgroup_label(cgroup),
+        };
+        lines.push(format!("Cgroup:    {} ({})", cgroup, label));
+    }
+
+    let suspicion = conn.pid.filter(|_| conn.host.is_none()).and_then(|pid| app.scans.suspicion_for(pid));
+    if let Some(suspicion) = suspicion {
+        lines.push(format!("Alert:     possible port scan, {}", suspicion.describe(app.scans.window())));
+    }
+
+    if let Some(firewall) = app.firewall.as_ref().filter(|firewall| firewall.blocks(conn)) {
+        lines.push(format!(
+            "Firewall:  new connections from other hosts look blocked by the {} rules (a guess)",
+            firewall.sources().join("/")
+        ));
+    }
+
+    if let Some(watch) = app.watch.as_ref().filter(|watch| watch.key == conn.key()) {
+        lines.push(format!("Watch:     {}", watch.describe()));
+    }
+
+    match app.unowned(conn) {
+        Some(Unowned::Kernel) => {
+            lines.push("Owner:     none, inode 0: held by the kernel (e.g. NFS or an in-kernel server)".to_string())
+        }
+        Some(Unowned::Orphan) => match app.network_monitor.stats().other_netns_processes {
+            0 => lines.push(format!(
+                "Owner:     orphan? No visible process has inode {} open; another PID namespace?",
+                conn.inode
+            )),
+            others => lines.push(format!(
+                "Owner:     orphan? No process in this network namespace has inode {} open ({} in others not searched)",
+                conn.inode, others
+            )),
+        },
+        None => {}
+    }
+
+    lines.push(match app.first_seen(conn) {
+        Some(seen) if app.view.age_format == AgeFormat::Absolute => {
+            format!("Seen:      {}", format_age(app.view.age_format, seen))
+        }
+        Some(seen) => format!("Seen:      {} ago", format_age(app.view.age_format, seen)),
+        None => "Seen:      before startup".to_string(),
+    });
+
+    if conn.protocol.starts_with("TCP") || conn.protocol.starts_with("UDP") {
+        lines.push(match app.socket_buffers.get(&conn.inode).filter(|_| conn.inode != 0) {
+            Some(buffers) => format!("Buffers:   recv {} bytes, send {} bytes", buffers.rcvbuf, buffers.sndbuf),
+            None => "Buffers:   -".to_string(),
+        });
+    }
+
+    if conn.protocol.starts_with("TCP") {
+        let timer = match conn.timer {
+            TimerKind::None => "none".to_string(),
+            kind => format!("{}, expires in ~{:.2}s", kind.name(), conn.timer_expires_secs),
+        };
+        lines.push(format!("Timer:     {} (unrecovered retransmits: {})", timer, conn.retransmits));
+
+        if let Some(queue) = app.listen_queues.get(&conn.inode).filter(|_| conn.state == "LISTEN") {
+            lines.push(format!("Accept q:  {} queued of {} backlog", queue.queued, queue.backlog));
+        }
+
+        if conn.state == "ESTABLISHED" {
+            let info = app.tcp_info.get(&conn.inode);
+            if let Some(info) = info {
+                lines.push(format!("RTT:       {:.1} ms (var {:.1} ms), cwnd {}", info.rtt_ms, info.rttvar_ms, info.snd_cwnd));
+                lines.push(format!(
+                    "In flight: ~{} bytes, {} retransmits total",
+                    info.bytes_in_flight, info.total_retrans
+                ));
+            }
+            let congestion = info.and_then(|info| info.congestion.as_deref());
+            lines.push(format!("Cong. ctl: {}", congestion.unwrap_or("-")));
+        }
+    }
+
+    // Wrapped rather than cut, so a long process name stays readable here
+    f.render_widget(Paragraph::new(lines.join("\n")).block(block).wrap(Wrap { trim: false }), area);
+}
+
+// "97% (312 of 320)" for the last scan's process name lookups
+fn cache_hits(stats: &network::ScanStats) -> String {
+    let lookups = stats.process_cache_hits + stats.process_cache_misses;
+    match lookups {
+        0 => "-".to_string(),
+        _ => format!(
+            "{:.0}% ({} of {})",
+            stats.process_cache_hits as f64 * 100.0 / lookups as f64,
+            stats.process_cache_hits,
+            lookups
+        ),
+    }
+}
+
+fn render_debug_overlay(f: &mut Frame, app: &App) {
+    let stats = app.network_monitor.stats();
+
+    let by_protocol: Vec<String> = stats
+        .by_protocol
+        .iter()
+        .map(|(proto, count)| format!("{} {}", proto, count))
+        .collect();
+
+    let dns_hit_rate = match app.dns.hit_rate() {
+        Some(rate) => format!("{:.0}%", rate * 100.0),
+        None => "-".to_string(),
+    };
+
+    let lines = [
+        format!("Last fetch:     {:.1} ms", stats.duration.as_secs_f64() * 1000.0),
+        format!("Connections:    {}", if by_protocol.is_empty() { "-".to_string() } else { by_protocol.join(", ") }),
+        format!("Inode map:      {} sockets", stats.inode_map_size),
+        format!("Other netns:    {} processes not searched", stats.other_netns_processes),
+        format!("Process cache:  {} PIDs, hit rate {}", stats.process_cache_size, cache_hits(stats)),
+        format!("Skipped lines:  {}", stats.skipped_lines),
+        format!(
+            "DNS cache:      {} entries, hit rate {}, {} pending",
+            app.dns.entry_count(),
+            dns_hit_rate,
+            app.dns.pending_count()
+        ),
+    ];
+
+    let area = centered_rect(60, lines.len() as u16 + 2, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines.join("\n"))
+            .block(Block::default().borders(Borders::ALL).title(" Debug ('D' to close) ")),
+        area,
+    );
+}
+
+fn render_finder(f: &mut Frame, app: &App, finder: &Finder) {
+    let matches = app.finder_matches(&finder.query);
+    let selected = finder.selected.min(matches.len().saturating_sub(1));
+
+    let mut lines = vec![Line::from(format!("> {}_", finder.query)), Line::from("")];
+    for (i, hit) in matches.iter().take(FINDER_ROWS).enumerate() {
+        let style = if i == selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        lines.push(Line::styled(hit.line.clone(), style));
+    }
+    if matches.is_empty() {
+        lines.push(Line::styled("no matches", Style::default().fg(Color::DarkGray)));
+    }
+
+    let title = format!(" Jump: {} of {} ('Enter' to go, Esc to cancel) ", matches.len().min(FINDER_ROWS), matches.len());
+    let area = centered_rect(f.area().width.saturating_sub(8).min(110), FINDER_ROWS as u16 + 4, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
 }
 
-impl App {
-    fn new() -> App {
-        App {
-            connections: Vec::new(),
-            table_state: TableState::default(),
-            last_update: Instant::now(),
-            network_monitor: NetworkMonitor::new(),
-        }
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     }
+}
 
-    async fn update_connections(&mut self) {
-        if let Ok(connections) = self.network_monitor.get_connections() {
-            self.connections = connections;
-            self.last_update = Instant::now();
-        }
+fn render_footer(f: &mut Frame, area: Rect, app: &App) {
+    if let (None, None, None, Some(banner)) = (&app.filter_input, &app.command_input, app.status_message(), &app.banner) {
+        let style = Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD);
+        f.render_widget(Paragraph::new(banner.as_str()).style(style), area);
+        return;
     }
 
-    fn next(&mut self) {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i >= self.connections.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+    let line = match (&app.filter_input, &app.command_input, app.status_message()) {
+        (Some(input), _, _) => match Filter::new(input) {
+            Ok(_) => format!("/{}_  ({}; Enter to apply, Esc to cancel)", input, FILTER_HELP),
+            Err(err) => format!("/{}_  (regex error: {}; Esc to cancel)", input, err),
+        },
+        (None, Some(input), _) => format!(":{}_  ({}; Enter to run, Esc to cancel)", input, COMMAND_HELP),
+        (None, None, Some(message)) => message.to_string(),
+        (None, None, None) => app.status_segments().join("  |  "),
+    };
+
+    f.render_widget(Paragraph::new(line).style(Style::default().fg(Color::Yellow)), area);
+}
+
+fn render_remotes_table(
+    f: &mut Frame,
+    area: Rect,
+    remotes: &[(String, usize)],
+    state: &mut TableState,
+    view: &ViewOptions,
+) {
+    let header_cells: Vec<Cell> = ["Remote Address", "Connections"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
+        .collect();
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows = remotes
+        .iter()
+        .map(|(remote, count)| {
+            let remote = view.masked(remote, false).unwrap_or_else(|| remote.clone());
+            Row::new(vec![Cell::from(remote), Cell::from(count.to_string())])
+        });
+
+    let widths = [
+        Constraint::Min(40),    // Remote Address
+        Constraint::Length(12), // Connections
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(format!(
+                    " Top Talkers - {} remotes (Enter to show connections, 't' to go back) ",
+                    remotes.len()
+                ))
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD)
+        );
+
+    f.render_stateful_widget(table, area, state);
+}
+
+fn render_process_table(f: &mut Frame, area: Rect, summaries: &[ProcessSummary]) {
+    let header_cells: Vec<Cell> = ["PID/Program", "Listen", "Active", "Read/s", "Write/s"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
+        .collect();
+
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows = summaries.iter().map(|summary| {
+        let pid_program = match summary.pid {
+            Some(pid) => format!("{}/{}", pid, summary.name),
+            None => summary.name.clone(),
         };
-        self.table_state.select(Some(i));
-    }
 
-    fn previous(&mut self) {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.connections.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
+        let (read, write) = match summary.io {
+            Some(io) => (format_rate(io.read_per_sec), format_rate(io.write_per_sec)),
+            None => ("-".to_string(), "-".to_string()),
         };
-        self.table_state.select(Some(i));
-    }
+
+        Row::new(vec![
+            Cell::from(pid_program),
+            Cell::from(summary.listening.to_string()),
+            Cell::from(summary.active.to_string()),
+            Cell::from(read),
+            Cell::from(write),
+        ])
+    });
+
+    let widths = [
+        Constraint::Min(25),    // PID/Program
+        Constraint::Length(8),  // Listen
+        Constraint::Length(8),  // Active
+        Constraint::Length(12), // Read/s
+        Constraint::Length(12), // Write/s
+    ];
+
+    // rchar/wchar cover all reads and writes, not just sockets
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " Processes - {} (I/O is total process I/O, not network-only; 'a' for connections) ",
+                    summaries.len()
+                ))
+        );
+
+    f.render_widget(table, area);
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+fn render_subnet_row(subnet: &str, count: usize, expanded: bool, columns: &[Column]) -> Row<'static> {
+    let marker = if expanded { "▾" } else { "▸" };
+    let style = Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD);
+    let label = format!("{} {}", marker, subnet);
+    let count = format!("({} conns)", count);
 
-    // Create app and run it
-    let app = App::new();
-    let res = run_app(&mut terminal, app).await;
+    // Only the active side groups, so the label and count go under the
+    // remote address and state
+    let cells: Vec<String> = columns
+        .iter()
+        .map(|column| match column.kind {
+            ColumnKind::Remote => label.clone(),
+            ColumnKind::State => count.clone(),
+            _ => String::new(),
+        })
+        .collect();
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    Row::new(cells).style(style)
+}
 
-    if let Err(err) = res {
-        println!("{:?}", err)
-    }
+fn anonymize_salt() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0);
+    nanos ^ ((std::process::id() as u64) << 32)
+}
 
-    Ok(())
+// Red is left out since it marks removed rows and CLOSE_WAIT, and magenta
+// since it marks subnet rows and ephemeral ports
+const PROCESS_PALETTE: &[Color] = &[
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::LightCyan,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::White,
+];
+
+// FNV-1a over the name, so a program keeps its colour across refreshes and
+// runs. Unattributed sockets are all grey.
+fn color_for_process(name: Option<&str>) -> Color {
+    let Some(name) = name else {
+        return Color::DarkGray;
+    };
+
+    let hash = name
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    PROCESS_PALETTE[(hash % PROCESS_PALETTE.len() as u64) as usize]
 }
 
-async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    let mut last_tick = Instant::now();
-    let tick_rate = Duration::from_millis(250);
-    let update_rate = Duration::from_secs(1);
+// How long a new connection takes to fade out with 'C' on age
+const AGE_FADE: Duration = Duration::from_secs(300);
 
-    // Initial data load
-    app.update_connections().await;
+// Steps down the xterm grayscale ramp (255 is the brightest) as the
+// connection ages. Past AGE_FADE, and for connections that predate startup,
+// rows are muted.
+fn color_for_age(first_seen: Option<Instant>) -> Color {
+    const BRIGHTEST: u8 = 255;
+    const DIMMEST: u8 = 243;
 
-    loop {
-        terminal.draw(|f| ui(f, &mut app))?;
+    let Some(seen) = first_seen.filter(|seen| seen.elapsed() < AGE_FADE) else {
+        return Color::DarkGray;
+    };
+    let faded = seen.elapsed().as_secs_f64() / AGE_FADE.as_secs_f64();
+    Color::Indexed(BRIGHTEST - (faded * (BRIGHTEST - DIMMEST) as f64) as u8)
+}
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+const LOCAL_IPC_STYLE: Style = Style::new().fg(Color::LightBlue);
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                    KeyCode::Down | KeyCode::Char('j') => app.next(),
-                    KeyCode::Up | KeyCode::Char('k') => app.previous(),
-                    _ => {}
-                }
-            }
+const EPHEMERAL_STYLE: Style = Style::new().fg(Color::Magenta).add_modifier(Modifier::ITALIC);
+
+// The optional Flags column sits right after Iface in both panels
+const FLAGS_COLUMN: usize = 3;
+
+struct Column {
+    kind: ColumnKind,
+    title: &'static str,
+    width: Constraint,
+    // Sort keys shown against this column; clicking it picks the first
+    sort_keys: &'static [SortKey],
+}
+
+fn panel_columns(panel: Panel, view: &ViewOptions) -> Vec<Column> {
+    use ColumnKind::*;
+    let column = |kind, title, width, sort_keys| Column { kind, title, width, sort_keys };
+
+    let mut columns = if panel == Panel::Listening {
+        vec![
+            column(Proto, "Proto", Constraint::Length(6), &[SortKey::Proto][..]),
+            column(Local, "Local Address", Constraint::Length(30), &[SortKey::LocalAddr, SortKey::LocalPort]),
+            column(Iface, "Iface", Constraint::Length(10), &[]),
+            column(AcceptRate, "~Accept/s", Constraint::Length(10), &[]),
+            column(Established, "Active", Constraint::Length(7), &[]),
+            column(Program, "PID/Program", Constraint::Min(25), &[SortKey::Program, SortKey::Pid]),
+        ]
+    } else {
+        vec![
+            column(Proto, "Proto", Constraint::Length(6), &[SortKey::Proto][..]),
+            column(Local, "Local Address", Constraint::Length(22), &[SortKey::LocalAddr, SortKey::LocalPort]),
+            column(Iface, "Iface", Constraint::Length(8), &[]),
+            // Wrapped, two lines of 24 fit the longest "[v6 address]:port"
+            column(
+                Remote,
+                "Foreign Address",
+                Constraint::Length(if view.wrap_remote { 24 } else { 22 }),
+                &[SortKey::RemoteAddr, SortKey::RemotePort],
+            ),
+            column(Scope, "Scope", Constraint::Length(8), &[]),
+            column(State, "State", Constraint::Length(14), &[SortKey::State]),
+            column(Age, "Age", Constraint::Length(view.age_format.width()), &[]),
+            column(Program, "PID/Program", Constraint::Min(18), &[SortKey::Program, SortKey::Pid]),
+        ]
+    };
+    if view.show_flags {
+        columns.insert(FLAGS_COLUMN, column(Flags, "Flags", Constraint::Length(11), &[]));
+    }
+    if view.show_stats {
+        // Before PID/Program. Listeners are mostly UDP sockets and TCP
+        // listeners, where drops and the receive queue are what matter.
+        let stats = if panel == Panel::Listening {
+            vec![
+                column(RecvQueue, "Recv-Q", Constraint::Length(7), &[]),
+                column(Drops, "Drops", Constraint::Length(7), &[]),
+            ]
+        } else {
+            vec![
+                column(RecvQueue, "Recv-Q", Constraint::Length(7), &[]),
+                column(SendQueue, "Send-Q", Constraint::Length(7), &[]),
+                column(Retransmits, "Retr", Constraint::Length(5), &[]),
+            ]
+        };
+        let at = columns.len() - 1;
+        columns.splice(at..at, stats);
+    }
+    if view.show_cgroup {
+        let at = columns.len() - 1;
+        columns.insert(at, column(Cgroup, "Cgroup", Constraint::Length(14), &[]));
+    }
+    if view.show_container {
+        let at = columns.len() - 1;
+        columns.insert(at, column(Container, "Container", Constraint::Length(16), &[]));
+    }
+    if view.show_inode {
+        let at = columns.len() - 1;
+        columns.insert(at, column(Inode, "Inode", Constraint::Length(10), &[]));
+    }
+    if panel == Panel::All {
+        columns.insert(0, column(Kind, "Kind", Constraint::Length(6), &[]));
+    }
+    if view.show_host {
+        columns.insert(0, column(Host, "Host", Constraint::Length(12), &[]));
+    }
+    columns::apply_order(&mut columns, &view.column_order, |column| column.kind);
+
+    // The last column takes the slack, wherever the program column ended up
+    for column in &mut columns {
+        if let Constraint::Min(width) = column.width {
+            column.width = Constraint::Length(width);
         }
+    }
+    if let Some(last) = columns.last_mut() {
+        if let Constraint::Length(width) = last.width {
+            last.width = Constraint::Min(width);
+        }
+    }
+    columns
+}
 
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
+// Mirrors how ratatui lays out table columns (Flex::Start, one cell of
+// spacing, no highlight symbol) so clicks and truncation can be worked out
+// per column
+fn column_rects(columns: &[Column], table_area: Rect) -> Vec<Rect> {
+    let inner = table_area.inner(Margin::new(1, 1));
+    let widths: Vec<Constraint> = columns.iter().map(|column| column.width).collect();
+    Layout::horizontal(widths).flex(Flex::Start).spacing(1).split(inner).to_vec()
+}
+
+fn column_at(columns: &[Column], table_area: Rect, x: u16) -> Option<&Column> {
+    column_rects(columns, table_area)
+        .iter()
+        .position(|rect| x >= rect.x && x < rect.x + rect.width)
+        .map(|i| &columns[i])
+}
+
+// By path component, so "/opt/app" takes in /opt/app/bin/server but not
+// /opt/application. Sockets whose executable can't be read never match.
+fn exe_matches(conn: &Connection, prefix: Option<&Path>) -> bool {
+    match (prefix, &conn.exe) {
+        (None, _) => true,
+        (Some(prefix), Some(exe)) => exe.starts_with(prefix),
+        (Some(_), None) => false,
+    }
+}
+
+// Listeners are left out: their receive queue is the accept backlog
+fn is_queued(conn: &Connection) -> bool {
+    !conn.is_listening() && (conn.send_queue > 0 || conn.recv_queue > 0)
+}
+
+// Past this many bytes in one queue the 'queued' badge turns red
+const QUEUE_SEVERE_BYTES: u32 = 64 * 1024;
+
+// Only TCP and UDP listeners merge; their families share a port space
+fn mergeable(conn: &Connection) -> bool {
+    conn.local_port > 0 && (conn.protocol.starts_with("TCP") || conn.protocol.starts_with("UDP"))
+}
+
+// "TCP" when every merged socket is TCP or TCP6, "mixed" when UDP is there too
+fn merged_protocol(members: &[&Connection]) -> String {
+    let mut bases: Vec<&str> = members.iter().map(|member| member.protocol.trim_end_matches('6')).collect();
+    bases.dedup();
+    match bases.as_slice() {
+        [base] => base.to_string(),
+        _ => "mixed".to_string(),
+    }
+}
+
+// PID/Program and Iface for a merged row. Different owners show as the first
+// plus a count; the detail pane lists each socket.
+fn merged_listener_cells(members: &[&Connection]) -> (String, String) {
+    let mut programs: Vec<String> = Vec::new();
+    for member in members {
+        let program = member.pid_program();
+        if !programs.contains(&program) {
+            programs.push(program);
         }
+    }
+    let program = match programs.len() {
+        1 => programs.remove(0),
+        n => format!("{} +{} more", programs[0], n - 1),
+    };
 
-        // Update connections periodically
-        if app.last_update.elapsed() >= update_rate {
-            app.update_connections().await;
+    let interface = members[0].interface.as_deref().unwrap_or("-");
+    let interface = if members.iter().all(|member| member.interface.as_deref().unwrap_or("-") == interface) {
+        interface.to_string()
+    } else {
+        "*".to_string()
+    };
+    (program, interface)
+}
+
+// The container's name once the daemon has answered, its short ID until
+// then or when there is no daemon to ask
+fn container_label(conn: &Connection, containers: &ContainerNames) -> String {
+    match conn.cgroup.as_deref().and_then(network::container_id) {
+        Some(id) => containers.name(id).map_or_else(|| id[..12].to_string(), str::to_string),
+        None => "-".to_string(),
+    }
+}
+
+// TIME_WAIT and --ssh sockets have no inode to show
+fn inode_text(conn: &Connection) -> String {
+    match conn.inode {
+        0 => "-".to_string(),
+        inode => inode.to_string(),
+    }
+}
+
+// SI suffixes from 1000 up: "999", "1.4k", "23k", "2.3M". One decimal below
+// ten keeps the text at four characters or less.
+fn humanize(n: u64) -> String {
+    const UNITS: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+    if n < 1000 {
+        return n.to_string();
+    }
+    let mut value = n as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 999.5 && unit + 1 < UNITS.len() {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if value < 9.95 {
+        format!("{:.1}{}", value, UNITS[unit])
+    } else {
+        format!("{:.0}{}", value, UNITS[unit])
+    }
+}
+
+// ESTABLISHED TCP connections on a TCP listener's port and host, from the
+// last refresh. Like the accept rate this goes by port alone, as a wildcard
+// listener takes connections on every local address.
+fn established_on(conn: &Connection, by_port: Option<&HashMap<u16, HashSet<ConnectionKey>>>) -> Option<usize> {
+    if !conn.protocol.starts_with("TCP") || conn.state != "LISTEN" {
+        return None;
+    }
+    let keys = by_port?.get(&conn.local_port);
+    Some(keys.map_or(0, |keys| keys.iter().filter(|key| key.host == conn.host).count()))
+}
+
+// Cuts `text` to `width` terminal cells with a trailing ellipsis. Goes by
+// display width char by char, so multi-byte and double-width names are never
+// split mid-character.
+fn truncate_to_width(text: &str, width: usize) -> String {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
         }
+        truncated.push(c);
+        used += char_width;
     }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
 }
 
-fn ui(f: &mut Frame, app: &mut App) {
-    // Split screen into two panels
-    let main_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-        .split(f.area());
+// `name` followed by its markers in `width` cells. The name is what gets
+// cut, so the markers stay readable unless they alone don't fit.
+fn fit_label(name: &str, markers: &str, width: usize) -> String {
+    use unicode_width::UnicodeWidthStr;
 
-    // Separate listening and active connections
-    let listening_connections: Vec<&Connection> = app.connections
-        .iter()
-        .filter(|conn| conn.state == "LISTEN" || conn.state.is_empty())
-        .collect();
+    let label = format!("{}{}", name, markers);
+    if label.width() <= width || markers.width() >= width {
+        return truncate_to_width(&label, width);
+    }
+    format!("{}{}", truncate_to_width(name, width - markers.width()), markers)
+}
 
-    let active_connections: Vec<&Connection> = app.connections
-        .iter()
-        .filter(|conn| conn.state != "LISTEN" && !conn.state.is_empty())
-        .collect();
+// `text` on two lines of `width` cells when it doesn't fit on one, with the
+// second cut like truncate_to_width. Splits by display width, as that does.
+fn wrap_to_two_lines(text: &str, width: usize) -> String {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-    // Render listening connections panel
-    render_connections_table(
-        f,
-        main_chunks[0],
-        &listening_connections,
-        "Listening Ports",
-        &mut app.table_state,
-        false,
-    );
+    if text.width() <= width {
+        return text.to_string();
+    }
 
-    // Render active connections panel
-    render_connections_table(
-        f,
-        main_chunks[1],
-        &active_connections,
-        "Active Connections",
-        &mut app.table_state,
-        true,
-    );
+    let mut used = 0;
+    let split = text
+        .char_indices()
+        .find(|(_, c)| {
+            used += c.width().unwrap_or(0);
+            used > width
+        })
+        .map_or(text.len(), |(at, _)| at);
+    format!("{}\n{}", &text[..split], truncate_to_width(&text[split..], width))
+}
+
+struct PanelView<'a> {
+    title: String,
+    connection_count: usize,
+    rows: Vec<PanelRow<'a>>,
+    panel: Panel,
+    sort: PanelSort,
+    focused: bool,
+    exposed_count: Option<usize>,
+    // (connections, largest queue in bytes) for the congestion badge
+    queued: Option<(usize, u32)>,
+    // Rows new since the baseline; empty when no baseline is set
+    added: HashSet<ConnectionKey>,
+    pinned: &'a HashSet<ConnectionKey>,
+    checked: &'a HashSet<ConnectionKey>,
+    scans: &'a ScanDetector,
+    firewall: Option<&'a Firewall>,
+    accept_rates: Option<&'a HashMap<u16, f64>>,
+    established_by_port: Option<&'a HashMap<u16, HashSet<ConnectionKey>>>,
+    listen_queues: &'a HashMap<u64, diag::ListenQueue>,
+    first_seen: Option<&'a HashMap<ConnectionKey, Option<Instant>>>,
+    // Merged listener rows ('M'), by the key of the row that stands for them
+    merged: HashMap<ConnectionKey, Vec<&'a Connection>>,
+    unowned: HashMap<ConnectionKey, Unowned>,
+    // PID/Program text for unattributed sockets whose owner is known
+    owners: HashMap<ConnectionKey, String>,
 }
 
 fn render_connections_table(
     f: &mut Frame,
-    area: ratatui::layout::Rect,
-    connections: &[&Connection],
-    title: &str,
-    _table_state: &mut TableState,
-    show_foreign: bool,
+    area: Rect,
+    panel: &PanelView,
+    state: &mut TableState,
+    view: &ViewOptions,
+    dns: &DnsCache,
+    containers: &ContainerNames,
 ) {
-    let header_cells: Vec<Cell> = if !show_foreign {
-        ["Proto", "Local Address", "PID/Program"]
-            .iter()
-            .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
-            .collect()
-    } else {
-        ["Proto", "Local Address", "Foreign Address", "State", "PID/Program"]
-            .iter()
-            .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
-            .collect()
-    };
+    let title = &panel.title;
+
+    let columns = panel_columns(panel.panel, view);
+    let program_width = columns
+        .iter()
+        .zip(column_rects(&columns, area))
+        .find(|(column, _)| column.kind == ColumnKind::Program)
+        .map_or(usize::MAX, |(_, rect)| rect.width as usize);
+    let remote_width = columns
+        .iter()
+        .zip(column_rects(&columns, area))
+        .find(|(column, _)| column.kind == ColumnKind::Remote)
+        .map(|(_, rect)| rect.width as usize);
+    // Listeners have no foreign address to wrap
+    let row_height = if view.wrap_remote && remote_width.is_some() { 2 } else { 1 };
+
+    let header_cells: Vec<Cell> = columns
+        .iter()
+        .map(|column| {
+            let arrow = match panel.sort.key {
+                Some(key) if column.sort_keys.contains(&key) => format!(" {}", panel.sort.arrow()),
+                _ => String::new(),
+            };
+            Cell::from(format!("{}{}", column.title, arrow))
+                .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        })
+        .collect();
     
     let header = Row::new(header_cells).height(1).bottom_margin(1);
 
-    let rows = connections.iter().map(|conn| {
-        let local_addr = if conn.local_port > 0 {
-            format!("{}:{}", conn.local_address, conn.local_port)
-        } else {
-            conn.local_address.clone()
+    let mut title_spans = vec![Span::raw(format!(
+        " {} - {} connections{} (Press 'q' to quit) ",
+        title,
+        view.number(panel.connection_count as u64),
+        match panel.sort.key {
+            Some(key) => format!(", sorted by {} {}", key.name(), panel.sort.arrow()),
+            None => String::new(),
+        }
+    ))];
+    if let Some((low, high)) = view.ephemeral_ports {
+        title_spans.push(Span::styled(format!(" ephemeral {}-{} ", low, high), EPHEMERAL_STYLE));
+    }
+    if let Some((count, largest)) = panel.queued.filter(|&(count, _)| count > 0) {
+        let severity = if largest >= QUEUE_SEVERE_BYTES { Color::Red } else { Color::Yellow };
+        title_spans.push(Span::styled(
+            format!(" {} queued ", view.number(count as u64)),
+            Style::default().fg(Color::Black).bg(severity).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(exposed) = panel.exposed_count.filter(|&n| n > 0) {
+        title_spans.push(Span::styled(
+            format!(" {} exposed ", exposed),
+            Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let title_line = Line::from(title_spans);
+
+    let rows = panel.rows.iter().map(|row| {
+        let (conn, removed) = match row {
+            PanelRow::Connection(conn) => (*conn, false),
+            PanelRow::Removed(conn) => (*conn, true),
+            PanelRow::Subnet { subnet, count, expanded } => {
+                let subnet = view.masked(subnet, false).unwrap_or_else(|| subnet.to_string());
+                return render_subnet_row(&subnet, *count, *expanded, &columns);
+            }
         };
+        let added = !removed && panel.added.contains(&conn.key());
 
-        let remote_addr = if conn.remote_port > 0 {
-            format!("{}:{}", conn.remote_address, conn.remote_port)
-        } else if conn.remote_address == "0.0.0.0" || conn.remote_address == "::" {
-            "*:*".to_string()
+        let pinned = panel.pinned.contains(&conn.key());
+        let overloaded = conn.state == "LISTEN"
+            && panel.listen_queues.get(&conn.inode).is_some_and(|queue| queue.is_near_full());
+        let merged = panel.merged.get(&conn.key());
+        // "443 (tcp, tcp6)" for a merged listener
+        let local_endpoint = match merged {
+            Some(members) => {
+                let protocols: Vec<String> = members.iter().map(|member| member.protocol.to_lowercase()).collect();
+                format!("{} ({})", conn.local_port, protocols.join(", "))
+            }
+            None => view.local_endpoint(conn),
+        };
+        let local_addr = match (overloaded, pinned) {
+            (true, _) => format!("⚠ {}", local_endpoint),
+            (false, true) => format!("◆ {}", local_endpoint),
+            (false, false) => local_endpoint,
+        };
+        let local_addr = if panel.checked.contains(&conn.key()) {
+            format!("✓ {}", local_addr)
         } else {
-            conn.remote_address.clone()
+            local_addr
+        };
+        // Hostnames would give away what the pseudonym hides
+        let remote_addr = match (view.masked(&conn.remote_address, false), dns.hostname(&conn.remote_address)) {
+            (Some(name), _) => format!("{}:{}", name, conn.remote_port),
+            (None, Some(host)) if view.resolve_hosts => format!("{}:{}", host, conn.remote_port),
+            _ => conn.remote_display(),
+        };
+        let remote_addr = match remote_width {
+            Some(width) if view.wrap_remote => wrap_to_two_lines(&remote_addr, width),
+            _ => remote_addr,
         };
 
-        let state = if conn.state.is_empty() {
-            "-".to_string()
+        let state = if conn.likely_tls(&view.tls_ports) {
+            format!("{} 🔒", conn.state_display())
         } else {
-            conn.state.clone()
+            conn.state_display().to_string()
         };
 
-        let pid_program = match (&conn.pid, &conn.process_name) {
-            (Some(pid), Some(name)) => format!("{}/{}", pid, name),
-            (Some(pid), None) => format!("{}/unknown", pid),
-            (None, Some(name)) => format!("-/{}", name),
-            (None, None) => "-".to_string(),
+        // An estimate from the refresh interval, so it's marked with '~'
+        let accept_rate = match panel.accept_rates {
+            Some(rates) if conn.protocol.starts_with("TCP") && conn.state == "LISTEN" => {
+                format!("~{:.1}", rates.get(&conn.local_port).copied().unwrap_or(0.0))
+            }
+            _ => "-".to_string(),
+        };
+        let (pid_program, interface) = match merged {
+            Some(members) => merged_listener_cells(members),
+            None => (
+                panel.owners.get(&conn.key()).cloned().unwrap_or_else(|| conn.pid_program()),
+                conn.interface.clone().unwrap_or_else(|| "-".to_string()),
+            ),
         };
+        let scanning = !conn.is_listening()
+            && conn.host.is_none()
+            && conn.pid.is_some_and(|pid| panel.scans.suspicion_for(pid).is_some());
+        let unowned = panel.unowned.get(&conn.key());
+        let local_ipc = view.local_ipc != LocalIpc::Off && conn.is_local_ipc();
+        let mut markers = String::new();
+        if local_ipc {
+            markers.push_str(" (local IPC)");
+        }
+        if panel.firewall.is_some_and(|firewall| firewall.blocks(conn)) {
+            markers.push_str(" (firewalled?)");
+        }
+        match unowned {
+            Some(Unowned::Orphan) => markers.push_str(" (orphan?)"),
+            None if scanning => markers.push_str(" (scanning?)"),
+            _ => {}
+        }
+        let pid_program = fit_label(&pid_program, &markers, program_width);
 
-        let style = match conn.state.as_str() {
-            "LISTEN" => Style::default().fg(Color::Green),
-            "ESTABLISHED" => Style::default().fg(Color::Cyan),
-            "TIME_WAIT" => Style::default().fg(Color::Yellow),
-            "CLOSE_WAIT" => Style::default().fg(Color::Red),
+        let style = match (view.color_by, conn.state.as_str()) {
+            // Colour is stripped afterwards, so states get a modifier each
+            // whatever 'C' is set to
+            (_, "LISTEN") if view.monochrome => Style::default().add_modifier(Modifier::BOLD),
+            (_, "TIME_WAIT") if view.monochrome => Style::default().add_modifier(Modifier::DIM),
+            (_, "CLOSE_WAIT") if view.monochrome => Style::default().add_modifier(Modifier::UNDERLINED),
+            _ if view.monochrome => Style::default(),
+            (ColorBy::Process, _) => Style::default().fg(color_for_process(conn.process_name.as_deref())),
+            (ColorBy::Age, _) => Style::default().fg(color_for_age(
+                panel.first_seen.and_then(|seen| seen.get(&conn.key()).copied().flatten()),
+            )),
+            _ if local_ipc && view.local_ipc == LocalIpc::Highlight => LOCAL_IPC_STYLE,
+            (_, "LISTEN") => Style::default().fg(Color::Green),
+            (_, "ESTABLISHED") => Style::default().fg(Color::Cyan),
+            (_, "TIME_WAIT") => Style::default().fg(Color::Yellow),
+            (_, "CLOSE_WAIT") => Style::default().fg(Color::Red),
             _ => Style::default().fg(Color::White),
         };
 
-        if !show_foreign {
-            Row::new(vec![
-                Cell::from(conn.protocol.clone()),
-                Cell::from(local_addr),
-                Cell::from(pid_program),
-            ]).style(style)
+        // Baseline diff marking overrides the state colours
+        let (style, protocol) = if let Some(members) = merged {
+            (style, merged_protocol(members))
+        } else if removed {
+            (Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT), format!("-{}", conn.protocol))
+        } else if added {
+            (Style::default().fg(Color::Green).add_modifier(Modifier::BOLD), format!("+{}", conn.protocol))
         } else {
-            Row::new(vec![
-                Cell::from(conn.protocol.clone()),
-                Cell::from(local_addr),
-                Cell::from(remote_addr),
-                Cell::from(state),
-                Cell::from(pid_program),
-            ]).style(style)
-        }
+            (style, conn.protocol.clone())
+        };
+        let style = if pinned { style.add_modifier(Modifier::BOLD) } else { style };
+        let style = if overloaded && !removed {
+            Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD)
+        } else {
+            style
+        };
+
+        let port_style = |port: u16| match view.ephemeral_ports {
+            Some((low, high)) if (low..=high).contains(&port) => EPHEMERAL_STYLE,
+            _ => Style::default(),
+        };
+
+        // One cell per column, in the columns' order, so headers and data
+        // move together when the config reorders them
+        let cells: Vec<Cell> = columns
+            .iter()
+            .map(|column| match column.kind {
+                ColumnKind::Host => Cell::from(conn.host.clone().unwrap_or_else(|| "local".to_string())),
+                ColumnKind::Kind => Cell::from(if conn.is_listening() { "listen" } else { "active" }),
+                ColumnKind::Proto => Cell::from(protocol.clone()),
+                ColumnKind::Local => Cell::from(local_addr.clone()).style(port_style(conn.local_port)),
+                ColumnKind::Iface => Cell::from(interface.clone()),
+                ColumnKind::Flags => Cell::from(conn.flags.clone().unwrap_or_default()),
+                ColumnKind::AcceptRate => Cell::from(accept_rate.clone()),
+                ColumnKind::Established => Cell::from(
+                    established_on(conn, panel.established_by_port).map_or("-".to_string(), |count| view.number(count as u64)),
+                ),
+                ColumnKind::Remote => Cell::from(remote_addr.clone()).style(port_style(conn.remote_port)),
+                // Public remotes stand out, as those connections leave the
+                // local network
+                ColumnKind::Scope => match network::address_scope(&conn.remote_address) {
+                    Some(scope @ network::Scope::Public) => {
+                        Cell::from(scope.name()).style(Style::default().fg(Color::LightYellow))
+                    }
+                    Some(scope) => Cell::from(scope.name()),
+                    None => Cell::from("-"),
+                },
+                ColumnKind::State => Cell::from(state.clone()),
+                // Connections open before startup have no known age
+                ColumnKind::Age => match panel.first_seen.and_then(|seen| seen.get(&conn.key()).copied().flatten()) {
+                    Some(seen) => Cell::from(format_age(view.age_format, seen)),
+                    None => Cell::from("-"),
+                },
+                ColumnKind::RecvQueue => Cell::from(view.number(conn.recv_queue.into())),
+                ColumnKind::SendQueue => Cell::from(view.number(conn.send_queue.into())),
+                // Only TCP retransmits; other protocols have none to show
+                ColumnKind::Retransmits if conn.protocol.starts_with("TCP") => {
+                    Cell::from(view.number(conn.retransmits.into()))
+                }
+                ColumnKind::Retransmits => Cell::from("-"),
+                ColumnKind::Drops => Cell::from(conn.drops.map_or("-".to_string(), |drops| view.number(drops))),
+                ColumnKind::Cgroup => Cell::from(conn.cgroup.as_deref().map_or("-".to_string(), network::cgroup_label)),
+                ColumnKind::Container => Cell::from(container_label(conn, containers)),
+                ColumnKind::Inode => Cell::from(inode_text(conn)),
+                ColumnKind::Program => match unowned {
+                    Some(Unowned::Orphan) => {
+                        Cell::from(pid_program.clone()).style(Style::default().fg(Color::LightMagenta))
+                    }
+                    Some(Unowned::Kernel) => Cell::from(pid_program.clone()).style(Style::default().fg(Color::DarkGray)),
+                    None if scanning => Cell::from(pid_program.clone())
+                        .style(Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD)),
+                    None => Cell::from(pid_program.clone()),
+                },
+            })
+            .collect();
+
+        Row::new(cells).style(style)
     });
+    let rows = rows.map(|row| row.height(row_height));
 
-    let widths = if !show_foreign {
-        [
-            Constraint::Length(6),  // Proto
-            Constraint::Length(30), // Local Address
-            Constraint::Min(25),    // PID/Program
-        ].as_ref()
-    } else {
-        [
-            Constraint::Length(6),  // Proto
-            Constraint::Length(22), // Local Address
-            Constraint::Length(22), // Foreign Address
-            Constraint::Length(12), // State
-            Constraint::Min(18),    // PID/Program
-        ].as_ref()
-    };
+    let widths: Vec<Constraint> = columns.iter().map(|column| column.width).collect();
 
     let table = Table::new(rows, widths)
         .header(header)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!(
-                    " {} - {} connections (Press 'q' to quit) ",
-                    title,
-                    connections.len()
-                ))
+                .border_style(if panel.focused {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                })
+                .title(title_line)
         )
         .highlight_style(
             Style::default()
@@ -286,5 +3508,117 @@ fn render_connections_table(
                 .add_modifier(Modifier::BOLD)
         );
 
-    f.render_widget(table, area);
+    f.render_stateful_widget(table, area, state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthStr;
+
+    #[test]
+    fn long_program_names_keep_their_markers() {
+        assert_eq!(fit_label("123/nginx", " (orphan?)", 30), "123/nginx (orphan?)");
+
+        let label = fit_label("4242/a-very-long-worker-name", " (firewalled?) (scanning?)", 34);
+        assert_eq!(label, "4242/a-… (firewalled?) (scanning?)");
+        assert_eq!(label.width(), 34);
+
+        // Markers wider than the cell are cut like any other text
+        assert_eq!(fit_label("1/x", " (scanning?)", 6), "1/x (…");
+    }
+
+    #[test]
+    fn compact_numbers_use_si_suffixes() {
+        assert_eq!(humanize(0), "0");
+        assert_eq!(humanize(999), "999");
+        assert_eq!(humanize(1000), "1.0k");
+        assert_eq!(humanize(1423), "1.4k");
+        assert_eq!(humanize(9_949), "9.9k");
+        assert_eq!(humanize(9_950), "10k");
+        assert_eq!(humanize(23_456), "23k");
+        // A value that would round to "1000k" moves up a unit instead
+        assert_eq!(humanize(999_499), "999k");
+        assert_eq!(humanize(999_500), "1.0M");
+        assert_eq!(humanize(2_345_678), "2.3M");
+        assert_eq!(humanize(u64::MAX), "18E");
+    }
+
+    #[test]
+    fn interval_command() {
+        let mut app = App::new(&Args::default(), Config::default());
+
+        assert_eq!(app.run_command("interval 250"), Ok("Refresh interval set to 250 ms".to_string()));
+        assert_eq!(app.config.refresh_rate, Duration::from_millis(250));
+        assert!(app.run_command("interval 2000ms").is_ok());
+        assert_eq!(app.config.refresh_rate, Duration::from_secs(2));
+
+        assert_eq!(
+            app.run_command("interval 50"),
+            Err("interval: 50 ms is out of range (100-60000 ms)".to_string())
+        );
+        assert_eq!(
+            app.run_command("interval soon"),
+            Err("interval: 'soon' is not a number of milliseconds".to_string())
+        );
+        assert_eq!(app.run_command("interval 1 2"), Err("Usage: interval <ms>".to_string()));
+        assert_eq!(
+            app.run_command("sort pid"),
+            Err("Unknown command 'sort' (commands: interval <ms>)".to_string())
+        );
+        assert_eq!(app.config.refresh_rate, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn exe_prefix_matches_whole_path_components() {
+        let running = |exe: Option<&str>| Connection { exe: exe.map(PathBuf::from), ..Default::default() };
+        let prefix = Some(Path::new("/opt/app"));
+
+        assert!(exe_matches(&running(Some("/opt/app/bin/server")), prefix));
+        assert!(exe_matches(&running(Some("/opt/app")), prefix));
+        assert!(!exe_matches(&running(Some("/opt/application/bin/server")), prefix));
+        assert!(!exe_matches(&running(None), prefix));
+        assert!(exe_matches(&running(None), None));
+    }
+
+    #[test]
+    fn long_addresses_wrap_onto_a_second_line() {
+        assert_eq!(wrap_to_two_lines("10.0.0.1:443", 20), "10.0.0.1:443");
+        assert_eq!(wrap_to_two_lines("[2001:db8::1]:443", 10), "[2001:db8:\n:1]:443");
+        assert_eq!(wrap_to_two_lines("[2001:db8:aaaa::1]:443", 8), "[2001:db\n8:aaaa:…");
+        // Double-width characters aren't split across the two lines
+        assert_eq!(wrap_to_two_lines("例え.example:80", 3), "例\nえ…");
+    }
+
+    #[test]
+    fn active_counts_established_connections_per_listener() {
+        let socket = |protocol: &str, state: &str, remote_port, host: Option<&str>| Connection {
+            protocol: protocol.to_string(),
+            local_port: 443,
+            remote_port,
+            state: state.to_string(),
+            host: host.map(str::to_string),
+            ..Default::default()
+        };
+        let established: HashSet<ConnectionKey> = [
+            socket("TCP", "ESTABLISHED", 50001, None),
+            socket("TCP6", "ESTABLISHED", 50002, None),
+            socket("TCP", "ESTABLISHED", 50003, Some("web1")),
+        ]
+        .iter()
+        .map(Connection::key)
+        .collect();
+        let by_port = HashMap::from([(443, established)]);
+
+        assert_eq!(established_on(&socket("TCP", "LISTEN", 0, None), Some(&by_port)), Some(2));
+        assert_eq!(established_on(&socket("TCP6", "LISTEN", 0, Some("web1")), Some(&by_port)), Some(1));
+        assert_eq!(
+            established_on(&Connection { local_port: 80, ..socket("TCP", "LISTEN", 0, None) }, Some(&by_port)),
+            Some(0)
+        );
+        // Not a TCP listener, or no refresh sampled yet
+        assert_eq!(established_on(&socket("UDP", "", 0, None), Some(&by_port)), None);
+        assert_eq!(established_on(&socket("TCP", "ESTABLISHED", 50001, None), Some(&by_port)), None);
+        assert_eq!(established_on(&socket("TCP", "LISTEN", 0, None), None), None);
+    }
 }
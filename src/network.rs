@@ -1,9 +1,106 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::net::{Ipv4Addr, Ipv6Addr};
-use anyhow::Result;
+use std::ffi::CStr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use crate::diag;
+use crate::ss;
+use log::{debug, trace, warn};
 
-#[derive(Debug, Clone)]
+// Kernel timer pending on a TCP socket (the "tr" half of tr:tm->when)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimerKind {
+    #[default]
+    None,
+    Retransmit,
+    Keepalive,
+    TimeWait,
+    ZeroWindowProbe,
+}
+
+impl TimerKind {
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => TimerKind::Retransmit,
+            2 => TimerKind::Keepalive,
+            3 => TimerKind::TimeWait,
+            4 => TimerKind::ZeroWindowProbe,
+            _ => TimerKind::None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimerKind::None => "none",
+            TimerKind::Retransmit => "retransmit",
+            TimerKind::Keepalive => "keepalive",
+            TimerKind::TimeWait => "timewait",
+            TimerKind::ZeroWindowProbe => "zero-window probe",
+        }
+    }
+}
+
+// Where an address sits, for telling internal traffic from egress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Loopback,
+    LinkLocal,
+    // RFC 1918 and IPv6 unique local (fc00::/7)
+    Private,
+    Public,
+}
+
+impl Scope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scope::Loopback => "loopback",
+            Scope::LinkLocal => "link",
+            Scope::Private => "private",
+            Scope::Public => "public",
+        }
+    }
+}
+
+// `None` for wildcards and for non-IP addresses. V4-mapped IPv6 addresses
+// are classified as the IPv4 address they carry.
+pub fn address_scope(address: &str) -> Option<Scope> {
+    let v4_scope = |v4: Ipv4Addr| {
+        if v4.is_unspecified() {
+            None
+        } else if v4.is_loopback() {
+            Some(Scope::Loopback)
+        } else if v4.is_link_local() {
+            Some(Scope::LinkLocal)
+        } else if v4.is_private() {
+            Some(Scope::Private)
+        } else {
+            Some(Scope::Public)
+        }
+    };
+
+    match address.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => v4_scope(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => v4_scope(v4),
+            None if v6.is_unspecified() => None,
+            None if v6.is_loopback() => Some(Scope::Loopback),
+            None if v6.segments()[0] & 0xffc0 == 0xfe80 => Some(Scope::LinkLocal),
+            None if v6.segments()[0] & 0xfe00 == 0xfc00 => Some(Scope::Private),
+            None => Some(Scope::Public),
+        },
+    }
+}
+
+// __SO_ACCEPTCON in the Flags column of /proc/net/unix
+const UNIX_ACCEPTCON: u32 = 0x10000;
+
+// /proc reports timer expiry in clock ticks; USER_HZ is 100 on every
+// mainstream architecture
+const USER_HZ: f64 = 100.0;
+
+#[derive(Debug, Clone, Default)]
 pub struct Connection {
     pub protocol: String,
     pub local_address: String,
@@ -13,179 +110,869 @@ pub struct Connection {
     pub state: String,
     pub pid: Option<u32>,
     pub process_name: Option<String>,
+    pub uid: Option<u32>,
+    pub inode: u64,
+    // UNIX sockets only: "ACC" for accepting listeners plus the refcount
+    pub flags: Option<String>,
+    pub interface: Option<String>,
+    pub timer: TimerKind,
+    pub timer_expires_secs: f64,
+    pub retransmits: u32,
+    // Bytes queued in the kernel. For listeners the receive queue is the
+    // accept backlog in use rather than bytes.
+    pub send_queue: u32,
+    pub recv_queue: u32,
+    // Datagrams dropped on receive; only UDP tables report this
+    pub drops: Option<u64>,
+    // Most specific cgroup path of the owning process, e.g.
+    // "/system.slice/docker-<id>.scope"
+    pub cgroup: Option<String>,
+    // Target of /proc/<pid>/exe, when readable
+    pub exe: Option<PathBuf>,
+    // The --ssh destination this socket was read from; `None` for this
+    // machine. Remote sockets have no inode, as theirs would be meaningless here.
+    pub host: Option<String>,
+}
+
+// Process name given to sockets the kernel holds itself (NFS, in-kernel
+// servers, tunnels), which have no file and so no inode or PID
+pub const KERNEL_PROCESS: &str = "[kernel]";
+
+// TIME_WAIT and SYN_RECV entries are inode-less mini-sockets of connections
+// being torn down or set up, not kernel sockets
+fn held_by_kernel(conn: &Connection) -> bool {
+    conn.inode == 0
+        && conn.pid.is_none()
+        && conn.protocol != "UNIX"
+        && conn.state != "TIME_WAIT"
+        && conn.state != "SYN_RECV"
+}
+
+// Identity of a socket across refreshes; PIDs and states can change while
+// the endpoints stay the same
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionKey {
+    pub protocol: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub host: Option<String>,
+    // UNIX sockets only: clients of one path have identical endpoints
+    pub inode: u64,
+}
+
+// "1.2.3.4:80" or "[2001:db8::1]:80"
+fn endpoint(address: &str, port: u16) -> String {
+    if address.contains(':') {
+        format!("[{}]:{}", address, port)
+    } else {
+        format!("{}:{}", address, port)
+    }
+}
+
+impl Connection {
+    pub fn key(&self) -> ConnectionKey {
+        ConnectionKey {
+            protocol: self.protocol.clone(),
+            local_address: self.local_address.clone(),
+            local_port: self.local_port,
+            remote_address: self.remote_address.clone(),
+            remote_port: self.remote_port,
+            host: self.host.clone(),
+            inode: if self.protocol == "UNIX" { self.inode } else { 0 },
+        }
+    }
+
+    // Listening sockets and connectionless (state-less) sockets share the top panel
+    pub fn is_listening(&self) -> bool {
+        self.state == "LISTEN" || self.state.is_empty()
+    }
+
+    // Bound to anything but loopback, wildcard binds included. Addresses we
+    // can't parse count as exposed so they aren't hidden from an audit.
+    pub fn is_exposed(&self) -> bool {
+        if self.protocol == "UNIX" {
+            return false;
+        }
+
+        address_scope(&self.local_address) != Some(Scope::Loopback)
+    }
+
+    pub fn is_attributed(&self) -> bool {
+        self.pid.is_some() || self.process_name.is_some()
+    }
+
+    // An application talking to itself or a neighbour over loopback
+    pub fn is_local_ipc(&self) -> bool {
+        address_scope(&self.local_address) == Some(Scope::Loopback)
+            && address_scope(&self.remote_address) == Some(Scope::Loopback)
+    }
+
+    pub fn is_kernel(&self) -> bool {
+        self.pid.is_none() && self.process_name.as_deref() == Some(KERNEL_PROCESS)
+    }
+
+    // IPv6 addresses are bracketed when a port follows, so "::1" on 631
+    // reads "[::1]:631" rather than the ambiguous "::1:631"
+    pub fn local_display(&self) -> String {
+        if self.local_port > 0 {
+            endpoint(&self.local_address, self.local_port)
+        } else {
+            self.local_address.clone()
+        }
+    }
+
+    // Table form of the local endpoint: IPv4 wildcard binds read "*:80" and
+    // IPv6 ones "[::]:80" as in ss, while the detail pane keeps "0.0.0.0:80"
+    pub fn local_bind_display(&self) -> String {
+        match self.local_address.as_str() {
+            "0.0.0.0" if self.local_port > 0 => format!("*:{}", self.local_port),
+            _ => self.local_display(),
+        }
+    }
+
+    pub fn remote_display(&self) -> String {
+        if self.remote_port > 0 {
+            endpoint(&self.remote_address, self.remote_port)
+        } else if self.remote_address == "0.0.0.0" || self.remote_address == "::" {
+            "*:*".to_string()
+        } else {
+            self.remote_address.clone()
+        }
+    }
+
+    pub fn state_display(&self) -> &str {
+        if self.state.is_empty() {
+            "-"
+        } else {
+            &self.state
+        }
+    }
+
+    pub fn pid_program(&self) -> String {
+        match (&self.pid, &self.process_name) {
+            (Some(pid), Some(name)) => format!("{}/{}", pid, name),
+            (Some(pid), None) => format!("{}/unknown", pid),
+            (None, Some(name)) => format!("-/{}", name),
+            (None, None) => "-".to_string(),
+        }
+    }
+
+    // Port-based guess only: we never inspect the traffic itself, so a TLS
+    // service on an unusual port is missed and plaintext on 443 is flagged.
+    pub fn likely_tls(&self, tls_ports: &[u16]) -> bool {
+        self.remote_port > 0 && tls_ports.contains(&self.remote_port)
+    }
+}
+
+// Bytes per second derived from rchar/wchar in /proc/<pid>/io. These count
+// every read()/write() the process makes (files, pipes, sockets), so they are
+// an upper bound on network traffic rather than a measurement of it.
+#[derive(Debug, Clone, Copy)]
+pub struct IoRate {
+    pub read_per_sec: f64,
+    pub write_per_sec: f64,
+}
+
+// Figures from the most recent `get_connections` call, for the debug overlay
+#[derive(Debug, Clone, Default)]
+pub struct ScanStats {
+    pub duration: Duration,
+    pub by_protocol: BTreeMap<String, usize>,
+    pub inode_map_size: usize,
+    // Processes whose fds weren't searched as they're in another network
+    // namespace than the one the sockets were read from
+    pub other_netns_processes: usize,
+    pub process_cache_size: usize,
+    // Process name lookups answered from the cache, and ones that read comm
+    pub process_cache_hits: usize,
+    pub process_cache_misses: usize,
+    pub skipped_lines: usize,
+}
+
+// Which of the core proc tables to read; unselected files are never opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolSet {
+    pub tcp: bool,
+    pub tcp6: bool,
+    pub udp: bool,
+    pub udp6: bool,
+}
+
+impl Default for ProtocolSet {
+    fn default() -> Self {
+        Self {
+            tcp: true,
+            tcp6: true,
+            udp: true,
+            udp6: true,
+        }
+    }
+}
+
+impl ProtocolSet {
+    // Comma-separated subset of tcp, tcp6, udp, udp6
+    pub fn parse(list: &str) -> Result<Self> {
+        let mut set = Self {
+            tcp: false,
+            tcp6: false,
+            udp: false,
+            udp6: false,
+        };
+
+        for name in list.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name.to_lowercase().as_str() {
+                "tcp" => set.tcp = true,
+                "tcp6" => set.tcp6 = true,
+                "udp" => set.udp = true,
+                "udp6" => set.udp6 = true,
+                _ => anyhow::bail!("unknown protocol '{}' (expected tcp, tcp6, udp or udp6)", name),
+            }
+        }
+
+        Ok(set)
+    }
+
+    pub fn is_all(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        [(self.tcp, "tcp"), (self.tcp6, "tcp6"), (self.udp, "udp"), (self.udp6, "udp6")]
+            .into_iter()
+            .filter_map(|(on, name)| on.then_some(name))
+            .collect()
+    }
+}
+
+// Where TCP and UDP sockets are read from; `ss` is a fallback for kernels
+// whose /proc/net layout we can't parse, and netlink asks sock_diag
+// directly. All three can be switched between live to compare them.
+// Windows has none of them, only the IP Helper tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Source {
+    #[cfg_attr(not(windows), default)]
+    Proc,
+    Ss,
+    Netlink,
+    #[cfg(windows)]
+    #[default]
+    Windows,
+}
+
+impl Source {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "proc" => Ok(Source::Proc),
+            "ss" => Ok(Source::Ss),
+            "netlink" => Ok(Source::Netlink),
+            #[cfg(windows)]
+            "windows" => Ok(Source::Windows),
+            _ => anyhow::bail!("unknown source '{}' (expected proc, ss or netlink)", name),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Proc => "proc",
+            Source::Ss => "ss",
+            Source::Netlink => "netlink",
+            #[cfg(windows)]
+            Source::Windows => "windows",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Source::Proc => Source::Ss,
+            Source::Ss => Source::Netlink,
+            Source::Netlink => Source::Proc,
+            #[cfg(windows)]
+            Source::Windows => Source::Windows,
+        }
+    }
+
+    // `proc_dir` is only read by the proc source
+    pub fn open(self, proc_dir: &Path) -> Box<dyn ConnectionSource> {
+        match self {
+            Source::Proc => Box::new(ProcSource::new(proc_dir.to_path_buf())),
+            Source::Ss => Box::<ss::SsCommandSource>::default(),
+            Source::Netlink => Box::new(NetlinkSource),
+            #[cfg(windows)]
+            Source::Windows => Box::<crate::iphlpapi::WindowsSource>::default(),
+        }
+    }
+}
+
+// One way of listing TCP and UDP sockets, as picked by `Source`
+pub trait ConnectionSource {
+    fn connections(&mut self, protocols: ProtocolSet) -> Result<Vec<Connection>>;
+
+    fn kind(&self) -> Source;
+
+    // Sources that fill in pid and process name themselves; the rest are
+    // attributed by the monitor from its scan of /proc/*/fd
+    fn attributes_owners(&self) -> bool {
+        false
+    }
+
+    // Lines of the last listing that couldn't be parsed, for the debug overlay
+    fn skipped_lines(&self) -> usize {
+        0
+    }
+}
+
+// /proc/net/{tcp,tcp6,udp,udp6}, under another directory with --proc-dir
+pub struct ProcSource {
+    dir: PathBuf,
+    skipped: usize,
+}
+
+impl ProcSource {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, skipped: 0 }
+    }
+}
+
+impl ConnectionSource for ProcSource {
+    fn connections(&mut self, protocols: ProtocolSet) -> Result<Vec<Connection>> {
+        let tables = [
+            (protocols.tcp, "tcp", "TCP"),
+            (protocols.tcp6, "tcp6", "TCP6"),
+            (protocols.udp, "udp", "UDP"),
+            (protocols.udp6, "udp6", "UDP6"),
+        ];
+
+        self.skipped = 0;
+        let mut connections = Vec::new();
+        for (selected, file, protocol) in tables {
+            if let Some(content) = read_if_selected(selected, &self.dir.join("net").join(file)) {
+                connections.extend(parse_proc_table(&content, protocol, &mut self.skipped));
+            }
+        }
+        Ok(connections)
+    }
+
+    fn kind(&self) -> Source {
+        Source::Proc
+    }
+
+    fn skipped_lines(&self) -> usize {
+        self.skipped
+    }
+}
+
+// The same sockets as the /proc tables, dumped over sock_diag
+pub struct NetlinkSource;
+
+impl ConnectionSource for NetlinkSource {
+    fn connections(&mut self, protocols: ProtocolSet) -> Result<Vec<Connection>> {
+        let tables = [
+            (protocols.tcp, "TCP", libc::AF_INET, libc::IPPROTO_TCP),
+            (protocols.tcp6, "TCP6", libc::AF_INET6, libc::IPPROTO_TCP),
+            (protocols.udp, "UDP", libc::AF_INET, libc::IPPROTO_UDP),
+            (protocols.udp6, "UDP6", libc::AF_INET6, libc::IPPROTO_UDP),
+        ];
+
+        let mut connections = Vec::new();
+        for (_, protocol, family, ip_protocol) in tables.into_iter().filter(|(selected, ..)| *selected) {
+            let tcp = ip_protocol == libc::IPPROTO_TCP;
+            let sockets = diag::sockets(family, ip_protocol).with_context(|| match tcp {
+                true => format!("sock_diag dump of {} failed", protocol),
+                false => format!("sock_diag dump of {} failed (is the udp_diag module loaded?)", protocol),
+            })?;
+
+            for socket in sockets {
+                // Request sockets are TCP_NEW_SYN_RECV here; /proc calls them SYN_RECV
+                let state = match (tcp, socket.state) {
+                    (false, _) => String::new(),
+                    (true, TCP_NEW_SYN_RECV) => tcp_state_name(TCP_SYN_RECV).to_string(),
+                    (true, code) => tcp_state_name(code).to_string(),
+                };
+                connections.push(Connection {
+                    protocol: protocol.to_string(),
+                    local_address: socket.local.0.to_string(),
+                    local_port: socket.local.1,
+                    remote_address: socket.remote.0.to_string(),
+                    remote_port: socket.remote.1,
+                    uid: Some(socket.uid).filter(|_| state != "TIME_WAIT"),
+                    inode: socket.inode,
+                    timer: TimerKind::from_code(socket.timer),
+                    timer_expires_secs: socket.expires_ms as f64 / 1000.0,
+                    retransmits: socket.retransmits as u32,
+                    // A listener's wqueue is its backlog, which /proc doesn't report
+                    send_queue: if state == "LISTEN" { 0 } else { socket.wqueue },
+                    recv_queue: socket.rqueue,
+                    drops: socket.drops.filter(|_| !tcp).map(u64::from),
+                    state,
+                    ..Default::default()
+                });
+            }
+        }
+
+        Ok(connections)
+    }
+
+    fn kind(&self) -> Source {
+        Source::Netlink
+    }
 }
 
 pub struct NetworkMonitor {
     process_cache: HashMap<u32, String>,
+    source: Box<dyn ConnectionSource>,
+    extra_protocols: bool,
+    unix_sockets: bool,
+    protocols: ProtocolSet,
+    ephemeral_ports: (u16, u16),
+    // False when fd links can't be read at all, e.g. in hardened containers
+    fd_access: bool,
+    io_samples: HashMap<u32, (u64, u64, Instant)>,
+    inode_map: HashMap<u64, u32>,
+    // Every socket inode each PID had open at the last scan. inode_map keeps
+    // one owner per inode, so it can't say whether a second holder of a
+    // shared socket had it before.
+    held_inodes: HashMap<u32, HashSet<u64>>,
+    // Where the net/ socket tables are read from; /proc unless --proc-dir
+    proc_dir: PathBuf,
+    stats: ScanStats,
 }
 
 impl NetworkMonitor {
     pub fn new() -> Self {
         Self {
             process_cache: HashMap::new(),
+            source: Source::default().open(Path::new("/proc")),
+            extra_protocols: false,
+            unix_sockets: false,
+            protocols: ProtocolSet::default(),
+            ephemeral_ports: ephemeral_port_range(),
+            fd_access: fd_links_readable(),
+            io_samples: HashMap::new(),
+            inode_map: HashMap::new(),
+            held_inodes: HashMap::new(),
+            proc_dir: PathBuf::from("/proc"),
+            stats: ScanStats::default(),
         }
     }
 
+    pub fn source(&self) -> Source {
+        self.source.kind()
+    }
+
+    // The fd scan may not run under the new source, so nothing from the old
+    // one's last scan is kept to attribute sockets with
+    pub fn set_source(&mut self, source: Source) {
+        self.source = source.open(&self.proc_dir);
+        self.inode_map.clear();
+        self.held_inodes.clear();
+        self.process_cache.clear();
+    }
+
+    // Takes effect on the next get_connections call
+    pub fn set_protocols(&mut self, protocols: ProtocolSet) {
+        self.protocols = protocols;
+    }
+
+    pub fn protocols(&self) -> ProtocolSet {
+        self.protocols
+    }
+
+    // Inclusive range the kernel picks client ports from
+    pub fn ephemeral_ports(&self) -> (u16, u16) {
+        self.ephemeral_ports
+    }
+
+    pub fn has_fd_access(&self) -> bool {
+        self.fd_access
+    }
+
+    pub fn stats(&self) -> &ScanStats {
+        &self.stats
+    }
+
+    // Only the socket tables under net/ come from `dir`; processes are still
+    // looked up in /proc
+    pub fn set_proc_dir(&mut self, dir: PathBuf) {
+        self.proc_dir = dir;
+        self.source = self.source.kind().open(&self.proc_dir);
+    }
+
+    fn proc_net(&self, name: &str) -> PathBuf {
+        self.proc_dir.join("net").join(name)
+    }
+
+    // SCTP and raw sockets are opt-in: their proc files only exist when the
+    // corresponding kernel support is loaded.
+    pub fn set_extra_protocols(&mut self, enabled: bool) {
+        self.extra_protocols = enabled;
+    }
+
+    // /proc/net/unix is usually far longer than the inet tables, so UNIX
+    // sockets are opt-in too
+    pub fn set_unix_sockets(&mut self, enabled: bool) {
+        self.unix_sockets = enabled;
+    }
+
+    // ss attributes its sockets itself, so the fd scan is only skipped when
+    // there are no UNIX, SCTP or raw sockets to attribute either
+    fn needs_fd_scan(&self) -> bool {
+        !self.source.attributes_owners() || self.unix_sockets || self.extra_protocols
+    }
+
     pub fn get_connections(&mut self) -> Result<Vec<Connection>> {
+        let started = Instant::now();
         let mut connections = Vec::new();
-        
-        // Parse TCP connections
-        connections.extend(self.parse_tcp_connections()?);
-        
-        // Parse UDP connections
-        connections.extend(self.parse_udp_connections()?);
+        self.stats.skipped_lines = 0;
+
+        let attributed = self.source.attributes_owners();
+        if self.needs_fd_scan() {
+            // One pass over /proc/*/fd instead of one per socket
+            self.build_inode_map();
+
+            // Only PIDs that still own sockets stay cached, so the cache can't
+            // grow with every short-lived process seen over a long session
+            let live: HashSet<u32> = self.inode_map.values().copied().collect();
+            self.process_cache.retain(|pid, _| live.contains(pid));
+        }
+
+        connections.extend(self.source.connections(self.protocols)?);
+        self.stats.skipped_lines += self.source.skipped_lines();
+        if !attributed {
+            for conn in &mut connections {
+                (conn.pid, conn.process_name) = self.get_process_info(conn.inode);
+            }
+        }
+
+        // sock_diag is where the netlink source got its uids from already
+        if self.source.kind() != Source::Netlink {
+            fill_owner_uids(&mut connections);
+        }
+
+        if self.extra_protocols {
+            connections.extend(self.parse_sctp_connections()?);
+            connections.extend(self.parse_raw_connections()?);
+        }
+
+        if self.unix_sockets {
+            connections.extend(self.parse_unix_connections());
+        }
+
+        let interfaces = interface_addresses();
+        let mut cgroups: HashMap<u32, Option<String>> = HashMap::new();
+        let mut exes: HashMap<u32, Option<PathBuf>> = HashMap::new();
+        for conn in &mut connections {
+            conn.interface = interface_for(&conn.local_address, &interfaces);
+            if held_by_kernel(conn) {
+                conn.process_name = Some(KERNEL_PROCESS.to_string());
+            }
+            let Some(pid) = conn.pid else {
+                continue;
+            };
+            // ss reports the cgroup itself
+            if conn.cgroup.is_none() {
+                conn.cgroup = cgroups.entry(pid).or_insert_with(|| process_cgroup(pid)).clone();
+            }
+            conn.exe = exes.entry(pid).or_insert_with(|| fs::read_link(format!("/proc/{}/exe", pid)).ok()).clone();
+        }
+
+        self.stats.by_protocol.clear();
+        for conn in &connections {
+            *self.stats.by_protocol.entry(conn.protocol.clone()).or_insert(0) += 1;
+        }
+        self.stats.inode_map_size = self.inode_map.len();
+        self.stats.process_cache_size = self.process_cache.len();
+        self.stats.duration = started.elapsed();
+        debug!(
+            "scanned {} connections in {:?} ({} inodes mapped, {} lines skipped)",
+            connections.len(),
+            self.stats.duration,
+            self.stats.inode_map_size,
+            self.stats.skipped_lines
+        );
         
         Ok(connections)
     }
 
-    fn parse_tcp_connections(&mut self) -> Result<Vec<Connection>> {
+    // Returns `None` on the first sample for a pid and whenever
+    // /proc/<pid>/io is unreadable (other users' processes without root).
+    pub fn process_io_rate(&mut self, pid: u32) -> Option<IoRate> {
+        let content = fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+
+        let mut rchar = None;
+        let mut wchar = None;
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("rchar:") {
+                rchar = value.trim().parse::<u64>().ok();
+            } else if let Some(value) = line.strip_prefix("wchar:") {
+                wchar = value.trim().parse::<u64>().ok();
+            }
+        }
+        let (rchar, wchar) = (rchar?, wchar?);
+
+        let now = Instant::now();
+        let previous = self.io_samples.insert(pid, (rchar, wchar, now));
+        let (prev_r, prev_w, prev_at) = previous?;
+
+        let secs = now.duration_since(prev_at).as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+
+        Some(IoRate {
+            read_per_sec: rchar.saturating_sub(prev_r) as f64 / secs,
+            write_per_sec: wchar.saturating_sub(prev_w) as f64 / secs,
+        })
+    }
+
+    // Drops I/O samples for processes that are no longer being aggregated
+    pub fn retain_io_samples(&mut self, pids: &HashSet<u32>) {
+        self.io_samples.retain(|pid, _| pids.contains(pid));
+    }
+
+    fn parse_sctp_connections(&mut self) -> Result<Vec<Connection>> {
         let mut connections = Vec::new();
-        
-        // Parse IPv4 TCP
-        if let Ok(content) = fs::read_to_string("/proc/net/tcp") {
-            connections.extend(self.parse_proc_net_file(&content, "TCP")?);
+
+        // Listening endpoints
+        if let Some(content) = read_proc_file(&self.proc_net("sctp/eps")) {
+            for line in content.lines().skip(1) {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 9 {
+                    continue;
+                }
+
+                let local_port = fields[5].parse::<u16>().unwrap_or(0);
+                let uid = fields[6].parse::<u32>().ok();
+                let inode = fields[7].parse::<u64>().unwrap_or(0);
+                let (pid, process_name) = self.get_process_info(inode);
+
+                connections.push(Connection {
+                    protocol: "SCTP".to_string(),
+                    local_address: fields[8].trim_start_matches('*').to_string(),
+                    local_port,
+                    remote_address: "0.0.0.0".to_string(),
+                    remote_port: 0,
+                    state: String::new(),
+                    pid,
+                    process_name,
+                    uid,
+                    inode,
+                    ..Default::default()
+                });
+            }
         }
-        
-        // Parse IPv6 TCP
-        if let Ok(content) = fs::read_to_string("/proc/net/tcp6") {
-            connections.extend(self.parse_proc_net_file(&content, "TCP6")?);
+
+        // Associations; addresses are printed in readable form as
+        // "LADDRS <-> RADDRS", the primary path prefixed with '*'
+        if let Some(content) = read_proc_file(&self.proc_net("sctp/assocs")) {
+            for line in content.lines().skip(1) {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let Some(arrow) = fields.iter().position(|f| *f == "<->") else {
+                    continue;
+                };
+                if arrow < 14 || arrow + 1 >= fields.len() {
+                    continue;
+                }
+
+                let uid = fields[9].parse::<u32>().ok();
+                let inode = fields[10].parse::<u64>().unwrap_or(0);
+                let (pid, process_name) = self.get_process_info(inode);
+
+                connections.push(Connection {
+                    protocol: "SCTP".to_string(),
+                    local_address: fields[13].trim_start_matches('*').to_string(),
+                    local_port: fields[11].parse::<u16>().unwrap_or(0),
+                    remote_address: fields[arrow + 1].trim_start_matches('*').to_string(),
+                    remote_port: fields[12].parse::<u16>().unwrap_or(0),
+                    state: self.parse_sctp_state(fields[4]),
+                    pid,
+                    process_name,
+                    uid,
+                    inode,
+                    ..Default::default()
+                });
+            }
         }
-        
+
         Ok(connections)
     }
 
-    fn parse_udp_connections(&mut self) -> Result<Vec<Connection>> {
+    fn parse_raw_connections(&mut self) -> Result<Vec<Connection>> {
         let mut connections = Vec::new();
-        
-        // Parse IPv4 UDP
-        if let Ok(content) = fs::read_to_string("/proc/net/udp") {
-            connections.extend(self.parse_proc_net_file(&content, "UDP")?);
+
+        // Same layout as tcp/udp; the "port" is the IP protocol number
+        if let Some(content) = read_proc_file(&self.proc_net("raw")) {
+            connections.extend(self.parse_proc_net_file(&content, "RAW"));
         }
-        
-        // Parse IPv6 UDP
-        if let Ok(content) = fs::read_to_string("/proc/net/udp6") {
-            connections.extend(self.parse_proc_net_file(&content, "UDP6")?);
+
+        if let Some(content) = read_proc_file(&self.proc_net("raw6")) {
+            connections.extend(self.parse_proc_net_file(&content, "RAW6"));
         }
-        
+
         Ok(connections)
     }
 
-    fn parse_proc_net_file(&mut self, content: &str, protocol: &str) -> Result<Vec<Connection>> {
+    // Columns: Num RefCount Protocol Flags Type St Inode [Path]. Newer
+    // kernels have added nothing before Inode, and the path is optional (and
+    // may contain spaces), so only the first seven fields are required.
+    fn parse_unix_connections(&mut self) -> Vec<Connection> {
         let mut connections = Vec::new();
-        
-        for (i, line) in content.lines().enumerate() {
-            if i == 0 {
-                continue; // Skip header
-            }
-            
+        let Some(content) = read_proc_file(&self.proc_net("unix")) else {
+            return connections;
+        };
+
+        for line in content.lines().skip(1) {
             let fields: Vec<&str> = line.split_whitespace().collect();
-            if fields.len() < 10 {
+            if fields.len() < 7 {
+                self.stats.skipped_lines += 1;
                 continue;
             }
-            
-            let local_addr = self.parse_address(fields[1])?;
-            let remote_addr = self.parse_address(fields[2])?;
-            let state = self.parse_state(fields[3], protocol)?;
-            let _uid = fields[7].parse::<u32>().unwrap_or(0);
-            let inode = fields[9].parse::<u32>().unwrap_or(0);
-            
+
+            let refcount = u32::from_str_radix(fields[1], 16).unwrap_or(0);
+            let accepting = u32::from_str_radix(fields[3], 16).unwrap_or(0) & UNIX_ACCEPTCON != 0;
+            let inode = fields[6].parse::<u64>().unwrap_or(0);
+            let path = escape_control(&fields[7..].join(" "));
+
+            // Unconnected sockets get an empty state so they land in the top
+            // panel with the listeners, like unconnected UDP
+            let state = match (fields[5], accepting) {
+                (_, true) => "LISTEN",
+                ("01", false) => "",
+                ("02", _) => "CONNECTING",
+                ("03", _) => "CONNECTED",
+                ("04", _) => "DISCONNECTING",
+                _ => "UNKNOWN",
+            };
+
+            let flags = if accepting {
+                format!("ACC ref {}", refcount)
+            } else {
+                format!("ref {}", refcount)
+            };
+
             let (pid, process_name) = self.get_process_info(inode);
-            
+
             connections.push(Connection {
-                protocol: protocol.to_string(),
-                local_address: local_addr.0,
-                local_port: local_addr.1,
-                remote_address: remote_addr.0,
-                remote_port: remote_addr.1,
-                state,
+                protocol: "UNIX".to_string(),
+                // Unnamed sockets are told apart by inode, as `ss` does
+                local_address: if path.is_empty() { format!("* {}", inode) } else { path },
+                remote_address: "-".to_string(),
+                state: state.to_string(),
                 pid,
                 process_name,
+                inode,
+                flags: Some(flags),
+                ..Default::default()
             });
         }
-        
-        Ok(connections)
+
+        connections
     }
 
-    fn parse_address(&self, addr_str: &str) -> Result<(String, u16)> {
-        let parts: Vec<&str> = addr_str.split(':').collect();
-        if parts.len() != 2 {
-            return Ok(("0.0.0.0".to_string(), 0));
+    // Raw sockets share the tcp/udp layout
+    fn parse_proc_net_file(&mut self, content: &str, protocol: &str) -> Vec<Connection> {
+        let mut connections = parse_proc_table(content, protocol, &mut self.stats.skipped_lines);
+        for conn in &mut connections {
+            (conn.pid, conn.process_name) = self.get_process_info(conn.inode);
         }
-        
-        let addr_hex = parts[0];
-        let port_hex = parts[1];
-        
-        let port = u16::from_str_radix(port_hex, 16).unwrap_or(0);
-        
-        let addr = if addr_hex.len() == 8 {
-            // IPv4
-            let addr_num = u32::from_str_radix(addr_hex, 16).unwrap_or(0);
-            let ip = Ipv4Addr::from(addr_num.to_le_bytes());
-            ip.to_string()
-        } else if addr_hex.len() == 32 {
-            // IPv6
-            let mut bytes = [0u8; 16];
-            for i in 0..16 {
-                let start = i * 2;
-                let end = start + 2;
-                if end <= addr_hex.len() {
-                    bytes[i] = u8::from_str_radix(&addr_hex[start..end], 16).unwrap_or(0);
-                }
-            }
-            let ip = Ipv6Addr::from(bytes);
-            ip.to_string()
-        } else {
-            "0.0.0.0".to_string()
-        };
-        
-        Ok((addr, port))
+        connections
     }
 
-    fn parse_state(&self, state_hex: &str, protocol: &str) -> Result<String> {
-        if protocol.starts_with("UDP") {
-            return Ok("".to_string()); // UDP is connectionless
-        }
-        
-        let state_num = u8::from_str_radix(state_hex, 16).unwrap_or(0);
-        let state = match state_num {
-            0x01 => "ESTABLISHED",
-            0x02 => "SYN_SENT",
-            0x03 => "SYN_RECV",
-            0x04 => "FIN_WAIT1",
-            0x05 => "FIN_WAIT2",
-            0x06 => "TIME_WAIT",
-            0x07 => "CLOSE",
-            0x08 => "CLOSE_WAIT",
-            0x09 => "LAST_ACK",
-            0x0A => "LISTEN",
-            0x0B => "CLOSING",
-            _ => "UNKNOWN",
+    fn parse_sctp_state(&self, state: &str) -> String {
+        let state = match state.parse::<u8>().unwrap_or(0) {
+            1 => "COOKIE_WAIT",
+            2 => "COOKIE_ECHOED",
+            3 => "ESTABLISHED",
+            4 => "SHUTDOWN_PENDING",
+            5 => "SHUTDOWN_SENT",
+            6 => "SHUTDOWN_RECEIVED",
+            7 => "SHUTDOWN_ACK_SENT",
+            _ => "CLOSED",
         };
-        
-        Ok(state.to_string())
+
+        state.to_string()
     }
 
-    fn get_process_info(&mut self, inode: u32) -> (Option<u32>, Option<String>) {
-        if inode == 0 {
-            return (None, None);
+    // Sockets are only ever read from our own network namespace: /proc/net
+    // is /proc/self/net, and ss and sock_diag see the namespace they run in.
+    // A process in another namespace (a container, `ip netns exec`) holds
+    // sockets that can't be in those tables, so its fds aren't searched at
+    // all, which on a busy container host is most of the walk. The only
+    // sockets this gives up on are ones created here and then handed to, or
+    // kept by, a process that moved namespace; those show as orphans, and
+    // the detail pane says how many processes weren't searched. Processes
+    // whose namespace link can't be read are searched as before.
+    //
+    // Cached process names are kept for PIDs whose sockets were all there at
+    // the last scan. A PID holding a socket it didn't have before loses its
+    // entry, so comm is read again for it: that's when a process that
+    // exec()ed, or a reused PID, would otherwise keep a stale name.
+    fn build_inode_map(&mut self) {
+        let previous = std::mem::take(&mut self.held_inodes);
+        self.inode_map.clear();
+        self.stats.other_netns_processes = 0;
+        self.stats.process_cache_hits = 0;
+        self.stats.process_cache_misses = 0;
+
+        // Every readlink would fail, so don't walk /proc just to find out
+        if !self.fd_access {
+            return;
         }
-        
-        // Try to find process by inode
+
+        let scanned = net_namespace("self");
         if let Ok(entries) = fs::read_dir("/proc") {
             for entry in entries.flatten() {
                 if let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() {
-                    if let Some(process_name) = self.get_process_name_by_inode(pid, inode) {
-                        self.process_cache.insert(pid, process_name.clone());
-                        return (Some(pid), Some(process_name));
+                    let namespace = net_namespace(&pid.to_string());
+                    if scanned.is_some() && namespace.is_some() && namespace != scanned {
+                        self.stats.other_netns_processes += 1;
+                        continue;
                     }
+                    let inodes = self.get_socket_inodes(pid);
+                    self.record_inodes(pid, inodes, &previous);
                 }
             }
         }
+    }
+
+    fn record_inodes(&mut self, pid: u32, inodes: Vec<u64>, previous: &HashMap<u32, HashSet<u64>>) {
+        let held = previous.get(&pid);
+        if inodes.iter().any(|inode| !held.is_some_and(|held| held.contains(inode))) {
+            self.process_cache.remove(&pid);
+        }
+        for &inode in &inodes {
+            self.inode_map.entry(inode).or_insert(pid);
+        }
+        self.held_inodes.insert(pid, inodes.into_iter().collect());
+    }
+
+    fn get_process_info(&mut self, inode: u64) -> (Option<u32>, Option<String>) {
+        if inode == 0 {
+            return (None, None);
+        }
         
-        (None, None)
+        let Some(&pid) = self.inode_map.get(&inode) else {
+            return (None, None);
+        };
+
+        if let Some(name) = self.process_cache.get(&pid) {
+            self.stats.process_cache_hits += 1;
+            return (Some(pid), Some(name.clone()));
+        }
+
+        self.stats.process_cache_misses += 1;
+        let process_name = self.get_process_name(pid);
+        trace!("inode {} -> pid {} ({:?})", inode, pid, process_name);
+        if let Some(name) = &process_name {
+            self.process_cache.insert(pid, name.clone());
+        }
+
+        (Some(pid), process_name)
     }
 
-    fn get_process_name_by_inode(&self, pid: u32, target_inode: u32) -> Option<String> {
+    fn get_socket_inodes(&self, pid: u32) -> Vec<u64> {
+        let mut inodes = Vec::new();
         let fd_dir = format!("/proc/{}/fd", pid);
         if let Ok(entries) = fs::read_dir(&fd_dir) {
             for entry in entries.flatten() {
@@ -193,23 +980,840 @@ impl NetworkMonitor {
                     if let Some(link_str) = link.to_str() {
                         if link_str.starts_with("socket:[") {
                             let inode_str = link_str.trim_start_matches("socket:[").trim_end_matches(']');
-                            if let Ok(inode) = inode_str.parse::<u32>() {
-                                if inode == target_inode {
-                                    return self.get_process_name(pid);
-                                }
+                            if let Ok(inode) = inode_str.parse::<u64>() {
+                                inodes.push(inode);
                             }
                         }
                     }
                 }
             }
         }
-        None
+        inodes
     }
 
     fn get_process_name(&self, pid: u32) -> Option<String> {
         let comm_path = format!("/proc/{}/comm", pid);
-        fs::read_to_string(&comm_path)
+        fs::read(&comm_path)
             .ok()
-            .map(|s| s.trim().to_string())
+            .map(|bytes| escape_control(String::from_utf8_lossy(&bytes).trim_end_matches('\n')))
+    }
+}
+
+// The namespace inode from /proc/<pid>/ns/net, a link to "net:[4026531840]".
+// Reading another user's needs the same access as their fds.
+fn net_namespace(pid: &str) -> Option<u64> {
+    let link = fs::read_link(format!("/proc/{}/ns/net", pid)).ok()?;
+    link.to_str()?.strip_prefix("net:[")?.strip_suffix(']')?.parse().ok()
+}
+
+// Sockets no PID could be found for still have an owner in sock_diag's
+// idiag_uid, so the tables that hold any are dumped once and the uid is
+// taken from there by inode. TIME_WAIT sockets (inode 0) have no owner.
+// A failed dump leaves whatever uid the source reported.
+fn fill_owner_uids(connections: &mut [Connection]) {
+    let tables = [
+        ("TCP", libc::AF_INET, libc::IPPROTO_TCP),
+        ("TCP6", libc::AF_INET6, libc::IPPROTO_TCP),
+        ("UDP", libc::AF_INET, libc::IPPROTO_UDP),
+        ("UDP6", libc::AF_INET6, libc::IPPROTO_UDP),
+    ];
+    let orphaned = |conn: &Connection| conn.pid.is_none() && conn.inode != 0;
+
+    let mut owners = HashMap::new();
+    for (protocol, family, ip_protocol) in tables {
+        if !connections.iter().any(|conn| conn.protocol == protocol && orphaned(conn)) {
+            continue;
+        }
+        match diag::sockets(family, ip_protocol) {
+            Ok(sockets) => owners.extend(sockets.into_iter().map(|socket| ((protocol, socket.inode), socket.uid))),
+            Err(err) => debug!("no sock_diag owners for {}: {}", protocol, err),
+        }
+    }
+    apply_owner_uids(connections, &owners);
+}
+
+fn apply_owner_uids(connections: &mut [Connection], owners: &HashMap<(&str, u64), u32>) {
+    for conn in connections.iter_mut().filter(|conn| conn.pid.is_none() && conn.inode != 0) {
+        if let Some(&uid) = owners.get(&(conn.protocol.as_str(), conn.inode)) {
+            conn.uid = Some(uid);
+        }
+    }
+}
+
+// Process names are set by the process itself (prctl, or argv[0] for comm)
+// and socket paths by whoever binds them, so control characters are shown as
+// escapes ("\n", "\u{1b}") rather than reaching the terminal, where a newline
+// or escape sequence would wreck the table
+pub fn escape_control(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_control() {
+            out.extend(c.escape_default());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Lines are "hierarchy:controllers:path". cgroup v2 has the single line
+// "0::/path"; v1 has one line per hierarchy, which can place a process at
+// different depths, so the deepest path is the most specific. The root "/"
+// says nothing and counts as none.
+fn process_cgroup(pid: u32) -> Option<String> {
+    let content = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    content
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .filter(|path| *path != "/")
+        .max_by_key(|path| path.matches('/').count())
+        .map(str::to_string)
+}
+
+// Short form of a cgroup path for the table: the container ID (12 chars, as
+// `docker ps` shows it) when the last component is a Docker, containerd,
+// CRI-O or Podman scope, otherwise the last component itself
+pub fn cgroup_label(path: &str) -> String {
+    match container_id(path) {
+        Some(id) => id[..12].to_string(),
+        None => path.rsplit('/').next().unwrap_or(path).to_string(),
+    }
+}
+
+// The full 64-digit container ID in a cgroup path, if it names a container
+pub fn container_id(path: &str) -> Option<&str> {
+    let last = path.rsplit('/').next().unwrap_or(path);
+    let name = last.strip_suffix(".scope").unwrap_or(last);
+    let id = ["docker-", "cri-containerd-", "crio-", "libpod-"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name);
+    (id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())).then_some(id)
+}
+
+// The kernel's default when the sysctl can't be read
+const DEFAULT_EPHEMERAL_PORTS: (u16, u16) = (32768, 60999);
+
+// ip_local_port_range lives under ipv4 but governs IPv6 sockets too
+fn ephemeral_port_range() -> (u16, u16) {
+    let parsed = fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range").ok().and_then(|content| {
+        let mut bounds = content.split_whitespace().map(|field| field.parse::<u16>());
+        match (bounds.next(), bounds.next()) {
+            (Some(Ok(low)), Some(Ok(high))) if low <= high => Some((low, high)),
+            _ => None,
+        }
+    });
+
+    parsed.unwrap_or_else(|| {
+        debug!("ip_local_port_range unreadable, assuming {:?}", DEFAULT_EPHEMERAL_PORTS);
+        DEFAULT_EPHEMERAL_PORTS
+    })
+}
+
+// System-wide listen queue counters from the TcpExt section of
+// /proc/net/netstat. ListenOverflows counts handshakes that found the accept
+// queue full; ListenDrops counts every SYN dropped at a listener, overflows
+// included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListenCounters {
+    pub overflows: u64,
+    pub drops: u64,
+}
+
+// The section comes as a pair of lines: names, then values in the same order
+pub fn listen_counters() -> Option<ListenCounters> {
+    let content = read_proc_file(Path::new("/proc/net/netstat"))?;
+    let mut lines = content.lines().filter(|line| line.starts_with("TcpExt:"));
+    let (names, values) = (lines.next()?, lines.next()?);
+
+    let mut counters = ListenCounters::default();
+    for (name, value) in names.split_whitespace().zip(values.split_whitespace()).skip(1) {
+        match name {
+            "ListenOverflows" => counters.overflows = value.parse().ok()?,
+            "ListenDrops" => counters.drops = value.parse().ok()?,
+            _ => {}
+        }
+    }
+    Some(counters)
+}
+
+// Skips the read entirely for protocols the user filtered out
+fn read_if_selected(selected: bool, path: &Path) -> Option<String> {
+    if selected {
+        read_proc_file(path)
+    } else {
+        None
+    }
+}
+
+// Every proc file the monitor may read, for the --version report
+pub const PROC_NET_FILES: &[&str] = &[
+    "/proc/net/tcp",
+    "/proc/net/tcp6",
+    "/proc/net/udp",
+    "/proc/net/udp6",
+    "/proc/net/sctp/eps",
+    "/proc/net/sctp/assocs",
+    "/proc/net/raw",
+    "/proc/net/raw6",
+    "/proc/net/unix",
+];
+
+// "<sysname> <release> <version> <machine>" from uname(2), falling back to
+// /proc/version
+pub fn kernel_version() -> Option<String> {
+    // SAFETY: utsname is plain C data and uname only writes into it
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } == 0 {
+        let field = |chars: &[libc::c_char]| {
+            // SAFETY: uname NUL-terminates every field on success
+            unsafe { CStr::from_ptr(chars.as_ptr()) }.to_string_lossy().into_owned()
+        };
+        return Some(format!(
+            "{} {} {} {}",
+            field(&uts.sysname),
+            field(&uts.release),
+            field(&uts.version),
+            field(&uts.machine)
+        ));
+    }
+
+    fs::read_to_string("/proc/version").ok().map(|version| version.trim().to_string())
+}
+
+// Probes our own fd table once at startup. If even /proc/self/fd can't be
+// listed and resolved, no other process's will be either.
+fn fd_links_readable() -> bool {
+    let readable = fs::read_dir("/proc/self/fd")
+        .ok()
+        .and_then(|mut entries| entries.next())
+        .and_then(|entry| entry.ok())
+        .is_some_and(|entry| fs::read_link(entry.path()).is_ok());
+
+    if !readable {
+        warn!("/proc/self/fd is not readable; process attribution disabled");
+    }
+    readable
+}
+
+// Unprivileged, only our own processes' fds can be read, so most sockets
+// stay unattributed. Probes the first process owned by someone else; true
+// when running as root, when that works (e.g. CAP_SYS_PTRACE) or when there
+// is nobody else's process to probe.
+pub fn other_users_fds_readable() -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = current_uid();
+    if uid == 0 {
+        return true;
+    }
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return true;
+    };
+    let foreign = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit())))
+        .find(|entry| entry.metadata().is_ok_and(|meta| meta.uid() != uid));
+    match foreign {
+        Some(entry) => fs::read_dir(entry.path().join("fd")).is_ok(),
+        None => true,
+    }
+}
+
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+// uid -> login name from /etc/passwd; directory services (LDAP, sssd) are
+// not consulted, so such users show up by number
+pub fn load_usernames() -> HashMap<u32, String> {
+    let mut users = HashMap::new();
+    if let Ok(content) = fs::read_to_string("/etc/passwd") {
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() > 2 {
+                if let Ok(uid) = fields[2].parse::<u32>() {
+                    users.entry(uid).or_insert_with(|| fields[0].to_string());
+                }
+            }
+        }
+    }
+    users
+}
+
+// Attempts per proc read before giving up; reads occasionally fail with
+// EINTR or a transient EACCES while processes churn
+const PROC_READ_ATTEMPTS: u32 = 3;
+const PROC_READ_BACKOFF: Duration = Duration::from_millis(5);
+
+// Missing files are expected (no IPv6, no SCTP module) and aren't retried;
+// anything else is retried briefly and then worth a warning in the log
+fn read_proc_file(path: &Path) -> Option<String> {
+    read_with_retry(path, |path| fs::read_to_string(path))
+}
+
+// The sleeps block the calling thread; the TUI scans inside block_in_place
+// so this never stalls a runtime worker
+fn read_with_retry(path: &Path, mut read: impl FnMut(&Path) -> std::io::Result<String>) -> Option<String> {
+    let mut attempt = 1;
+    loop {
+        match read(path) {
+            Ok(content) => return Some(content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                debug!("{} not present", path.display());
+                return None;
+            }
+            Err(err) if attempt < PROC_READ_ATTEMPTS => {
+                debug!("reading {} failed (attempt {}): {}; retrying", path.display(), attempt, err);
+                std::thread::sleep(PROC_READ_BACKOFF * attempt);
+                attempt += 1;
+            }
+            Err(err) => {
+                warn!("failed to read {} after {} attempts: {}", path.display(), attempt, err);
+                return None;
+            }
+        }
+    }
+}
+
+// Rows of a tcp/udp/raw table, not yet attributed to processes. Lines
+// whose columns can't be located are counted in `skipped`.
+fn parse_proc_table(content: &str, protocol: &str, skipped: &mut usize) -> Vec<Connection> {
+    let mut connections = Vec::new();
+    
+    for (i, line) in content.lines().enumerate() {
+        if i == 0 {
+            continue; // Skip header
+        }
+        
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(columns) = ProcColumns::locate(&fields) else {
+            *skipped += 1;
+            continue;
+        };
+        
+        let local_addr = parse_address(fields[columns.local]);
+        let remote_addr = parse_address(fields[columns.local + 1]);
+        let state = parse_state(fields[columns.local + 2], protocol);
+        let (timer, timer_expires_secs) = parse_timer(fields[columns.timer]);
+        let retransmits = u32::from_str_radix(fields[columns.timer + 1], 16).unwrap_or(0);
+        let (send_queue, recv_queue) = fields[columns.local + 3].split_once(':').unwrap_or(("0", "0"));
+        let drops = if protocol.starts_with("UDP") {
+            fields.get(columns.inode + 3).and_then(|f| f.parse().ok())
+        } else {
+            None
+        };
+        // TIME_WAIT sockets have no owner left; the kernel prints 0
+        let uid = fields[columns.uid].parse::<u32>().ok().filter(|_| state != "TIME_WAIT");
+        let inode = fields[columns.inode].parse::<u64>().unwrap_or(0);
+        
+        connections.push(Connection {
+            protocol: protocol.to_string(),
+            local_address: local_addr.0,
+            local_port: local_addr.1,
+            remote_address: remote_addr.0,
+            remote_port: remote_addr.1,
+            state,
+            uid,
+            inode,
+            timer,
+            timer_expires_secs,
+            retransmits,
+            send_queue: u32::from_str_radix(send_queue, 16).unwrap_or(0),
+            recv_queue: u32::from_str_radix(recv_queue, 16).unwrap_or(0),
+            drops,
+            ..Default::default()
+        });
+    }
+    
+    connections
+}
+
+fn parse_address(addr_str: &str) -> (String, u16) {
+    let parts: Vec<&str> = addr_str.split(':').collect();
+    if parts.len() != 2 {
+        return ("0.0.0.0".to_string(), 0);
+    }
+    
+    let addr_hex = parts[0];
+    let port_hex = parts[1];
+    
+    let port = u16::from_str_radix(port_hex, 16).unwrap_or(0);
+    
+    // The kernel prints each 32-bit word of the network-order address
+    // as a native integer, so the words come back with to_ne_bytes: IPv4
+    // is one word, IPv6 four, each byte-swapped on little-endian hosts
+    let addr = if addr_hex.len() == 8 {
+        // IPv4
+        let addr_num = u32::from_str_radix(addr_hex, 16).unwrap_or(0);
+        let ip = Ipv4Addr::from(addr_num.to_ne_bytes());
+        ip.to_string()
+    } else if addr_hex.len() == 32 {
+        // IPv6
+        let mut bytes = [0u8; 16];
+        for (i, word) in bytes.chunks_exact_mut(4).enumerate() {
+            let value = u32::from_str_radix(&addr_hex[i * 8..i * 8 + 8], 16).unwrap_or(0);
+            word.copy_from_slice(&value.to_ne_bytes());
+        }
+        let ip = Ipv6Addr::from(bytes);
+        ip.to_string()
+    } else {
+        "0.0.0.0".to_string()
+    };
+    
+    (addr, port)
+}
+
+fn parse_state(state_hex: &str, protocol: &str) -> String {
+    if !protocol.starts_with("TCP") {
+        return "".to_string(); // UDP and raw sockets are connectionless
+    }
+    
+    let state_num = u8::from_str_radix(state_hex, 16).unwrap_or(0);
+    tcp_state_name(state_num).to_string()
+}
+
+// Field positions in a /proc/net/{tcp,udp}[6] line. Some kernels pad or
+// split the leading "sl" column differently, which shifts everything after
+// it, so the columns are found by shape instead of by fixed index: the local
+// address is the first "hex:port" field (8 or 32 hex digits), followed by
+// remote, st and tx_queue:rx_queue, then the "tr:tm->when" timer. retrnsmt,
+// uid, timeout and inode come right after the timer; uid and inode must be
+// decimal or the line is rejected rather than misattributed.
+struct ProcColumns {
+    local: usize,
+    timer: usize,
+    uid: usize,
+    inode: usize,
+}
+
+impl ProcColumns {
+    fn locate(fields: &[&str]) -> Option<Self> {
+        let local = fields.iter().position(|field| is_hex_pair(field, &[8, 32], &[4]))?;
+        let timer = local + 4 + fields.get(local + 4..)?.iter().position(|field| is_hex_pair(field, &[2], &[8]))?;
+        let (uid, inode) = (timer + 2, timer + 4);
+
+        let decimal = |index: usize| fields.get(index).is_some_and(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit()));
+        if !decimal(uid) || !decimal(inode) {
+            return None;
+        }
+        Some(Self { local, timer, uid, inode })
+    }
+}
+
+// "left:right" with hex digits, where each side has one of the given lengths
+fn is_hex_pair(field: &str, left_lens: &[usize], right_lens: &[usize]) -> bool {
+    let hex = |part: &str, lens: &[usize]| lens.contains(&part.len()) && part.bytes().all(|b| b.is_ascii_hexdigit());
+    match field.split_once(':') {
+        Some((left, right)) => hex(left, left_lens) && hex(right, right_lens),
+        None => false,
+    }
+}
+
+const TCP_SYN_RECV: u8 = 0x03;
+const TCP_NEW_SYN_RECV: u8 = 0x0C;
+
+// The kernel's TCP state numbers, as /proc prints them in hex
+fn tcp_state_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        TCP_SYN_RECV => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// Splits the "tr:tm->when" field, e.g. "01:00000014" is a retransmit timer
+// firing in 20 ticks (0.2s)
+fn parse_timer(field: &str) -> (TimerKind, f64) {
+    let Some((kind, when)) = field.split_once(':') else {
+        return (TimerKind::None, 0.0);
+    };
+
+    let kind = TimerKind::from_code(u8::from_str_radix(kind, 16).unwrap_or(0));
+    let ticks = u64::from_str_radix(when, 16).unwrap_or(0);
+    (kind, ticks as f64 / USER_HZ)
+}
+
+// Maps every configured local address to the interface that carries it
+fn interface_addresses() -> HashMap<IpAddr, String> {
+    let mut addresses = HashMap::new();
+    let mut ifap: *mut libc::ifaddrs = std::ptr::null_mut();
+
+    // SAFETY: getifaddrs allocates the list we walk and free below; each
+    // ifa_addr is checked for null and read according to its sa_family.
+    unsafe {
+        if libc::getifaddrs(&mut ifap) != 0 {
+            return addresses;
+        }
+
+        let mut cursor = ifap;
+        while !cursor.is_null() {
+            let ifa = &*cursor;
+            cursor = ifa.ifa_next;
+
+            if ifa.ifa_addr.is_null() || ifa.ifa_name.is_null() {
+                continue;
+            }
+
+            let ip = match (*ifa.ifa_addr).sa_family as i32 {
+                libc::AF_INET => {
+                    let sa = &*(ifa.ifa_addr as *const libc::sockaddr_in);
+                    IpAddr::V4(Ipv4Addr::from(sa.sin_addr.s_addr.to_ne_bytes()))
+                }
+                libc::AF_INET6 => {
+                    let sa = &*(ifa.ifa_addr as *const libc::sockaddr_in6);
+                    IpAddr::V6(Ipv6Addr::from(sa.sin6_addr.s6_addr))
+                }
+                _ => continue,
+            };
+
+            let name = CStr::from_ptr(ifa.ifa_name).to_string_lossy().into_owned();
+            addresses.entry(ip).or_insert(name);
+        }
+
+        libc::freeifaddrs(ifap);
+    }
+
+    addresses
+}
+
+fn interface_for(address: &str, interfaces: &HashMap<IpAddr, String>) -> Option<String> {
+    let ip = address.parse::<IpAddr>().ok()?;
+    if ip.is_unspecified() {
+        return Some("*".to_string());
+    }
+
+    // IPv4-mapped addresses on dual-stack sockets belong to the v4 interface
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    };
+
+    interfaces.get(&ip).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP_HEADER: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode";
+
+    fn parse(content: &str, protocol: &str) -> Vec<Connection> {
+        NetworkMonitor::new().parse_proc_net_file(content, protocol)
+    }
+
+    #[test]
+    fn parses_an_active_retransmit_timer() {
+        let content = format!(
+            "{}\n{}\n",
+            TCP_HEADER,
+            "   0: 0100007F:1F90 0A000009:01BB 01 00000010:00000000 01:00000037 00000002  1000        0 123456 1 0000000000000000 20 4 30 10 -1"
+        );
+        let connections = parse(&content, "TCP");
+        assert_eq!(connections.len(), 1);
+
+        let conn = &connections[0];
+        assert_eq!(conn.timer, TimerKind::Retransmit);
+        assert!((conn.timer_expires_secs - 0.55).abs() < 1e-9);
+        assert_eq!(conn.retransmits, 2);
+        assert_eq!(conn.send_queue, 16);
+        assert_eq!(conn.state, "ESTABLISHED");
+    }
+
+    #[test]
+    fn unix_clients_of_one_path_have_distinct_keys() {
+        let client = |inode| Connection {
+            protocol: "UNIX".to_string(),
+            local_address: "/run/docker.sock".to_string(),
+            remote_address: "-".to_string(),
+            state: "CONNECTED".to_string(),
+            inode,
+            ..Default::default()
+        };
+        assert_ne!(client(1001).key(), client(1002).key());
+        assert_eq!(client(1001).key(), client(1001).key());
+
+        // TCP keys stay on the endpoints, so a socket keeps its identity when
+        // it drops into TIME_WAIT and loses its inode
+        let tcp = |inode| Connection {
+            protocol: "TCP".to_string(),
+            inode,
+            ..Default::default()
+        };
+        assert_eq!(tcp(1001).key(), tcp(0).key());
+    }
+
+    #[test]
+    fn inodes_above_u32_keep_their_owner() {
+        let big: u64 = u32::MAX as u64 + 6;
+        let mut monitor = NetworkMonitor::new();
+        monitor.inode_map.insert(big, 4242);
+        monitor.inode_map.insert(5, 1);
+        monitor.process_cache.insert(4242, "worker".to_string());
+
+        let content = format!(
+            "{}\n   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 {} 1 0000000000000000 100 0 0 10 0\n",
+            TCP_HEADER, big
+        );
+        let connections = monitor.parse_proc_net_file(&content, "TCP");
+        assert_eq!(connections[0].inode, 4294967301);
+        assert_eq!(connections[0].pid, Some(4242));
+        assert_eq!(connections[0].process_name.as_deref(), Some("worker"));
+    }
+
+    #[test]
+    fn transient_read_errors_are_retried() {
+        let mut failures = 2;
+        let content = read_with_retry(Path::new("/proc/net/tcp"), |_| {
+            if failures > 0 {
+                failures -= 1;
+                return Err(std::io::Error::from(std::io::ErrorKind::Interrupted));
+            }
+            Ok("header\n".to_string())
+        });
+        assert_eq!(content.as_deref(), Some("header\n"));
+
+        let mut attempts = 0;
+        let content = read_with_retry(Path::new("/proc/net/tcp"), |_| {
+            attempts += 1;
+            Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        });
+        assert_eq!(content, None);
+        assert_eq!(attempts, PROC_READ_ATTEMPTS);
+
+        // A missing table is final straight away
+        let mut attempts = 0;
+        let content = read_with_retry(Path::new("/proc/net/sctp/eps"), |_| {
+            attempts += 1;
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+        });
+        assert_eq!(content, None);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn unreadable_table_under_proc_dir_leaves_the_others() {
+        let dir = std::env::temp_dir().join(format!("netmon-tui-proc-{}", std::process::id()));
+        fs::create_dir_all(dir.join("net")).unwrap();
+        fs::write(
+            dir.join("net/tcp"),
+            format!(
+                "{}\n   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1 1 0000000000000000 100 0 0 10 0\n",
+                TCP_HEADER
+            ),
+        )
+        .unwrap();
+        // Reading a directory fails with EISDIR on every attempt
+        fs::create_dir_all(dir.join("net/tcp6")).unwrap();
+
+        let connections = ProcSource::new(dir.clone())
+            .connections(ProtocolSet { udp: false, udp6: false, ..Default::default() })
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].local_port, 8080);
+        assert_eq!(connections[0].state, "LISTEN");
+    }
+
+    const TCP6_HEADER: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode";
+
+    // ::1 port 631 talking to 2001:db8::1 port 443, as a little-endian
+    // kernel prints it
+    const TCP6_ROW: &str = "   3: 00000000000000000000000001000000:0277 B80D0120000000000000000001000000:01BB 01 00000000:00000002 02:000003E8 00000000  1000        0 98765 1 0000000000000000 20 4 30 10 -1";
+
+    #[test]
+    fn locates_tcp6_columns() {
+        let fields: Vec<&str> = TCP6_ROW.split_whitespace().collect();
+        let columns = ProcColumns::locate(&fields).unwrap();
+        assert_eq!(fields[columns.local], "00000000000000000000000001000000:0277");
+        assert_eq!(fields[columns.timer], "02:000003E8");
+        assert_eq!(fields[columns.uid], "1000");
+        assert_eq!(fields[columns.inode], "98765");
+
+        assert!(ProcColumns::locate(&TCP6_HEADER.split_whitespace().collect::<Vec<_>>()).is_none());
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_a_tcp6_row() {
+        let connections = parse(&format!("{}\n{}\n", TCP6_HEADER, TCP6_ROW), "TCP6");
+        assert_eq!(connections.len(), 1);
+
+        let conn = &connections[0];
+        assert_eq!(conn.protocol, "TCP6");
+        assert_eq!(conn.local_address, "::1");
+        assert_eq!(conn.local_port, 631);
+        assert_eq!(conn.remote_address, "2001:db8::1");
+        assert_eq!(conn.remote_port, 443);
+        assert_eq!(conn.state, "ESTABLISHED");
+        assert_eq!(conn.recv_queue, 2);
+        assert_eq!(conn.timer, TimerKind::Keepalive);
+        assert_eq!(conn.uid, Some(1000));
+        assert_eq!(conn.inode, 98765);
+    }
+
+    // Each 32-bit word is byte-swapped on little-endian hosts
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn decodes_proc_hex_addresses() {
+        let decode = parse_address;
+        assert_eq!(decode("0100007F:0016"), ("127.0.0.1".to_string(), 22));
+        assert_eq!(decode("B80D0120000000000000000001000000:01BB"), ("2001:db8::1".to_string(), 443));
+        assert_eq!(decode("B80D012078563412EFBEADDE0D0CDEC0:0050"), ("2001:db8:1234:5678:dead:beef:c0de:c0d".to_string(), 80));
+        assert_eq!(decode("0000000000000000FFFF00000900000A:0035"), ("::ffff:10.0.0.9".to_string(), 53));
+        assert_eq!(decode("00000000000000000000000000000000:0000"), ("::".to_string(), 0));
+    }
+
+    #[test]
+    fn control_characters_are_escaped() {
+        assert_eq!(escape_control("evil\nname"), "evil\\nname");
+        assert_eq!(escape_control("x\n\u{1b}[2Jy"), "x\\n\\u{1b}[2Jy");
+        assert_eq!(escape_control("\u{1b}[31mred"), "\\u{1b}[31mred");
+        assert_eq!(escape_control("tab\there"), "tab\\there");
+        assert_eq!(escape_control("nginx: wörker"), "nginx: wörker");
+    }
+
+    #[test]
+    fn formats_endpoints() {
+        assert_eq!(endpoint("::", 80), "[::]:80");
+        assert_eq!(endpoint("::1", 631), "[::1]:631");
+        assert_eq!(endpoint("::ffff:10.0.0.9", 53), "[::ffff:10.0.0.9]:53");
+        assert_eq!(endpoint("10.0.0.9", 53), "10.0.0.9:53");
+
+        let conn = |local: &str, local_port, remote: &str, remote_port| Connection {
+            protocol: "TCP6".to_string(),
+            local_address: local.to_string(),
+            local_port,
+            remote_address: remote.to_string(),
+            remote_port,
+            ..Default::default()
+        };
+
+        let wildcard = conn("::", 443, "::", 0);
+        assert_eq!(wildcard.local_display(), "[::]:443");
+        assert_eq!(wildcard.local_bind_display(), "[::]:443");
+        assert_eq!(wildcard.remote_display(), "*:*");
+
+        let loopback = conn("::1", 631, "::1", 40000);
+        assert_eq!(loopback.local_display(), "[::1]:631");
+        assert_eq!(loopback.remote_display(), "[::1]:40000");
+
+        let mapped = conn("::ffff:10.0.0.9", 53, "::ffff:10.0.0.1", 5353);
+        assert_eq!(mapped.local_bind_display(), "[::ffff:10.0.0.9]:53");
+        assert_eq!(mapped.remote_display(), "[::ffff:10.0.0.1]:5353");
+
+        let v4 = conn("0.0.0.0", 22, "0.0.0.0", 0);
+        assert_eq!(v4.local_display(), "0.0.0.0:22");
+        assert_eq!(v4.local_bind_display(), "*:22");
+        assert_eq!(v4.remote_display(), "*:*");
+    }
+
+    #[test]
+    fn owner_uids_fill_sockets_without_a_pid() {
+        let socket = |protocol: &str, pid, inode| Connection {
+            protocol: protocol.to_string(),
+            pid,
+            inode,
+            ..Default::default()
+        };
+        let mut connections = vec![
+            socket("TCP", None, 100),
+            socket("TCP", Some(1), 101),
+            socket("TCP6", None, 100),
+            socket("TCP", None, 0),
+        ];
+        let owners: HashMap<(&str, u64), u32> = [(("TCP", 100), 70), (("TCP", 101), 1000), (("TCP", 0), 5)].into_iter().collect();
+        apply_owner_uids(&mut connections, &owners);
+
+        assert_eq!(connections[0].uid, Some(70));
+        // Attributed sockets keep their own uid, and inodes are per table
+        assert_eq!(connections[1].uid, None);
+        assert_eq!(connections[2].uid, None);
+        assert_eq!(connections[3].uid, None);
+    }
+
+    #[test]
+    fn shared_sockets_keep_every_holders_name() {
+        let mut monitor = NetworkMonitor::new();
+        // A pre-fork server: the parent and a worker share the listener
+        monitor.record_inodes(10, vec![7], &HashMap::new());
+        monitor.record_inodes(20, vec![7], &HashMap::new());
+        monitor.process_cache.insert(10, "nginx".to_string());
+        monitor.process_cache.insert(20, "nginx".to_string());
+
+        let previous = std::mem::take(&mut monitor.held_inodes);
+        monitor.inode_map.clear();
+        monitor.record_inodes(10, vec![7, 8], &previous);
+        monitor.record_inodes(20, vec![7], &previous);
+        assert_eq!(monitor.inode_map.get(&7), Some(&10));
+        // The worker isn't inode 7's recorded owner, but it held it before
+        assert!(monitor.process_cache.contains_key(&20));
+        // Only a socket the PID didn't hold before drops its cached name
+        assert!(!monitor.process_cache.contains_key(&10));
+    }
+
+    #[test]
+    fn fd_scan_runs_for_sockets_ss_leaves_unattributed() {
+        let mut monitor = NetworkMonitor::new();
+        assert!(monitor.needs_fd_scan());
+
+        monitor.set_source(Source::Ss);
+        assert!(!monitor.needs_fd_scan());
+        monitor.set_unix_sockets(true);
+        assert!(monitor.needs_fd_scan());
+        monitor.set_unix_sockets(false);
+        monitor.set_extra_protocols(true);
+        assert!(monitor.needs_fd_scan());
+    }
+
+    #[test]
+    fn switching_sources_forgets_the_last_scan() {
+        let mut monitor = NetworkMonitor::new();
+        monitor.record_inodes(10, vec![7], &HashMap::new());
+        monitor.process_cache.insert(10, "nginx".to_string());
+
+        monitor.set_source(Source::Ss);
+        assert_eq!(monitor.get_process_info(7), (None, None));
+        assert!(monitor.held_inodes.is_empty());
+        assert!(monitor.process_cache.is_empty());
+    }
+
+    #[test]
+    fn only_fileless_sockets_are_tagged_as_kernel() {
+        let socket = |protocol: &str, state: &str, inode, pid| Connection {
+            protocol: protocol.to_string(),
+            state: state.to_string(),
+            inode,
+            pid,
+            ..Default::default()
+        };
+
+        assert!(held_by_kernel(&socket("TCP", "LISTEN", 0, None)));
+        assert!(held_by_kernel(&socket("UDP", "", 0, None)));
+        assert!(!held_by_kernel(&socket("TCP", "LISTEN", 4242, None)));
+        assert!(!held_by_kernel(&socket("TCP", "LISTEN", 0, Some(1))));
+        // Mini-sockets of connections being set up or torn down
+        assert!(!held_by_kernel(&socket("TCP", "TIME_WAIT", 0, None)));
+        assert!(!held_by_kernel(&socket("TCP6", "SYN_RECV", 0, None)));
+        assert!(!held_by_kernel(&socket("UNIX", "", 0, None)));
+    }
+
+    #[test]
+    fn timer_fields() {
+        assert_eq!(parse_timer("00:00000000"), (TimerKind::None, 0.0));
+        assert_eq!(parse_timer("02:000AFC80"), (TimerKind::Keepalive, 7200.0));
+        assert_eq!(parse_timer("03:00000BB8"), (TimerKind::TimeWait, 30.0));
+        assert_eq!(parse_timer("garbage"), (TimerKind::None, 0.0));
     }
 }
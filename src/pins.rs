@@ -0,0 +1,137 @@
+use crate::network::ConnectionKey;
+use log::{info, warn};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Pins kept between runs with `persist_pins`, one key per line:
+// protocol, local address, local port, remote address, remote port, host
+// ("-" for this machine) and inode (0 unless UNIX), tab-separated. Addresses
+// never contain tabs since control characters are escaped when read.
+pub fn state_file() -> Option<PathBuf> {
+    state_file_in(std::env::var_os("XDG_STATE_HOME"), std::env::var_os("HOME"))
+}
+
+fn state_file_in(xdg_state_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    let base = match xdg_state_home {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(home?).join(".local").join("state"),
+    };
+    Some(base.join("netmon-tui").join("pins"))
+}
+
+// A missing file is an empty set; unreadable lines are skipped
+pub fn load(path: &Path) -> HashSet<ConnectionKey> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return HashSet::new(),
+        Err(err) => {
+            warn!("cannot read pins from {}: {}", path.display(), err);
+            return HashSet::new();
+        }
+    };
+
+    let pins: HashSet<ConnectionKey> = content.lines().filter_map(parse_line).collect();
+    info!("loaded {} pins", pins.len());
+    pins
+}
+
+pub fn save(path: &Path, pins: &HashSet<ConnectionKey>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut content = String::new();
+    for key in pins {
+        content.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            key.protocol,
+            key.local_address,
+            key.local_port,
+            key.remote_address,
+            key.remote_port,
+            key.host.as_deref().unwrap_or("-"),
+            key.inode
+        ));
+    }
+    fs::write(path, content)
+}
+
+fn parse_line(line: &str) -> Option<ConnectionKey> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [protocol, local_address, local_port, remote_address, remote_port, host, inode] = fields[..] else {
+        return None;
+    };
+
+    Some(ConnectionKey {
+        protocol: protocol.to_string(),
+        local_address: local_address.to_string(),
+        local_port: local_port.parse().ok()?,
+        remote_address: remote_address.to_string(),
+        remote_port: remote_port.parse().ok()?,
+        host: (host != "-").then(|| host.to_string()),
+        inode: inode.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Connection;
+
+    #[test]
+    fn state_file_prefers_xdg_state_home() {
+        assert_eq!(
+            state_file_in(Some("/xdg".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/xdg/netmon-tui/pins"))
+        );
+        assert_eq!(
+            state_file_in(None, Some("/home/me".into())),
+            Some(PathBuf::from("/home/me/.local/state/netmon-tui/pins"))
+        );
+        assert_eq!(state_file_in(None, None), None);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let listener = Connection {
+            protocol: "TCP6".to_string(),
+            local_address: "::".to_string(),
+            local_port: 443,
+            remote_address: "::".to_string(),
+            ..Default::default()
+        };
+        let remote = Connection {
+            protocol: "UDP".to_string(),
+            local_address: "0.0.0.0".to_string(),
+            local_port: 53,
+            host: Some("web1".to_string()),
+            ..Default::default()
+        };
+        let unix = Connection {
+            protocol: "UNIX".to_string(),
+            local_address: "/run/my app.sock".to_string(),
+            inode: 1 << 33,
+            ..Default::default()
+        };
+        let pins: HashSet<ConnectionKey> = [listener.key(), remote.key(), unix.key()].into_iter().collect();
+
+        let path = std::env::temp_dir().join(format!("netmon-tui-pins-{}", std::process::id())).join("pins");
+        save(&path, &pins).unwrap();
+        let loaded = load(&path);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, pins);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        assert!(parse_line("TCP\t0.0.0.0\t22\t0.0.0.0\t0\t-\t0").is_some());
+        assert!(parse_line("TCP\t0.0.0.0\t22\t0.0.0.0\t0\t-").is_none());
+        assert!(parse_line("TCP\t0.0.0.0\tssh\t0.0.0.0\t0\t-\t0").is_none());
+        assert!(parse_line("").is_none());
+        assert!(load(Path::new("/nonexistent/netmon-tui/pins")).is_empty());
+    }
+}
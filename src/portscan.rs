@@ -0,0 +1,201 @@
+use crate::network::Connection;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// Thresholds from the config; 0 turns a check off
+#[derive(Debug, Clone, Copy)]
+pub struct ScanThresholds {
+    pub window: Duration,
+    // Distinct ports on a single remote host
+    pub ports: usize,
+    // Distinct remote hosts
+    pub hosts: usize,
+}
+
+impl Default for ScanThresholds {
+    fn default() -> Self {
+        Self { window: Duration::from_secs(10), ports: 20, hosts: 50 }
+    }
+}
+
+// Why a process looks like it's scanning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Suspicion {
+    // (remote host, distinct ports)
+    Ports(String, usize),
+    Hosts(usize),
+}
+
+impl Suspicion {
+    pub fn describe(&self, window: Duration) -> String {
+        match self {
+            Suspicion::Ports(host, ports) => {
+                format!("{} ports on {} in the last {}s", ports, host, window.as_secs())
+            }
+            Suspicion::Hosts(hosts) => format!("{} remote hosts in the last {}s", hosts, window.as_secs()),
+        }
+    }
+}
+
+// Remembers which remote endpoints each local process had a connection to at
+// each refresh, over a sliding window. This only sees what was open when a
+// scan was taken, so it's a heuristic: a fast scanner can finish between two
+// refreshes, and a busy client (a crawler, a monitoring agent) can trip the
+// hosts check legitimately.
+#[derive(Default)]
+pub struct ScanDetector {
+    thresholds: ScanThresholds,
+    // When each (remote address, remote port) was last seen, per PID
+    seen: HashMap<u32, HashMap<(String, u16), Instant>>,
+    suspects: HashMap<u32, Suspicion>,
+}
+
+impl ScanDetector {
+    pub fn new(thresholds: ScanThresholds) -> Self {
+        Self { thresholds, ..Default::default() }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.thresholds.window
+    }
+
+    // Records a refresh and returns the PIDs that crossed a threshold with
+    // it, so the caller can alert once per process rather than every refresh
+    pub fn update(&mut self, connections: &[Connection]) -> Vec<u32> {
+        let now = Instant::now();
+        // Connections accepted on a local listener come from many client
+        // ports by nature, so only ones this host opened count
+        let listening: HashSet<(&str, u16)> = connections
+            .iter()
+            .filter(|conn| conn.host.is_none() && conn.is_listening())
+            .map(|conn| (base_protocol(conn), conn.local_port))
+            .collect();
+        // --ssh rows are left out: their PIDs belong to other machines
+        let outgoing = connections.iter().filter(|conn| {
+            conn.host.is_none()
+                && !conn.is_listening()
+                && conn.remote_port > 0
+                && !listening.contains(&(base_protocol(conn), conn.local_port))
+        });
+        for conn in outgoing {
+            let Some(pid) = conn.pid else {
+                continue;
+            };
+            self.seen.entry(pid).or_default().insert((conn.remote_address.clone(), conn.remote_port), now);
+        }
+
+        let window = self.thresholds.window;
+        for endpoints in self.seen.values_mut() {
+            endpoints.retain(|_, seen| now.duration_since(*seen) <= window);
+        }
+        self.seen.retain(|_, endpoints| !endpoints.is_empty());
+
+        let previous: HashSet<u32> = self.suspects.keys().copied().collect();
+        self.suspects = self
+            .seen
+            .iter()
+            .filter_map(|(pid, endpoints)| Some((*pid, self.suspicion(endpoints)?)))
+            .collect();
+        self.suspects.keys().filter(|pid| !previous.contains(pid)).copied().collect()
+    }
+
+    fn suspicion(&self, endpoints: &HashMap<(String, u16), Instant>) -> Option<Suspicion> {
+        let mut ports_by_host: HashMap<&str, usize> = HashMap::new();
+        for (address, _) in endpoints.keys() {
+            *ports_by_host.entry(address.as_str()).or_default() += 1;
+        }
+
+        if self.thresholds.ports > 0 {
+            let busiest = ports_by_host.iter().max_by_key(|(host, ports)| (**ports, std::cmp::Reverse(**host)));
+            if let Some((host, &ports)) = busiest.filter(|(_, ports)| **ports >= self.thresholds.ports) {
+                return Some(Suspicion::Ports(host.to_string(), ports));
+            }
+        }
+        if self.thresholds.hosts > 0 && ports_by_host.len() >= self.thresholds.hosts {
+            return Some(Suspicion::Hosts(ports_by_host.len()));
+        }
+        None
+    }
+
+    pub fn suspicion_for(&self, pid: u32) -> Option<&Suspicion> {
+        self.suspects.get(&pid)
+    }
+}
+
+// "TCP" for TCP6 too, as a v6 listener also accepts IPv4 clients
+fn base_protocol(conn: &Connection) -> &str {
+    conn.protocol.trim_end_matches('6')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outgoing(pid: u32, remote: &str, remote_port: u16) -> Connection {
+        Connection {
+            protocol: "TCP".to_string(),
+            local_address: "10.0.0.5".to_string(),
+            local_port: 40000 + remote_port % 1000,
+            remote_address: remote.to_string(),
+            remote_port,
+            state: "SYN_SENT".to_string(),
+            pid: Some(pid),
+            ..Default::default()
+        }
+    }
+
+    fn detector() -> ScanDetector {
+        ScanDetector::new(ScanThresholds { window: Duration::from_secs(60), ports: 5, hosts: 3 })
+    }
+
+    #[test]
+    fn many_ports_on_one_host() {
+        let mut scans = detector();
+        let probes: Vec<Connection> = (20..25).map(|port| outgoing(7, "10.0.0.9", port)).collect();
+        assert!(scans.update(&probes[..4]).is_empty());
+        assert_eq!(scans.update(&probes), vec![7]);
+        assert_eq!(scans.suspicion_for(7), Some(&Suspicion::Ports("10.0.0.9".to_string(), 5)));
+
+        // Already flagged, so it isn't reported again
+        assert!(scans.update(&probes).is_empty());
+        assert!(scans.suspicion_for(7).is_some());
+    }
+
+    #[test]
+    fn many_hosts() {
+        let mut scans = detector();
+        let probes = [outgoing(8, "10.0.0.1", 22), outgoing(8, "10.0.0.2", 22), outgoing(8, "10.0.0.3", 22)];
+        assert_eq!(scans.update(&probes), vec![8]);
+        assert_eq!(scans.suspicion_for(8), Some(&Suspicion::Hosts(3)));
+    }
+
+    #[test]
+    fn accepted_and_remote_connections_are_not_counted() {
+        let mut scans = detector();
+        let listener = Connection {
+            protocol: "TCP6".to_string(),
+            local_port: 443,
+            state: "LISTEN".to_string(),
+            pid: Some(9),
+            ..Default::default()
+        };
+        let mut connections = vec![listener];
+        for port in 50000..50010 {
+            connections.push(Connection { local_port: 443, ..outgoing(9, "10.0.0.9", port) });
+        }
+        for port in 20..30 {
+            connections.push(Connection { host: Some("web1".to_string()), ..outgoing(10, "10.0.0.9", port) });
+        }
+
+        assert!(scans.update(&connections).is_empty());
+        assert!(scans.suspicion_for(9).is_none());
+        assert!(scans.suspicion_for(10).is_none());
+    }
+
+    #[test]
+    fn zero_turns_a_check_off() {
+        let mut scans = ScanDetector::new(ScanThresholds { window: Duration::from_secs(60), ports: 0, hosts: 0 });
+        let probes: Vec<Connection> = (1..100).map(|port| outgoing(7, "10.0.0.9", port)).collect();
+        assert!(scans.update(&probes).is_empty());
+    }
+}
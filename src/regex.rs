@@ -0,0 +1,385 @@
+// A small backtracking regex engine for the '~' filter mode, since the
+// filter only ever matches one short line per connection. Supports literals,
+// '.', classes ("[a-z]", "[^0-9]", "\d", "\w", "\s"), groups, '|', '^', '$'
+// and the '*', '+', '?' and "{n,m}" quantifiers. Matching ignores case.
+pub struct Regex {
+    alternatives: Vec<Vec<Node>>,
+}
+
+enum Node {
+    Char(char),
+    Any,
+    Class { ranges: Vec<(char, char)>, negated: bool },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize> },
+}
+
+// Pathological patterns like "(a*)*b" are exponential under backtracking;
+// past this many steps a line simply doesn't match
+const STEP_LIMIT: usize = 100_000;
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let mut parser = Parser { chars: pattern.chars().collect(), pos: 0 };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err("unmatched ')'".to_string());
+        }
+        Ok(Self { alternatives })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+        let mut matcher = Matcher { text: &text, steps: 0 };
+        (0..=text.len()).any(|start| {
+            self.alternatives
+                .iter()
+                .any(|seq| matcher.sequence(seq, start, &mut |_, _| true))
+        })
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        self.pos += found as usize;
+        found
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.next().expect("caller checked for a char");
+        Ok(match c {
+            '(' => {
+                // Non-capturing groups behave the same here
+                if self.peek() == Some('?') && self.chars.get(self.pos + 1) == Some(&':') {
+                    self.pos += 2;
+                }
+                let alternatives = self.alternatives()?;
+                if !self.eat(')') {
+                    return Err("missing ')'".to_string());
+                }
+                Node::Group(alternatives)
+            }
+            '[' => self.class()?,
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '*' | '+' | '?' | '{' => return Err(format!("nothing to repeat before '{}'", c)),
+            '\\' => match self.next() {
+                Some(escaped) => match perl_class(escaped) {
+                    Some((ranges, negated)) => Node::Class { ranges, negated },
+                    None => Node::Char(lowercase(escaped)),
+                },
+                None => return Err("trailing '\\'".to_string()),
+            },
+            c => Node::Char(lowercase(c)),
+        })
+    }
+
+    fn quantified(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                self.bounds()?
+            }
+            _ => return Ok(node),
+        };
+        // The quantifier itself, or the '}' that bounds() stopped on
+        self.pos += 1;
+        if matches!(self.peek(), Some('*' | '+' | '?' | '{')) {
+            return Err("nested quantifier".to_string());
+        }
+        Ok(Node::Repeat { node: Box::new(node), min, max })
+    }
+
+    // "{n}", "{n,}" or "{n,m}", after the '{', leaving pos on the '}'
+    fn bounds(&mut self) -> Result<(usize, Option<usize>), String> {
+        let close = self.chars[self.pos..]
+            .iter()
+            .position(|&c| c == '}')
+            .ok_or_else(|| "missing '}'".to_string())?;
+        let body: String = self.chars[self.pos..self.pos + close].iter().collect();
+        self.pos += close;
+
+        let invalid = || format!("invalid repetition '{{{}}}'", body);
+        let number = |text: &str| text.trim().parse::<usize>().map_err(|_| invalid());
+        let (min, max) = match body.split_once(',') {
+            None => {
+                let n = number(&body)?;
+                (n, Some(n))
+            }
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(invalid());
+        }
+        Ok((min, max))
+    }
+
+    // After the '['
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.next().ok_or_else(|| "missing ']'".to_string())?;
+            // A ']' right after the '[' or '[^' is a literal
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let low = if c == '\\' {
+                let escaped = self.next().ok_or_else(|| "missing ']'".to_string())?;
+                match perl_class(escaped) {
+                    Some((_, true)) => return Err(format!("'\\{}' is not supported inside [...]", escaped)),
+                    Some((class, false)) => {
+                        ranges.extend(class);
+                        continue;
+                    }
+                    None => escaped,
+                }
+            } else {
+                c
+            };
+
+            let is_range = self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']');
+            if is_range {
+                self.pos += 1;
+                let high = match self.next() {
+                    Some('\\') => self.next().ok_or_else(|| "missing ']'".to_string())?,
+                    Some(high) => high,
+                    None => return Err("missing ']'".to_string()),
+                };
+                if high < low {
+                    return Err(format!("invalid range '{}-{}'", low, high));
+                }
+                ranges.push((low, high));
+            } else {
+                ranges.push((low, low));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+}
+
+fn perl_class(c: char) -> Option<(Vec<(char, char)>, bool)> {
+    let ranges = match c.to_ascii_lowercase() {
+        'd' => vec![('0', '9')],
+        'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        's' => vec![(' ', ' '), ('\t', '\r')],
+        _ => return None,
+    };
+    Some((ranges, c.is_ascii_uppercase()))
+}
+
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+struct Matcher<'a> {
+    // Already lowercased
+    text: &'a [char],
+    steps: usize,
+}
+
+impl Matcher<'_> {
+    // False once the budget is spent, failing every path still open
+    fn step(&mut self) -> bool {
+        self.steps += 1;
+        self.steps <= STEP_LIMIT
+    }
+
+    // Matches `nodes` at `pos`, calling `rest` with each end position until
+    // it accepts
+    fn sequence(&mut self, nodes: &[Node], pos: usize, rest: &mut dyn FnMut(&mut Self, usize) -> bool) -> bool {
+        let Some((node, tail)) = nodes.split_first() else {
+            return rest(self, pos);
+        };
+        if !self.step() {
+            return false;
+        }
+        match node {
+            Node::Repeat { node, min, max } => self.repeat(node, *min, *max, 0, pos, tail, rest),
+            _ => self.single(node, pos, &mut |m, end| m.sequence(tail, end, rest)),
+        }
+    }
+
+    fn single(&mut self, node: &Node, pos: usize, rest: &mut dyn FnMut(&mut Self, usize) -> bool) -> bool {
+        let current = self.text.get(pos).copied();
+        match node {
+            Node::Char(c) => current == Some(*c) && rest(self, pos + 1),
+            Node::Any => current.is_some() && rest(self, pos + 1),
+            Node::Class { ranges, negated } => match current {
+                Some(c) => in_class(ranges, c) != *negated && rest(self, pos + 1),
+                None => false,
+            },
+            Node::Start => pos == 0 && rest(self, pos),
+            Node::End => pos == self.text.len() && rest(self, pos),
+            Node::Group(alternatives) => alternatives
+                .iter()
+                .any(|seq| self.sequence(seq, pos, rest)),
+            Node::Repeat { .. } => unreachable!("repeats are handled by sequence()"),
+        }
+    }
+
+    // Greedy: tries one more repetition before handing over to `tail`. A
+    // repetition that consumed nothing isn't repeated again, so "(a*)*"
+    // terminates.
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &mut self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        count: usize,
+        pos: usize,
+        tail: &[Node],
+        rest: &mut dyn FnMut(&mut Self, usize) -> bool,
+    ) -> bool {
+        if !self.step() {
+            return false;
+        }
+        let room = match max {
+            Some(max) => count < max,
+            None => true,
+        };
+        if room {
+            let more = self.single(node, pos, &mut |m, end| {
+                (end != pos || count < min) && m.repeat(node, min, max, count + 1, end, tail, rest)
+            });
+            if more {
+                return true;
+            }
+        }
+        count >= min && self.sequence(tail, pos, rest)
+    }
+}
+
+// Classes compare both cases, since only the text was lowercased
+fn in_class(ranges: &[(char, char)], c: char) -> bool {
+    let upper = c.to_uppercase().next().unwrap_or(c);
+    ranges
+        .iter()
+        .any(|&(low, high)| (low..=high).contains(&c) || (low..=high).contains(&upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn anchors() {
+        assert!(matches("^ssh", "sshd 22"));
+        assert!(!matches("^ssh", "openssh"));
+        assert!(matches(":443$", "10.0.0.1:443"));
+        assert!(!matches(":443$", "10.0.0.1:4430"));
+        assert!(matches("^$", ""));
+        assert!(matches("nginx", "/usr/sbin/nginx -g"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches("^[a-c]+$", "cab"));
+        assert!(!matches("^[a-c]+$", "cad"));
+        assert!(matches("^[^0-9]+$", "nginx"));
+        assert!(!matches("^[^0-9]+$", "nginx2"));
+        assert!(matches(r"^\d{1,3}(\.\d{1,3}){3}$", "192.168.1.10"));
+        assert!(!matches(r"^\d{1,3}(\.\d{1,3}){3}$", "192.168.1"));
+        assert!(matches(r"\w+\s\w+", "foo bar"));
+        assert!(matches(r"^\S+$", "foo"));
+        assert!(!matches(r"^\S+$", "foo bar"));
+        assert!(matches("[]x]", "]"));
+        assert!(matches("^.$", "x"));
+        // Case is ignored on both sides
+        assert!(matches("[A-Z]+", "sshd"));
+        assert!(matches("SSHD", "sshd"));
+    }
+
+    #[test]
+    fn alternation() {
+        assert!(matches("^(ssh|http)d$", "sshd"));
+        assert!(matches("^(ssh|http)d$", "httpd"));
+        assert!(!matches("^(ssh|http)d$", "ftpd"));
+        assert!(matches("^a|b$", "axx"));
+        assert!(matches("^a|b$", "xxb"));
+        assert!(matches("(?:udp|tcp)6", "tcp6"));
+    }
+
+    #[test]
+    fn repetition() {
+        assert!(matches("^ab*c$", "ac"));
+        assert!(matches("^ab+c$", "abbc"));
+        assert!(!matches("^ab+c$", "ac"));
+        assert!(matches("^colou?r$", "color"));
+        assert!(matches("^a{2}$", "aa"));
+        assert!(!matches("^a{2}$", "aaa"));
+        assert!(matches("^a{2,}$", "aaaa"));
+        assert!(!matches("^a{2,3}$", "aaaa"));
+        assert!(matches("^(ab)+$", "ababab"));
+        assert!(matches("^(a*)*$", "aaa"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        for pattern in ["(ab", "ab)", "*a", "a**", "[ab", "a{3,1}", "a{x}", "[z-a]", "\\", r"[\D]"] {
+            assert!(Regex::new(pattern).is_err(), "{:?} should not compile", pattern);
+        }
+    }
+
+    #[test]
+    fn pathological_patterns_give_up() {
+        let text = "a".repeat(40);
+        let started = std::time::Instant::now();
+        assert!(!matches("(a*)*b", &text));
+        assert!(!matches("(a|a)*b", &text));
+        assert!(!matches("^(a+)+$", &format!("{}!", text)));
+        assert!(!matches("(a?){30}a{30}b", &text));
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
+}
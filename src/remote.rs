@@ -0,0 +1,150 @@
+use crate::network::{Connection, ProtocolSet};
+use crate::ss;
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Other machines read with --ssh. Each host runs `ss` over ssh on its own
+// thread, so a slow or unreachable host never holds up the local refresh;
+// the table shows whatever each host returned last.
+pub struct RemoteHosts {
+    hosts: Vec<RemoteHost>,
+}
+
+struct RemoteHost {
+    destination: String,
+    latest: Arc<Mutex<Result<Vec<Connection>, String>>>,
+    // Capacity 1: a refresh requested while a fetch is running is folded
+    // into the next one
+    poke: SyncSender<ProtocolSet>,
+}
+
+impl RemoteHosts {
+    pub fn start(destinations: &[String]) -> Self {
+        let hosts = destinations
+            .iter()
+            .map(|destination| {
+                let latest = Arc::new(Mutex::new(Ok(Vec::new())));
+                let (poke, requests) = mpsc::sync_channel(1);
+                let (thread_destination, thread_latest) = (destination.clone(), latest.clone());
+                thread::spawn(move || poll(&thread_destination, requests, &thread_latest));
+                RemoteHost { destination: destination.clone(), latest, poke }
+            })
+            .collect();
+        Self { hosts }
+    }
+
+    // Asks every host for a fresh listing; results arrive in the background
+    pub fn refresh(&self, protocols: ProtocolSet) {
+        for host in &self.hosts {
+            if let Err(TrySendError::Disconnected(_)) = host.poke.try_send(protocols) {
+                debug!("ssh poller for {} has exited", host.destination);
+            }
+        }
+    }
+
+    // The last successful listing of every host that is currently reachable
+    pub fn connections(&self) -> Vec<Connection> {
+        let mut connections = Vec::new();
+        for host in &self.hosts {
+            if let Ok(listing) = &*host.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+                connections.extend(listing.iter().cloned());
+            }
+        }
+        connections
+    }
+
+    // (destination, error) for each host whose last fetch failed
+    pub fn errors(&self) -> Vec<(String, String)> {
+        self.hosts
+            .iter()
+            .filter_map(|host| match &*host.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+                Err(err) => Some((host.destination.clone(), err.clone())),
+                Ok(_) => None,
+            })
+            .collect()
+    }
+}
+
+fn poll(destination: &str, requests: Receiver<ProtocolSet>, latest: &Mutex<Result<Vec<Connection>, String>>) {
+    // Ends when RemoteHosts is dropped
+    while let Ok(protocols) = requests.recv() {
+        let result = fetch(destination, protocols).map_err(|err| format!("{:#}", err));
+        if let Err(err) = &result {
+            debug!("ssh {}: {}", destination, err);
+        }
+        *latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = result;
+    }
+}
+
+// One listing from `destination` (anything ssh accepts, e.g. "user@host"
+// or a Host alias). BatchMode makes a host that wants a password fail rather
+// than prompt on the TUI's terminal.
+fn fetch(destination: &str, protocols: ProtocolSet) -> Result<Vec<Connection>> {
+    let output = Command::new("ssh")
+        .args(ssh_args(destination))
+        .output()
+        .context("cannot run ssh")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+        bail!("ssh exited with {}: {}", output.status, reason.trim());
+    }
+
+    let mut connections = ss::parse_output(&String::from_utf8_lossy(&output.stdout), protocols);
+    for conn in &mut connections {
+        conn.host = Some(destination.to_string());
+        // Inodes only mean something on the host itself, and would otherwise
+        // match local sock_diag data
+        conn.inode = 0;
+    }
+    Ok(connections)
+}
+
+// The "--" keeps a destination like "-oProxyCommand=..." from being read
+// as an ssh option
+fn ssh_args(destination: &str) -> Vec<&str> {
+    let mut args = vec!["-o", "BatchMode=yes", "-o", "ConnectTimeout=5", "--", destination, "ss"];
+    args.extend(ss::ARGS.iter().copied());
+    args
+}
+
+// For --once and --summary: every host fetched in parallel, with failures
+// reported as (destination, error) alongside what did arrive
+pub fn fetch_all(destinations: &[String], protocols: ProtocolSet) -> (Vec<Connection>, Vec<(String, String)>) {
+    let handles: Vec<_> = destinations
+        .iter()
+        .map(|destination| {
+            let destination = destination.clone();
+            thread::spawn(move || fetch(&destination, protocols))
+        })
+        .collect();
+
+    let mut connections = Vec::new();
+    let mut errors = Vec::new();
+    for (destination, handle) in destinations.iter().zip(handles) {
+        match handle.join() {
+            Ok(Ok(listing)) => connections.extend(listing),
+            Ok(Err(err)) => errors.push((destination.clone(), format!("{:#}", err))),
+            Err(_) => errors.push((destination.clone(), "ssh thread panicked".to_string())),
+        }
+    }
+    (connections, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destination_is_never_an_option() {
+        let args = ssh_args("-oProxyCommand=touch /tmp/pwned");
+        let separator = args.iter().position(|&arg| arg == "--").unwrap();
+        assert_eq!(args[separator + 1], "-oProxyCommand=touch /tmp/pwned");
+        assert_eq!(args[separator + 2], "ss");
+        assert!(args[..separator].iter().all(|arg| !arg.contains("ProxyCommand")));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::network::Connection;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+// Plain netstat-style table for `--once`
+pub fn print_plain(connections: &[Connection]) {
+    // A closed pipe (`| head`) just ends the output
+    let _ = write_plain(&mut io::stdout().lock(), connections);
+}
+
+// The same table for `--output-on-exit`, which may go to a file
+// A Host column leads when any row came from --ssh
+pub fn write_plain<'a>(out: &mut dyn Write, connections: impl IntoIterator<Item = &'a Connection>) -> io::Result<()> {
+    let connections: Vec<&Connection> = connections.into_iter().collect();
+    let host_width = connections.iter().filter_map(|conn| conn.host.as_deref()).map(str::len).max();
+    let host_width = host_width.map(|width| width.max("local".len()));
+
+    if let Some(width) = host_width {
+        write!(out, "{:<width$} ", "Host", width = width)?;
+    }
+    writeln!(
+        out,
+        "{:<6} {:<45} {:<10} {:<45} {:<12} PID/Program",
+        "Proto", "Local Address", "Iface", "Foreign Address", "State"
+    )?;
+
+    for conn in connections {
+        if let Some(width) = host_width {
+            write!(out, "{:<width$} ", conn.host.as_deref().unwrap_or("local"), width = width)?;
+        }
+        writeln!(
+            out,
+            "{:<6} {:<45} {:<10} {:<45} {:<12} {}",
+            conn.protocol,
+            conn.local_display(),
+            conn.interface.as_deref().unwrap_or("-"),
+            conn.remote_display(),
+            conn.state_display(),
+            conn.pid_program()
+        )?;
+    }
+    Ok(())
+}
+
+// `ss -s`-style overview for `--summary`: one line per protocol (v4 and v6
+// together) with counts per state, e.g. "tcp: estab 30, listen 12"
+pub fn print_summary(connections: &[Connection]) {
+    let mut by_protocol: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+    for conn in connections {
+        let protocol = conn.protocol.trim_end_matches('6').to_lowercase();
+        *by_protocol.entry(protocol).or_default().entry(state_label(&conn.state)).or_insert(0) += 1;
+    }
+
+    println!("total: {}", connections.len());
+    for (protocol, states) in &by_protocol {
+        // Stateless protocols (UDP, raw) just get a count
+        if states.len() == 1 && states.contains_key("") {
+            println!("{}: {}", protocol, states[""]);
+            continue;
+        }
+
+        let counts: Vec<String> = states
+            .iter()
+            .map(|(state, count)| {
+                let state = if state.is_empty() { "unconn" } else { state };
+                format!("{} {}", state, count)
+            })
+            .collect();
+        println!("{}: {}", protocol, counts.join(", "));
+    }
+}
+
+fn state_label(state: &str) -> String {
+    match state {
+        "ESTABLISHED" => "estab".to_string(),
+        other => other.to_lowercase().replace('_', "-"),
+    }
+}
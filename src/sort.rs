@@ -0,0 +1,167 @@
+use crate::network::Connection;
+use anyhow::{bail, Result};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Proto,
+    LocalAddr,
+    LocalPort,
+    RemoteAddr,
+    RemotePort,
+    State,
+    Pid,
+    Program,
+}
+
+const ALL_KEYS: &[SortKey] = &[
+    SortKey::Proto,
+    SortKey::LocalAddr,
+    SortKey::LocalPort,
+    SortKey::RemoteAddr,
+    SortKey::RemotePort,
+    SortKey::State,
+    SortKey::Pid,
+    SortKey::Program,
+];
+
+impl SortKey {
+    pub fn parse(name: &str) -> Result<Self> {
+        match ALL_KEYS.iter().find(|key| key.name() == name) {
+            Some(key) => Ok(*key),
+            None => {
+                let names: Vec<&str> = ALL_KEYS.iter().map(|key| key.name()).collect();
+                bail!("unknown sort key '{}' (expected one of: {})", name, names.join(", "))
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortKey::Proto => "proto",
+            SortKey::LocalAddr => "laddr",
+            SortKey::LocalPort => "lport",
+            SortKey::RemoteAddr => "raddr",
+            SortKey::RemotePort => "rport",
+            SortKey::State => "state",
+            SortKey::Pid => "pid",
+            SortKey::Program => "program",
+        }
+    }
+
+    // Cycles through the keys for the interactive 's' binding; `None` means
+    // the kernel's own ordering.
+    pub fn cycle(current: Option<SortKey>) -> Option<SortKey> {
+        match current {
+            None => Some(ALL_KEYS[0]),
+            Some(key) => {
+                let i = ALL_KEYS.iter().position(|k| *k == key).unwrap_or(0);
+                ALL_KEYS.get(i + 1).copied()
+            }
+        }
+    }
+
+    pub fn compare(&self, a: &Connection, b: &Connection) -> Ordering {
+        match self {
+            SortKey::Proto => a.protocol.cmp(&b.protocol),
+            SortKey::LocalAddr => compare_endpoints(
+                (&a.local_address, a.local_port),
+                (&b.local_address, b.local_port),
+            ),
+            SortKey::LocalPort => a.local_port.cmp(&b.local_port),
+            SortKey::RemoteAddr => compare_endpoints(
+                (&a.remote_address, a.remote_port),
+                (&b.remote_address, b.remote_port),
+            ),
+            SortKey::RemotePort => a.remote_port.cmp(&b.remote_port),
+            SortKey::State => a.state.cmp(&b.state),
+            // Unattributed sockets sort after everything else
+            SortKey::Pid => match (a.pid, b.pid) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            SortKey::Program => a.process_name.cmp(&b.process_name),
+        }
+    }
+}
+
+// Orders numerically so 10.0.0.9 comes before 10.0.0.10, then by port.
+// Anything that isn't an IP (UNIX socket paths) sorts after the addresses,
+// by plain text.
+fn compare_endpoints(a: (&str, u16), b: (&str, u16)) -> Ordering {
+    let address = match (a.0.parse::<IpAddr>(), b.0.parse::<IpAddr>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.0.cmp(b.0),
+    };
+    address.then(a.1.cmp(&b.1))
+}
+
+pub fn sort_connections<C: Borrow<Connection>>(connections: &mut [C], key: SortKey, descending: bool) {
+    connections.sort_by(|a, b| {
+        let ordering = key.compare(a.borrow(), b.borrow());
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(address: &str, port: u16) -> Connection {
+        Connection {
+            remote_address: address.to_string(),
+            remote_port: port,
+            ..Default::default()
+        }
+    }
+
+    fn sorted(mut connections: Vec<Connection>, descending: bool) -> Vec<String> {
+        sort_connections(&mut connections, SortKey::RemoteAddr, descending);
+        connections
+            .iter()
+            .map(|c| format!("{}:{}", c.remote_address, c.remote_port))
+            .collect()
+    }
+
+    #[test]
+    fn addresses_sort_numerically_then_by_port() {
+        let connections = vec![
+            remote("10.0.0.10", 22),
+            remote("10.0.0.9", 443),
+            remote("10.0.0.9", 80),
+            remote("9.9.9.9", 53),
+        ];
+        assert_eq!(
+            sorted(connections, false),
+            ["9.9.9.9:53", "10.0.0.9:80", "10.0.0.9:443", "10.0.0.10:22"]
+        );
+    }
+
+    #[test]
+    fn non_ip_addresses_sort_last() {
+        let connections = vec![
+            remote("/run/b.sock", 0),
+            remote("::1", 8080),
+            remote("/run/a.sock", 0),
+            remote("127.0.0.1", 8080),
+        ];
+        assert_eq!(
+            sorted(connections.clone(), false),
+            ["127.0.0.1:8080", "::1:8080", "/run/a.sock:0", "/run/b.sock:0"]
+        );
+        assert_eq!(
+            sorted(connections, true),
+            ["/run/b.sock:0", "/run/a.sock:0", "::1:8080", "127.0.0.1:8080"]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use crate::network::{escape_control, Connection, ConnectionSource, ProtocolSet, Source, TimerKind};
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::process::Command;
+
+// Both TCP and UDP are always requested so the Netid column is present;
+// unselected protocols are dropped afterwards
+pub const ARGS: &[&str] = &["-H", "-t", "-u", "-a", "-n", "-p", "-e", "-o"];
+
+// Fallback for kernels whose /proc/net format we can't parse: the same
+// sockets as reported by running `ss` here
+#[derive(Default)]
+pub struct SsCommandSource {
+    skipped: usize,
+}
+
+impl ConnectionSource for SsCommandSource {
+    fn connections(&mut self, protocols: ProtocolSet) -> Result<Vec<Connection>> {
+        let output = Command::new("ss").args(ARGS).output().context("cannot run ss")?;
+        if !output.status.success() {
+            bail!("ss exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        let (connections, skipped) = parse_lines(&String::from_utf8_lossy(&output.stdout), protocols);
+        self.skipped = skipped;
+        Ok(connections)
+    }
+
+    fn kind(&self) -> Source {
+        Source::Ss
+    }
+
+    fn attributes_owners(&self) -> bool {
+        true
+    }
+
+    fn skipped_lines(&self) -> usize {
+        self.skipped
+    }
+}
+
+// Output of `ss` run with ARGS, here or on a --ssh host
+pub fn parse_output(text: &str, protocols: ProtocolSet) -> Vec<Connection> {
+    parse_lines(text, protocols).0
+}
+
+// The connections, and how many lines couldn't be parsed
+fn parse_lines(text: &str, protocols: ProtocolSet) -> (Vec<Connection>, usize) {
+    let mut skipped = 0;
+    let connections: Vec<Connection> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let conn = parse_line(line);
+            skipped += conn.is_none() as usize;
+            conn
+        })
+        .filter(|conn| selected(protocols, &conn.protocol))
+        .collect();
+
+    if skipped > 0 {
+        debug!("skipped {} unparseable ss lines", skipped);
+    }
+    (connections, skipped)
+}
+
+fn selected(protocols: ProtocolSet, protocol: &str) -> bool {
+    match protocol {
+        "TCP" => protocols.tcp,
+        "TCP6" => protocols.tcp6,
+        "UDP" => protocols.udp,
+        "UDP6" => protocols.udp6,
+        _ => false,
+    }
+}
+
+// One line of `ss -H -tuanpeo`:
+//   tcp ESTAB 0 0 127.0.0.1:40370 127.0.0.1:48271 users:(("curl",pid=151,fd=15))
+//       timer:(keepalive,36sec,0) uid:1000 ino:1013 sk:1 cgroup:/ <->
+// Names match what the /proc parser produces, so both sources render alike.
+fn parse_line(line: &str) -> Option<Connection> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 6 {
+        return None;
+    }
+
+    let netid = match fields[0] {
+        "tcp" => "TCP",
+        "udp" => "UDP",
+        _ => return None,
+    };
+    let (local_address, local_port, v6) = parse_endpoint(fields[4], None)?;
+    let (remote_address, remote_port, _) = parse_endpoint(fields[5], Some(v6))?;
+
+    let mut conn = Connection {
+        protocol: if v6 { format!("{}6", netid) } else { netid.to_string() },
+        local_address,
+        local_port,
+        remote_address,
+        remote_port,
+        // Like /proc, UDP sockets carry no state even when connected
+        state: if netid == "TCP" { state_name(fields[1]).to_string() } else { String::new() },
+        recv_queue: fields[2].parse().unwrap_or(0),
+        // For listeners ss puts the backlog here, which /proc doesn't report
+        send_queue: if fields[1] == "LISTEN" { 0 } else { fields[3].parse().unwrap_or(0) },
+        // ss leaves out uid:0, and TIME-WAIT sockets have no owner at all
+        uid: Some(0).filter(|_| fields[1] != "TIME-WAIT"),
+        ..Default::default()
+    };
+
+    for field in &fields[6..] {
+        if let Some(users) = field.strip_prefix("users:((") {
+            // Only the first owner is kept, as with the fd scan
+            let mut parts = users.split(',');
+            conn.process_name = parts.next().map(|name| escape_control(name.trim_matches('"')));
+            conn.pid = parts.find_map(|part| part.strip_prefix("pid=")).and_then(|pid| pid.parse().ok());
+        } else if let Some(timer) = field.strip_prefix("timer:(").and_then(|timer| timer.strip_suffix(')')) {
+            let parts: Vec<&str> = timer.split(',').collect();
+            if parts.len() == 3 {
+                conn.timer = match parts[0] {
+                    "on" => TimerKind::Retransmit,
+                    "keepalive" => TimerKind::Keepalive,
+                    "timewait" => TimerKind::TimeWait,
+                    "persist" => TimerKind::ZeroWindowProbe,
+                    _ => TimerKind::None,
+                };
+                conn.timer_expires_secs = parse_interval(parts[1]);
+                conn.retransmits = parts[2].parse().unwrap_or(0);
+            }
+        } else if let Some(uid) = field.strip_prefix("uid:") {
+            conn.uid = uid.parse().ok();
+        } else if let Some(inode) = field.strip_prefix("ino:") {
+            conn.inode = inode.parse().unwrap_or(0);
+        } else if let Some(cgroup) = field.strip_prefix("cgroup:") {
+            conn.cgroup = Some(cgroup.to_string()).filter(|cgroup| cgroup != "/");
+        }
+    }
+
+    Some(conn)
+}
+
+// "127.0.0.1:80", "127.0.0.53%lo:53", "[::1]:631", "*:22" or "0.0.0.0:*".
+// Returns the address in the form Ipv4Addr/Ipv6Addr print it and whether it
+// is IPv6; a bare '*' takes the family of `family`, or IPv6 for a local
+// wildcard since ss only prints it for dual-stack sockets.
+fn parse_endpoint(endpoint: &str, family: Option<bool>) -> Option<(String, u16, bool)> {
+    let (address, port) = endpoint.rsplit_once(':')?;
+    let port = if port == "*" { 0 } else { port.parse().ok()? };
+
+    let address = address.split('%').next()?;
+    let (address, v6) = match address {
+        "*" => {
+            let v6 = family.unwrap_or(true);
+            (if v6 { "::" } else { "0.0.0.0" }.to_string(), v6)
+        }
+        _ => match address.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            Some(v6) => (v6.split('%').next()?.to_string(), true),
+            None => (address.to_string(), false),
+        },
+    };
+
+    Some((address, port, v6))
+}
+
+fn state_name(state: &str) -> &str {
+    match state {
+        "ESTAB" => "ESTABLISHED",
+        "SYN-SENT" => "SYN_SENT",
+        "SYN-RECV" => "SYN_RECV",
+        "FIN-WAIT-1" => "FIN_WAIT1",
+        "FIN-WAIT-2" => "FIN_WAIT2",
+        "TIME-WAIT" => "TIME_WAIT",
+        "UNCONN" => "CLOSE",
+        "CLOSE-WAIT" => "CLOSE_WAIT",
+        "LAST-ACK" => "LAST_ACK",
+        "LISTEN" => "LISTEN",
+        "CLOSING" => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// ss prints timer expiries as runs like "206ms", "36sec" or "1min7sec"
+fn parse_interval(text: &str) -> f64 {
+    let mut total = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let unit_len = rest[number_len..].find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len() - number_len);
+        let number: f64 = rest[..number_len].parse().unwrap_or(0.0);
+        let scale = match &rest[number_len..number_len + unit_len] {
+            "ms" => 0.001,
+            "sec" => 1.0,
+            "min" => 60.0,
+            "h" => 3600.0,
+            _ => 0.0,
+        };
+        total += number * scale;
+        rest = &rest[number_len + unit_len..];
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Captured from `ss -H -tuanpeo` (trailing padding trimmed)
+    const CAPTURED: &str = r#"udp UNCONN   0      0      127.0.0.53:53          0.0.0.0:*     users:(("python3",pid=13296,fd=8)) ino:168525 sk:a53 cgroup:/ <->
+udp UNCONN   0      0               *:5353              *:*     users:(("python3",pid=13296,fd=7)) ino:168524 sk:a54 cgroup:/ v6only:0 <->
+tcp LISTEN   0      5       127.0.0.1:48271       0.0.0.0:*     users:(("python3",pid=114,fd=3)) uid:65534 ino:853 sk:5 cgroup:/ <->
+tcp LISTEN   0      128       0.0.0.0:2024        0.0.0.0:*     ino:662 sk:6 cgroup:/ <->
+tcp ESTAB    0      0       127.0.0.1:38920     127.0.0.1:48271 users:(("claude",pid=26748,fd=20)) timer:(keepalive,33sec,0) ino:161927 sk:a51 cgroup:/ <->
+tcp LISTEN   0      16              *:8443              *:*     users:(("python3",pid=13296,fd=3)) ino:168520 sk:a55 cgroup:/ v6only:0 <->
+tcp LISTEN   0      4           [::1]:6379           [::]:*     users:(("python3",pid=13296,fd=4)) ino:168521 sk:a56 cgroup:/ v6only:1 <->
+tcp SYN-SENT 0      1       [fd00::2]:41884 [2001:db8::1]:443   users:(("python3",pid=13296,fd=9)) timer:(on,096ms,0) ino:168526 sk:a57 cgroup:/ <->
+tcp ESTAB    0      0           [::1]:42108         [::1]:6379  users:(("python3",pid=13296,fd=5)) ino:168522 sk:a58 cgroup:/ <->
+tcp LISTEN   0      511       0.0.0.0:80          0.0.0.0:*     users:(("nginx",pid=1402,fd=6),("nginx",pid=1401,fd=6)) ino:24310 sk:3 cgroup:/system.slice/nginx.service <->
+tcp TIME-WAIT 0     0       127.0.0.1:48271     127.0.0.1:38900 timer:(timewait,52sec,0) ino:0 sk:a5a"#;
+
+    fn captured() -> Vec<Connection> {
+        parse_output(CAPTURED, ProtocolSet::default())
+    }
+
+    fn find(port: u16) -> Connection {
+        captured().into_iter().find(|conn| conn.local_port == port).unwrap()
+    }
+
+    #[test]
+    fn every_captured_line_parses() {
+        assert_eq!(parse_lines(CAPTURED, ProtocolSet::default()).1, 0);
+        assert_eq!(captured().len(), 11);
+    }
+
+    #[test]
+    fn owner_from_users_field() {
+        let conn = find(48271);
+        assert_eq!(conn.protocol, "TCP");
+        assert_eq!(conn.local_address, "127.0.0.1");
+        assert_eq!((conn.remote_address.as_str(), conn.remote_port), ("0.0.0.0", 0));
+        assert_eq!(conn.state, "LISTEN");
+        assert_eq!(conn.pid, Some(114));
+        assert_eq!(conn.process_name.as_deref(), Some("python3"));
+        assert_eq!(conn.uid, Some(65534));
+        assert_eq!(conn.inode, 853);
+        assert_eq!(conn.cgroup, None);
+        // The backlog ss prints for listeners isn't a send queue
+        assert_eq!(conn.send_queue, 0);
+
+        // Shared listeners keep the first owner ss lists
+        let nginx = find(80);
+        assert_eq!((nginx.pid, nginx.process_name.as_deref()), (Some(1402), Some("nginx")));
+        assert_eq!(nginx.cgroup.as_deref(), Some("/system.slice/nginx.service"));
+
+        // uid:0 is left out by ss; no users field means no owner we can see
+        let unowned = find(2024);
+        assert_eq!((unowned.pid, unowned.uid), (None, Some(0)));
+    }
+
+    #[test]
+    fn ipv6_brackets_and_wildcards() {
+        let redis = find(6379);
+        assert_eq!(redis.protocol, "TCP6");
+        assert_eq!(redis.local_address, "::1");
+        assert_eq!((redis.remote_address.as_str(), redis.remote_port), ("::", 0));
+
+        let client = find(41884);
+        assert_eq!(client.protocol, "TCP6");
+        assert_eq!(client.local_address, "fd00::2");
+        assert_eq!((client.remote_address.as_str(), client.remote_port), ("2001:db8::1", 443));
+        assert_eq!(client.state, "SYN_SENT");
+        assert_eq!(client.send_queue, 1);
+        assert_eq!(client.timer, TimerKind::Retransmit);
+        assert!((client.timer_expires_secs - 0.096).abs() < 1e-9);
+
+        // A bare '*' is a dual-stack socket
+        let dual = find(8443);
+        assert_eq!((dual.protocol.as_str(), dual.local_address.as_str()), ("TCP6", "::"));
+        assert_eq!((dual.remote_address.as_str(), dual.remote_port), ("::", 0));
+
+        let mdns = find(5353);
+        assert_eq!((mdns.protocol.as_str(), mdns.state.as_str()), ("UDP6", ""));
+    }
+
+    #[test]
+    fn timers_and_time_wait() {
+        let client = find(38920);
+        assert_eq!(client.state, "ESTABLISHED");
+        assert_eq!(client.timer, TimerKind::Keepalive);
+        assert_eq!(client.timer_expires_secs, 33.0);
+        assert_eq!(client.pid, Some(26748));
+
+        let closed = captured().into_iter().find(|conn| conn.state == "TIME_WAIT").unwrap();
+        assert_eq!(closed.remote_port, 38900);
+        assert_eq!(closed.timer, TimerKind::TimeWait);
+        assert_eq!(closed.uid, None);
+        assert_eq!(closed.inode, 0);
+    }
+
+    #[test]
+    fn unselected_protocols_and_junk_are_dropped() {
+        let protocols = ProtocolSet {
+            tcp: true,
+            tcp6: false,
+            udp: false,
+            udp6: false,
+        };
+        let connections = parse_output(CAPTURED, protocols);
+        assert!(connections.iter().all(|conn| conn.protocol == "TCP"));
+        assert_eq!(connections.len(), 5);
+
+        let (connections, skipped) = parse_lines("State Recv-Q\nu_str ESTAB 0 0 * 1 * 2\n\n", ProtocolSet::default());
+        assert!(connections.is_empty());
+        assert_eq!(skipped, 2);
+    }
+
+    #[test]
+    fn intervals() {
+        assert!((parse_interval("206ms") - 0.206).abs() < 1e-9);
+        assert_eq!(parse_interval("36sec"), 36.0);
+        assert_eq!(parse_interval("1min7sec"), 67.0);
+        assert_eq!(parse_interval("2h"), 7200.0);
+        assert_eq!(parse_interval(""), 0.0);
+    }
+}